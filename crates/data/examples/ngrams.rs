@@ -0,0 +1,123 @@
+//! Build the syllable frequency table (`src/dictionaries/vi_ngrams.txt`)
+//!
+//! ```text
+//! cargo run -p data --example ngrams -- [--min-pair N] [--weight W] FILE... > vi_ngrams.txt
+//! ```
+//!
+//! FILE is a gettext catalog (`.mo`, its translations are counted) or UTF-8
+//! text (`#` lines skipped). `--weight` applies to the files after it, so a
+//! small general text can balance the technical wording of catalogs.
+//! Pairs seen fewer than `--min-pair` times (default 3) are dropped.
+//!
+//! The bundled table comes from the Vietnamese catalogs of a Debian system
+//! and the Vim tutor, with the everyday text weighted up:
+//!
+//! ```text
+//! cargo run -p data --example ngrams -- \
+//!     /usr/share/locale/vi/LC_MESSAGES/*.mo \
+//!     /usr/share/vim/vim90/lang/vi/LC_MESSAGES/vim.mo \
+//!     /usr/share/vim/vim90/tutor/tutor.vi.utf-8 \
+//!     --weight 20 crates/data/corpus/vi_everyday.txt \
+//!     > crates/data/src/dictionaries/vi_ngrams.txt
+//! ```
+
+use data::ngram::Ngrams;
+use std::process::ExitCode;
+
+/// Translated strings of a GNU `.mo` catalog, without the header entry
+fn mo_strings(bytes: &[u8]) -> Option<Vec<String>> {
+    let word = |at: usize, big: bool| -> Option<usize> {
+        let b: [u8; 4] = bytes.get(at..at + 4)?.try_into().ok()?;
+        Some(if big {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        } as usize)
+    };
+    let big = match word(0, false)? {
+        0x950412de => false,
+        0xde120495 => true,
+        _ => return None,
+    };
+    let count = word(8, big)?;
+    let originals = word(12, big)?;
+    let translations = word(16, big)?;
+    let mut strings = Vec::new();
+    for i in 0..count {
+        let original_len = word(originals + i * 8, big)?;
+        if original_len == 0 {
+            continue;
+        }
+        let len = word(translations + i * 8, big)?;
+        let at = word(translations + i * 8 + 4, big)?;
+        let text = std::str::from_utf8(bytes.get(at..at + len)?).ok()?;
+        // Plural forms are NUL-separated
+        strings.extend(text.split('\0').map(str::to_string));
+    }
+    Some(strings)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut ngrams = Ngrams::new();
+    let mut min_pair = 3;
+    let mut weight = 1;
+    let mut sources = Vec::new();
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        let value = |v: Option<&String>| v.and_then(|v| v.parse::<u32>().ok());
+        match arg.as_str() {
+            "--min-pair" => match value(args_iter.next()) {
+                Some(n) => min_pair = n,
+                None => return usage(),
+            },
+            "--weight" => match value(args_iter.next()) {
+                Some(n) => weight = n,
+                None => return usage(),
+            },
+            path => {
+                let bytes = match std::fs::read(path) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        eprintln!("{}: {}", path, e);
+                        return ExitCode::FAILURE;
+                    }
+                };
+                if path.ends_with(".mo") {
+                    let Some(strings) = mo_strings(&bytes) else {
+                        eprintln!("{}: not a gettext catalog", path);
+                        return ExitCode::FAILURE;
+                    };
+                    for text in strings {
+                        ngrams.add_text(&text, weight);
+                    }
+                } else {
+                    let text = String::from_utf8_lossy(&bytes);
+                    for line in text.lines().filter(|l| !l.starts_with('#')) {
+                        ngrams.add_text(line, weight);
+                    }
+                }
+                sources.push(format!("{} (weight {})", path, weight));
+            }
+        }
+    }
+    if sources.is_empty() {
+        return usage();
+    }
+
+    println!("# Vietnamese syllable frequencies: syllable count next:count ...");
+    println!(
+        "# Generated by crates/data/examples/ngrams.rs, pairs under {} dropped, from:",
+        min_pair
+    );
+    for source in &sources {
+        println!("#   {}", source);
+    }
+    print!("{}", ngrams.to_text(min_pair));
+    ExitCode::SUCCESS
+}
+
+fn usage() -> ExitCode {
+    eprintln!("usage: ngrams [--min-pair N] [--weight W] FILE...");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,290 @@
+//! Case Conversion
+//!
+//! UPPER, lower, Sentence, Title and tOGGLE case for Vietnamese text.
+//! Vietnamese letters go through `chars::parse_char`/`to_char`, so tone,
+//! mark and stroke are kept exactly (đ ↔ Đ, ữ ↔ Ữ); other characters use
+//! Unicode case mapping. Decomposed (NFD) text works too: combining marks
+//! have no case and pass through.
+
+use crate::chars::{get_d, parse_char, to_char};
+
+/// Case conversion mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Case {
+    /// TIẾNG VIỆT
+    #[default]
+    Upper,
+    /// tiếng việt
+    Lower,
+    /// Tiếng việt. Câu mới
+    Sentence,
+    /// Tiếng Việt Và Bạn
+    Title,
+    /// Tiếng Việt và Bạn: particles stay lowercase except at sentence start
+    TitleKeepParticles,
+    /// tIẾNG vIỆT
+    Toggle,
+}
+
+impl Case {
+    /// Mode from its FFI id (0-5 in declaration order)
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Case::Upper),
+            1 => Some(Case::Lower),
+            2 => Some(Case::Sentence),
+            3 => Some(Case::Title),
+            4 => Some(Case::TitleKeepParticles),
+            5 => Some(Case::Toggle),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Function words left lowercase by `Case::TitleKeepParticles`
+/// (Vietnamese, then English)
+pub const PARTICLES: &[&str] = &[
+    "và", "với", "của", "các", "những", "là", "thì", "mà", "cho", "trong", "ngoài", "trên", "dưới",
+    "ở", "tại", "để", "như", "nhưng", "hay", "hoặc", "về", "từ", "đến", "tới", "bởi", "vì", "do",
+    "nếu", "khi", "a", "an", "the", "and", "or", "of", "in", "on", "at", "to", "for", "by", "with",
+];
+
+/// Vietnamese letter in the given case, None for other characters
+///
+/// `to_char` covers vowels and d; other letters fall through to Unicode.
+fn vietnamese_case(c: char, caps: bool) -> Option<char> {
+    let p = parse_char(c)?;
+    if p.stroke {
+        return Some(get_d(caps));
+    }
+    to_char(p.key, caps, p.tone, p.mark)
+}
+
+/// Uppercase one character (multi-char mappings like ß → SS are skipped)
+pub fn upper_char(c: char) -> char {
+    vietnamese_case(c, true)
+        .or_else(|| single(c.to_uppercase()))
+        .unwrap_or(c)
+}
+
+/// Lowercase one character
+pub fn lower_char(c: char) -> char {
+    vietnamese_case(c, false)
+        .or_else(|| single(c.to_lowercase()))
+        .unwrap_or(c)
+}
+
+/// The only char of a case mapping
+fn single(mut mapped: impl Iterator<Item = char>) -> Option<char> {
+    let c = mapped.next()?;
+    mapped.next().is_none().then_some(c)
+}
+
+pub fn to_upper(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match vietnamese_case(c, true) {
+            Some(v) => out.push(v),
+            None => out.extend(c.to_uppercase()),
+        }
+    }
+    out
+}
+
+pub fn to_lower(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match vietnamese_case(c, false) {
+            Some(v) => out.push(v),
+            None => out.extend(c.to_lowercase()),
+        }
+    }
+    out
+}
+
+/// Convert text to the given case
+pub fn convert_case(s: &str, case: Case) -> String {
+    match case {
+        Case::Upper => to_upper(s),
+        Case::Lower => to_lower(s),
+        Case::Toggle => s
+            .chars()
+            .map(|c| {
+                if c.is_uppercase() {
+                    lower_char(c)
+                } else {
+                    upper_char(c)
+                }
+            })
+            .collect(),
+        Case::Sentence => sentence_case(s),
+        Case::Title => title_case(s, false),
+        Case::TitleKeepParticles => title_case(s, true),
+    }
+}
+
+/// Capitalize text like the trigger it replaces (`CaseMode::MatchCase`)
+///
+/// "VN" → "VIỆT NAM", "Vn" → "Việt nam", "vn" → replacement unchanged.
+pub fn match_case(pattern: &str, text: &str) -> String {
+    if pattern.chars().all(|c| c.is_uppercase()) {
+        to_upper(text)
+    } else if pattern.chars().next().is_some_and(|c| c.is_uppercase()) {
+        let mut chars = text.chars();
+        match chars.next() {
+            Some(first) => upper_char(first).to_string() + chars.as_str(),
+            None => String::new(),
+        }
+    } else {
+        text.to_string()
+    }
+}
+
+/// Part of a word for title case (apostrophes between letters are too)
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || crate::chars::is_combining(c)
+}
+
+/// Ends a sentence: the next word starts with a capital
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '\n')
+}
+
+fn sentence_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut capitalize = true;
+    for c in s.chars() {
+        if c.is_alphanumeric() {
+            out.push(if capitalize {
+                upper_char(c)
+            } else {
+                lower_char(c)
+            });
+            capitalize = false;
+        } else {
+            out.push(c);
+            capitalize |= is_sentence_end(c);
+        }
+    }
+    out
+}
+
+fn title_case(s: &str, keep_particles: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut sentence_start = true;
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if !is_word_char(chars[i]) {
+            sentence_start |= is_sentence_end(chars[i]);
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len()
+            && (is_word_char(chars[i])
+                || (chars[i] == '\'' && chars.get(i + 1).is_some_and(|&c| is_word_char(c))))
+        {
+            i += 1;
+        }
+        let word = to_lower(&chars[start..i].iter().collect::<String>());
+        let particle = keep_particles && !sentence_start && PARTICLES.contains(&word.as_str());
+        let mut letters = word.chars();
+        if let (false, Some(first)) = (particle, letters.next()) {
+            out.push(upper_char(first));
+            out.push_str(letters.as_str());
+        } else {
+            out.push_str(&word);
+        }
+        sentence_start = false;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upper_lower() {
+        assert_eq!(to_upper("tiếng việt đường ữ"), "TIẾNG VIỆT ĐƯỜNG Ữ");
+        assert_eq!(to_lower("TIẾNG VIỆT ĐƯỜNG Ữ"), "tiếng việt đường ữ");
+        assert_eq!(to_upper("hello, 123"), "HELLO, 123");
+        assert_eq!(to_upper("straße"), "STRASSE");
+        // Single-char conversion skips multi-char mappings
+        assert_eq!(upper_char('ß'), 'ß');
+    }
+
+    #[test]
+    fn test_sentence_case() {
+        assert_eq!(
+            convert_case("xin CHÀO. tôi là AN! bạn khỏe?\nđi thôi", Case::Sentence),
+            "Xin chào. Tôi là an! Bạn khỏe?\nĐi thôi"
+        );
+    }
+
+    #[test]
+    fn test_title_case() {
+        assert_eq!(
+            convert_case("thành phố hồ chí minh và hà nội", Case::Title),
+            "Thành Phố Hồ Chí Minh Và Hà Nội"
+        );
+        assert_eq!(
+            convert_case("bà rịa-vũng tàu", Case::Title),
+            "Bà Rịa-Vũng Tàu"
+        );
+        assert_eq!(convert_case("don't STOP", Case::Title), "Don't Stop");
+    }
+
+    #[test]
+    fn test_title_keep_particles() {
+        assert_eq!(
+            convert_case(
+                "truyện kiều của nguyễn du và các bạn",
+                Case::TitleKeepParticles
+            ),
+            "Truyện Kiều của Nguyễn Du và các Bạn"
+        );
+        // Particles are capitalized at sentence start
+        assert_eq!(
+            convert_case("của tôi. và bạn", Case::TitleKeepParticles),
+            "Của Tôi. Và Bạn"
+        );
+    }
+
+    #[test]
+    fn test_toggle_case() {
+        assert_eq!(convert_case("Tiếng VIỆT", Case::Toggle), "tIẾNG việt");
+    }
+
+    #[test]
+    fn test_decomposed() {
+        assert_eq!(to_upper("vie\u{0302}\u{0323}t"), "VIE\u{0302}\u{0323}T");
+        assert_eq!(
+            convert_case("vie\u{0302}\u{0323}t nam", Case::Title),
+            "Vie\u{0302}\u{0323}t Nam"
+        );
+    }
+
+    #[test]
+    fn test_match_case() {
+        assert_eq!(match_case("VN", "Việt Nam"), "VIỆT NAM");
+        assert_eq!(match_case("Vn", "việt nam"), "Việt nam");
+        assert_eq!(match_case("vn", "Việt Nam"), "Việt Nam");
+        assert_eq!(match_case("Đg", "đường"), "Đường");
+        assert_eq!(match_case("Đ", "đường"), "ĐƯỜNG");
+    }
+
+    #[test]
+    fn test_ids() {
+        for id in 0..6 {
+            assert_eq!(Case::from_id(id).unwrap().id(), id);
+        }
+        assert_eq!(Case::from_id(6), None);
+    }
+}
@@ -402,6 +402,151 @@ pub fn parse_char(c: char) -> Option<ParsedChar> {
     }
 }
 
+// DECOMPOSED (NFD) TEXT: base letter + combining marks
+
+/// Combining diacritics used by decomposed Vietnamese text
+pub mod combining {
+    pub const GRAVE: char = '\u{0300}'; // huyền
+    pub const ACUTE: char = '\u{0301}'; // sắc
+    pub const CIRCUMFLEX: char = '\u{0302}'; // â, ê, ô
+    pub const TILDE: char = '\u{0303}'; // ngã
+    pub const BREVE: char = '\u{0306}'; // ă
+    pub const HOOK_ABOVE: char = '\u{0309}'; // hỏi
+    pub const HORN: char = '\u{031B}'; // ơ, ư
+    pub const DOT_BELOW: char = '\u{0323}'; // nặng
+}
+
+/// Check if character is a Vietnamese combining diacritic
+pub fn is_combining(c: char) -> bool {
+    matches!(
+        c,
+        combining::GRAVE
+            | combining::ACUTE
+            | combining::CIRCUMFLEX
+            | combining::TILDE
+            | combining::BREVE
+            | combining::HOOK_ABOVE
+            | combining::HORN
+            | combining::DOT_BELOW
+    )
+}
+
+/// Apply a combining diacritic to parsed character components
+///
+/// Returns None if the diacritic doesn't fit the letter (circumflex on u,
+/// breve on o, ...), so callers can keep the combining character as-is.
+pub fn apply_combining(parsed: ParsedChar, c: char) -> Option<ParsedChar> {
+    let mut result = parsed;
+    let is_vowel = keys::is_vowel(parsed.key);
+    match c {
+        combining::ACUTE if is_vowel => result.mark = mark::SAC,
+        combining::GRAVE if is_vowel => result.mark = mark::HUYEN,
+        combining::HOOK_ABOVE if is_vowel => result.mark = mark::HOI,
+        combining::TILDE if is_vowel => result.mark = mark::NGA,
+        combining::DOT_BELOW if is_vowel => result.mark = mark::NANG,
+        combining::CIRCUMFLEX if matches!(parsed.key, keys::A | keys::E | keys::O) => {
+            result.tone = tone::CIRCUMFLEX
+        }
+        combining::BREVE if parsed.key == keys::A => result.tone = tone::HORN,
+        combining::HORN if matches!(parsed.key, keys::O | keys::U) => result.tone = tone::HORN,
+        _ => return None,
+    }
+    Some(result)
+}
+
+/// Decompose a Vietnamese character into base letter + combining marks (NFD)
+///
+/// Marks are in canonical order: horn, dot below, then the rest
+/// ("ợ" → o + U+031B + U+0323, "ấ" → a + U+0302 + U+0301).
+/// đ has no decomposition; other characters are returned unchanged.
+pub fn decompose(c: char) -> Vec<char> {
+    let Some(parsed) = parse_char(c) else {
+        return vec![c];
+    };
+    if parsed.stroke || (parsed.tone == tone::NONE && parsed.mark == mark::NONE) {
+        return vec![c];
+    }
+    let Some(base) = to_char(parsed.key, parsed.caps, tone::NONE, mark::NONE) else {
+        return vec![c];
+    };
+
+    let modifier = match (parsed.tone, parsed.key) {
+        (tone::CIRCUMFLEX, _) => Some(combining::CIRCUMFLEX),
+        (tone::HORN, keys::A) => Some(combining::BREVE),
+        (tone::HORN, _) => Some(combining::HORN),
+        _ => None,
+    };
+    let tone_mark = match parsed.mark {
+        mark::SAC => Some(combining::ACUTE),
+        mark::HUYEN => Some(combining::GRAVE),
+        mark::HOI => Some(combining::HOOK_ABOVE),
+        mark::NGA => Some(combining::TILDE),
+        mark::NANG => Some(combining::DOT_BELOW),
+        _ => None,
+    };
+
+    let mut out = vec![base];
+    match (modifier, tone_mark) {
+        // Dot below (class 220) sorts before circumflex/breve (230), after horn (216)
+        (Some(m), Some(combining::DOT_BELOW)) if m != combining::HORN => {
+            out.extend([combining::DOT_BELOW, m])
+        }
+        (m, t) => out.extend(m.into_iter().chain(t)),
+    }
+    out
+}
+
+/// Compose decomposed Vietnamese text into precomposed characters (NFC)
+///
+/// Combining marks that don't form a Vietnamese letter are kept as-is.
+pub fn compose(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut last: Option<ParsedChar> = None;
+    for c in s.chars() {
+        if is_combining(c) {
+            if let Some(applied) = last.and_then(|p| apply_combining(p, c)) {
+                if let Some(composed) =
+                    to_char(applied.key, applied.caps, applied.tone, applied.mark)
+                {
+                    out.pop();
+                    out.push(composed);
+                    last = Some(applied);
+                    continue;
+                }
+            }
+            out.push(c);
+            last = None;
+            continue;
+        }
+        out.push(c);
+        last = parse_char(c).filter(|p| !p.stroke);
+    }
+    out
+}
+
+/// Strip Vietnamese diacritics: "Tiếng Việt có dấu" → "Tieng Viet co dau"
+///
+/// Removes tones, marks and the stroke (đ → d, Đ → D). Accepts precomposed
+/// or decomposed text; other characters are kept unchanged.
+pub fn remove_diacritics(s: &str) -> String {
+    s.chars()
+        .filter(|&c| !is_combining(c))
+        .map(|c| match parse_char(c) {
+            Some(p) if p.stroke => {
+                if p.caps {
+                    'D'
+                } else {
+                    'd'
+                }
+            }
+            Some(p) if p.tone != tone::NONE || p.mark != mark::NONE => {
+                to_char(p.key, p.caps, tone::NONE, mark::NONE).unwrap_or(c)
+            }
+            _ => c,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -550,4 +695,55 @@ mod tests {
             assert_eq!((p.key, p.tone, p.mark), (key, t, m), "Failed for '{}'", ch);
         }
     }
+
+    // ===== Decomposed (NFD) tests =====
+
+    #[test]
+    fn test_decompose() {
+        assert_eq!(decompose('á'), vec!['a', combining::ACUTE]);
+        assert_eq!(
+            decompose('ấ'),
+            vec!['a', combining::CIRCUMFLEX, combining::ACUTE]
+        );
+        assert_eq!(
+            decompose('ậ'),
+            vec!['a', combining::DOT_BELOW, combining::CIRCUMFLEX]
+        );
+        assert_eq!(
+            decompose('ặ'),
+            vec!['a', combining::DOT_BELOW, combining::BREVE]
+        );
+        assert_eq!(
+            decompose('ợ'),
+            vec!['o', combining::HORN, combining::DOT_BELOW]
+        );
+        assert_eq!(decompose('Ừ'), vec!['U', combining::HORN, combining::GRAVE]);
+        assert_eq!(decompose('đ'), vec!['đ']);
+        assert_eq!(decompose('a'), vec!['a']);
+        assert_eq!(decompose('→'), vec!['→']);
+    }
+
+    #[test]
+    fn test_compose() {
+        let nfd: String = "Tiếng Việt đường".chars().flat_map(decompose).collect();
+        assert_ne!(nfd, "Tiếng Việt đường");
+        assert_eq!(compose(&nfd), "Tiếng Việt đường");
+        // Non-canonical order still composes
+        assert_eq!(compose("a\u{0301}\u{0302}"), "ấ");
+        // Marks that don't fit a Vietnamese letter are kept
+        assert_eq!(compose("n\u{0303}"), "n\u{0303}");
+        assert_eq!(compose("u\u{0302}"), "u\u{0302}");
+    }
+
+    #[test]
+    fn test_remove_diacritics() {
+        assert_eq!(remove_diacritics("Tiếng Việt có dấu"), "Tieng Viet co dau");
+        assert_eq!(remove_diacritics("ĐƯỜNG đi"), "DUONG di");
+        assert_eq!(remove_diacritics("Ăn Ước Ở"), "An Uoc O");
+        // Decomposed input
+        let nfd: String = "Hà Nội".chars().flat_map(decompose).collect();
+        assert_eq!(remove_diacritics(&nfd), "Ha Noi");
+        // Other text unchanged
+        assert_eq!(remove_diacritics("abc 123 ñ 東京"), "abc 123 ñ 東京");
+    }
 }
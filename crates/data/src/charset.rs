@@ -0,0 +1,763 @@
+//! Legacy Vietnamese Charsets
+//!
+//! Conversion between Unicode and the 8-bit encodings still used by
+//! government and printing workflows:
+//! - TCVN3 (ABC): one byte per letter, fonts like .VnTime
+//! - VNI-Windows: base letter + diacritic byte, fonts like VNI-Times
+//! - VISCII: one byte per letter (RFC 1456)
+//! - CP1258: Windows code page, precomposed or base + combining mark
+//!
+//! ASCII is identical in all of them. Only Vietnamese letters are mapped;
+//! other characters have no legacy encoding.
+//!
+//! VPS is not supported yet: it needs a byte table checked against a
+//! published reference, and none is available to verify one. It will take
+//! the next free id when added.
+//!
+//! Unicode output comes precomposed (NFC) or decomposed (NFD, see
+//! `chars::decompose`); neither uses the byte tables here.
+
+/// Output character set
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Charset {
+    /// Unicode precomposed (NFC) - default
+    #[default]
+    Unicode,
+    /// TCVN3 (ABC)
+    Tcvn3,
+    /// VNI-Windows
+    VniWindows,
+    /// VISCII
+    Viscii,
+    /// Windows-1258
+    Cp1258,
+    /// Unicode decomposed (NFD): base letter + combining marks
+    UnicodeNfd,
+}
+
+impl Charset {
+    /// Charset by FFI id: 0=Unicode, 1=TCVN3, 2=VNI-Windows, 3=VISCII, 4=CP1258,
+    /// 5=Unicode NFD
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Charset::Unicode),
+            1 => Some(Charset::Tcvn3),
+            2 => Some(Charset::VniWindows),
+            3 => Some(Charset::Viscii),
+            4 => Some(Charset::Cp1258),
+            5 => Some(Charset::UnicodeNfd),
+            _ => None,
+        }
+    }
+
+    /// FFI id of this charset
+    pub fn id(self) -> u8 {
+        match self {
+            Charset::Unicode => 0,
+            Charset::Tcvn3 => 1,
+            Charset::VniWindows => 2,
+            Charset::Viscii => 3,
+            Charset::Cp1258 => 4,
+            Charset::UnicodeNfd => 5,
+        }
+    }
+
+    /// Conversion table for Vietnamese letters (empty for Unicode forms)
+    pub fn table(self) -> &'static [(char, &'static [u8])] {
+        match self {
+            Charset::Unicode | Charset::UnicodeNfd => &[],
+            Charset::Tcvn3 => TCVN3,
+            Charset::VniWindows => VNI_WINDOWS,
+            Charset::Viscii => VISCII,
+            Charset::Cp1258 => CP1258,
+        }
+    }
+}
+
+/// Encode one character
+///
+/// Returns the legacy byte sequence (1-2 bytes), or None if the character
+/// has no encoding in this charset. Unicode forms return None for non-ASCII.
+pub fn encode_char(c: char, charset: Charset) -> Option<&'static [u8]> {
+    if c.is_ascii() {
+        return ASCII.get(c as usize).map(std::slice::from_ref);
+    }
+    charset
+        .table()
+        .iter()
+        .find(|(ch, _)| *ch == c)
+        .map(|&(_, bytes)| bytes)
+}
+
+/// Encode a string, replacing unmappable characters with '?'
+pub fn encode(s: &str, charset: Charset) -> Vec<u8> {
+    let mut out = Vec::with_capacity(s.len());
+    for c in s.chars() {
+        match encode_char(c, charset) {
+            Some(bytes) => out.extend_from_slice(bytes),
+            None => out.push(b'?'),
+        }
+    }
+    out
+}
+
+/// Decode legacy bytes to Unicode
+///
+/// Two-byte sequences (VNI-Windows, CP1258) are matched before single bytes.
+/// Unmapped bytes decode as Latin-1. TCVN3 uppercase letters that share
+/// the lowercase byte decode as lowercase.
+pub fn decode(bytes: &[u8], charset: Charset) -> String {
+    let table = charset.table();
+    let mut out = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let pair = rest
+            .get(..2)
+            .and_then(|p| table.iter().find(|(_, b)| *b == p));
+        let single = || table.iter().find(|(_, b)| *b == &rest[..1]);
+        if let Some(&(c, _)) = pair {
+            out.push(c);
+            i += 2;
+        } else if let Some(&(c, _)) = single() {
+            out.push(c);
+            i += 1;
+        } else {
+            out.push(bytes[i] as char);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Identity table for ASCII bytes (lets encode_char return a static slice)
+const ASCII: [u8; 128] = {
+    let mut table = [0u8; 128];
+    let mut i = 0;
+    while i < 128 {
+        table[i] = i as u8;
+        i += 1;
+    }
+    table
+};
+
+/// TCVN3 (ABC): lowercase letters and Ă Â Ê Ô Ơ Ư Đ have their own byte
+/// Other uppercase letters share the lowercase byte; ABC fonts come in
+/// separate uppercase variants (.VnTimeH) that render them capitalized.
+pub const TCVN3: &[(char, &[u8])] = &[
+    ('á', &[0xB8]),
+    ('à', &[0xB5]),
+    ('ả', &[0xB6]),
+    ('ã', &[0xB7]),
+    ('ạ', &[0xB9]),
+    ('ă', &[0xA8]),
+    ('ắ', &[0xBE]),
+    ('ằ', &[0xBB]),
+    ('ẳ', &[0xBC]),
+    ('ẵ', &[0xBD]),
+    ('ặ', &[0xC6]),
+    ('â', &[0xA9]),
+    ('ấ', &[0xCA]),
+    ('ầ', &[0xC7]),
+    ('ẩ', &[0xC8]),
+    ('ẫ', &[0xC9]),
+    ('ậ', &[0xCB]),
+    ('é', &[0xD0]),
+    ('è', &[0xCC]),
+    ('ẻ', &[0xCE]),
+    ('ẽ', &[0xCF]),
+    ('ẹ', &[0xD1]),
+    ('ê', &[0xAA]),
+    ('ế', &[0xD5]),
+    ('ề', &[0xD2]),
+    ('ể', &[0xD3]),
+    ('ễ', &[0xD4]),
+    ('ệ', &[0xD6]),
+    ('í', &[0xDD]),
+    ('ì', &[0xD7]),
+    ('ỉ', &[0xD8]),
+    ('ĩ', &[0xDC]),
+    ('ị', &[0xDE]),
+    ('ó', &[0xE3]),
+    ('ò', &[0xDF]),
+    ('ỏ', &[0xE1]),
+    ('õ', &[0xE2]),
+    ('ọ', &[0xE4]),
+    ('ô', &[0xAB]),
+    ('ố', &[0xE8]),
+    ('ồ', &[0xE5]),
+    ('ổ', &[0xE6]),
+    ('ỗ', &[0xE7]),
+    ('ộ', &[0xE9]),
+    ('ơ', &[0xAC]),
+    ('ớ', &[0xED]),
+    ('ờ', &[0xEA]),
+    ('ở', &[0xEB]),
+    ('ỡ', &[0xEC]),
+    ('ợ', &[0xEE]),
+    ('ú', &[0xF3]),
+    ('ù', &[0xEF]),
+    ('ủ', &[0xF1]),
+    ('ũ', &[0xF2]),
+    ('ụ', &[0xF4]),
+    ('ư', &[0xAD]),
+    ('ứ', &[0xF8]),
+    ('ừ', &[0xF5]),
+    ('ử', &[0xF6]),
+    ('ữ', &[0xF7]),
+    ('ự', &[0xF9]),
+    ('ý', &[0xFD]),
+    ('ỳ', &[0xFA]),
+    ('ỷ', &[0xFB]),
+    ('ỹ', &[0xFC]),
+    ('ỵ', &[0xFE]),
+    ('đ', &[0xAE]),
+    ('Á', &[0xB8]),
+    ('À', &[0xB5]),
+    ('Ả', &[0xB6]),
+    ('Ã', &[0xB7]),
+    ('Ạ', &[0xB9]),
+    ('Ă', &[0xA1]),
+    ('Ắ', &[0xBE]),
+    ('Ằ', &[0xBB]),
+    ('Ẳ', &[0xBC]),
+    ('Ẵ', &[0xBD]),
+    ('Ặ', &[0xC6]),
+    ('Â', &[0xA2]),
+    ('Ấ', &[0xCA]),
+    ('Ầ', &[0xC7]),
+    ('Ẩ', &[0xC8]),
+    ('Ẫ', &[0xC9]),
+    ('Ậ', &[0xCB]),
+    ('É', &[0xD0]),
+    ('È', &[0xCC]),
+    ('Ẻ', &[0xCE]),
+    ('Ẽ', &[0xCF]),
+    ('Ẹ', &[0xD1]),
+    ('Ê', &[0xA3]),
+    ('Ế', &[0xD5]),
+    ('Ề', &[0xD2]),
+    ('Ể', &[0xD3]),
+    ('Ễ', &[0xD4]),
+    ('Ệ', &[0xD6]),
+    ('Í', &[0xDD]),
+    ('Ì', &[0xD7]),
+    ('Ỉ', &[0xD8]),
+    ('Ĩ', &[0xDC]),
+    ('Ị', &[0xDE]),
+    ('Ó', &[0xE3]),
+    ('Ò', &[0xDF]),
+    ('Ỏ', &[0xE1]),
+    ('Õ', &[0xE2]),
+    ('Ọ', &[0xE4]),
+    ('Ô', &[0xA4]),
+    ('Ố', &[0xE8]),
+    ('Ồ', &[0xE5]),
+    ('Ổ', &[0xE6]),
+    ('Ỗ', &[0xE7]),
+    ('Ộ', &[0xE9]),
+    ('Ơ', &[0xA5]),
+    ('Ớ', &[0xED]),
+    ('Ờ', &[0xEA]),
+    ('Ở', &[0xEB]),
+    ('Ỡ', &[0xEC]),
+    ('Ợ', &[0xEE]),
+    ('Ú', &[0xF3]),
+    ('Ù', &[0xEF]),
+    ('Ủ', &[0xF1]),
+    ('Ũ', &[0xF2]),
+    ('Ụ', &[0xF4]),
+    ('Ư', &[0xA6]),
+    ('Ứ', &[0xF8]),
+    ('Ừ', &[0xF5]),
+    ('Ử', &[0xF6]),
+    ('Ữ', &[0xF7]),
+    ('Ự', &[0xF9]),
+    ('Ý', &[0xFD]),
+    ('Ỳ', &[0xFA]),
+    ('Ỷ', &[0xFB]),
+    ('Ỹ', &[0xFC]),
+    ('Ỵ', &[0xFE]),
+    ('Đ', &[0xA7]),
+];
+
+/// VNI-Windows: base letter followed by a diacritic byte (á = a + 0xF9)
+/// ơ, ư, đ and most i forms are single bytes.
+pub const VNI_WINDOWS: &[(char, &[u8])] = &[
+    ('á', &[0x61, 0xF9]),
+    ('à', &[0x61, 0xF8]),
+    ('ả', &[0x61, 0xFB]),
+    ('ã', &[0x61, 0xF5]),
+    ('ạ', &[0x61, 0xEF]),
+    ('ă', &[0x61, 0xEA]),
+    ('ắ', &[0x61, 0xE9]),
+    ('ằ', &[0x61, 0xE8]),
+    ('ẳ', &[0x61, 0xFA]),
+    ('ẵ', &[0x61, 0xFC]),
+    ('ặ', &[0x61, 0xEB]),
+    ('â', &[0x61, 0xE2]),
+    ('ấ', &[0x61, 0xE1]),
+    ('ầ', &[0x61, 0xE0]),
+    ('ẩ', &[0x61, 0xE5]),
+    ('ẫ', &[0x61, 0xE3]),
+    ('ậ', &[0x61, 0xE4]),
+    ('é', &[0x65, 0xF9]),
+    ('è', &[0x65, 0xF8]),
+    ('ẻ', &[0x65, 0xFB]),
+    ('ẽ', &[0x65, 0xF5]),
+    ('ẹ', &[0x65, 0xEF]),
+    ('ê', &[0x65, 0xE2]),
+    ('ế', &[0x65, 0xE1]),
+    ('ề', &[0x65, 0xE0]),
+    ('ể', &[0x65, 0xE5]),
+    ('ễ', &[0x65, 0xE3]),
+    ('ệ', &[0x65, 0xE4]),
+    ('í', &[0xED]),
+    ('ì', &[0xEC]),
+    ('ỉ', &[0xE6]),
+    ('ĩ', &[0xF3]),
+    ('ị', &[0xF2]),
+    ('ó', &[0x6F, 0xF9]),
+    ('ò', &[0x6F, 0xF8]),
+    ('ỏ', &[0x6F, 0xFB]),
+    ('õ', &[0x6F, 0xF5]),
+    ('ọ', &[0x6F, 0xEF]),
+    ('ô', &[0x6F, 0xE2]),
+    ('ố', &[0x6F, 0xE1]),
+    ('ồ', &[0x6F, 0xE0]),
+    ('ổ', &[0x6F, 0xE5]),
+    ('ỗ', &[0x6F, 0xE3]),
+    ('ộ', &[0x6F, 0xE4]),
+    ('ơ', &[0xF4]),
+    ('ớ', &[0xF4, 0xF9]),
+    ('ờ', &[0xF4, 0xF8]),
+    ('ở', &[0xF4, 0xFB]),
+    ('ỡ', &[0xF4, 0xF5]),
+    ('ợ', &[0xF4, 0xEF]),
+    ('ú', &[0x75, 0xF9]),
+    ('ù', &[0x75, 0xF8]),
+    ('ủ', &[0x75, 0xFB]),
+    ('ũ', &[0x75, 0xF5]),
+    ('ụ', &[0x75, 0xEF]),
+    ('ư', &[0xF6]),
+    ('ứ', &[0xF6, 0xF9]),
+    ('ừ', &[0xF6, 0xF8]),
+    ('ử', &[0xF6, 0xFB]),
+    ('ữ', &[0xF6, 0xF5]),
+    ('ự', &[0xF6, 0xEF]),
+    ('ý', &[0x79, 0xF9]),
+    ('ỳ', &[0x79, 0xF8]),
+    ('ỷ', &[0x79, 0xFB]),
+    ('ỹ', &[0x79, 0xF5]),
+    ('ỵ', &[0xEE]),
+    ('đ', &[0xF1]),
+    ('Á', &[0x41, 0xD9]),
+    ('À', &[0x41, 0xD8]),
+    ('Ả', &[0x41, 0xDB]),
+    ('Ã', &[0x41, 0xD5]),
+    ('Ạ', &[0x41, 0xCF]),
+    ('Ă', &[0x41, 0xCA]),
+    ('Ắ', &[0x41, 0xC9]),
+    ('Ằ', &[0x41, 0xC8]),
+    ('Ẳ', &[0x41, 0xDA]),
+    ('Ẵ', &[0x41, 0xDC]),
+    ('Ặ', &[0x41, 0xCB]),
+    ('Â', &[0x41, 0xC2]),
+    ('Ấ', &[0x41, 0xC1]),
+    ('Ầ', &[0x41, 0xC0]),
+    ('Ẩ', &[0x41, 0xC5]),
+    ('Ẫ', &[0x41, 0xC3]),
+    ('Ậ', &[0x41, 0xC4]),
+    ('É', &[0x45, 0xD9]),
+    ('È', &[0x45, 0xD8]),
+    ('Ẻ', &[0x45, 0xDB]),
+    ('Ẽ', &[0x45, 0xD5]),
+    ('Ẹ', &[0x45, 0xCF]),
+    ('Ê', &[0x45, 0xC2]),
+    ('Ế', &[0x45, 0xC1]),
+    ('Ề', &[0x45, 0xC0]),
+    ('Ể', &[0x45, 0xC5]),
+    ('Ễ', &[0x45, 0xC3]),
+    ('Ệ', &[0x45, 0xC4]),
+    ('Í', &[0xCD]),
+    ('Ì', &[0xCC]),
+    ('Ỉ', &[0xC6]),
+    ('Ĩ', &[0xD3]),
+    ('Ị', &[0xD2]),
+    ('Ó', &[0x4F, 0xD9]),
+    ('Ò', &[0x4F, 0xD8]),
+    ('Ỏ', &[0x4F, 0xDB]),
+    ('Õ', &[0x4F, 0xD5]),
+    ('Ọ', &[0x4F, 0xCF]),
+    ('Ô', &[0x4F, 0xC2]),
+    ('Ố', &[0x4F, 0xC1]),
+    ('Ồ', &[0x4F, 0xC0]),
+    ('Ổ', &[0x4F, 0xC5]),
+    ('Ỗ', &[0x4F, 0xC3]),
+    ('Ộ', &[0x4F, 0xC4]),
+    ('Ơ', &[0xD4]),
+    ('Ớ', &[0xD4, 0xD9]),
+    ('Ờ', &[0xD4, 0xD8]),
+    ('Ở', &[0xD4, 0xDB]),
+    ('Ỡ', &[0xD4, 0xD5]),
+    ('Ợ', &[0xD4, 0xCF]),
+    ('Ú', &[0x55, 0xD9]),
+    ('Ù', &[0x55, 0xD8]),
+    ('Ủ', &[0x55, 0xDB]),
+    ('Ũ', &[0x55, 0xD5]),
+    ('Ụ', &[0x55, 0xCF]),
+    ('Ư', &[0xD6]),
+    ('Ứ', &[0xD6, 0xD9]),
+    ('Ừ', &[0xD6, 0xD8]),
+    ('Ử', &[0xD6, 0xDB]),
+    ('Ữ', &[0xD6, 0xD5]),
+    ('Ự', &[0xD6, 0xCF]),
+    ('Ý', &[0x59, 0xD9]),
+    ('Ỳ', &[0x59, 0xD8]),
+    ('Ỷ', &[0x59, 0xDB]),
+    ('Ỹ', &[0x59, 0xD5]),
+    ('Ỵ', &[0xCE]),
+    ('Đ', &[0xD1]),
+];
+
+/// VISCII (RFC 1456): one byte per letter, including control-code positions
+pub const VISCII: &[(char, &[u8])] = &[
+    ('á', &[0xE1]),
+    ('à', &[0xE0]),
+    ('ả', &[0xE4]),
+    ('ã', &[0xE3]),
+    ('ạ', &[0xD5]),
+    ('ă', &[0xE5]),
+    ('ắ', &[0xA1]),
+    ('ằ', &[0xA2]),
+    ('ẳ', &[0xC6]),
+    ('ẵ', &[0xC7]),
+    ('ặ', &[0xA3]),
+    ('â', &[0xE2]),
+    ('ấ', &[0xA4]),
+    ('ầ', &[0xA5]),
+    ('ẩ', &[0xA6]),
+    ('ẫ', &[0xE7]),
+    ('ậ', &[0xA7]),
+    ('é', &[0xE9]),
+    ('è', &[0xE8]),
+    ('ẻ', &[0xEB]),
+    ('ẽ', &[0xA8]),
+    ('ẹ', &[0xA9]),
+    ('ê', &[0xEA]),
+    ('ế', &[0xAA]),
+    ('ề', &[0xAB]),
+    ('ể', &[0xAC]),
+    ('ễ', &[0xAD]),
+    ('ệ', &[0xAE]),
+    ('í', &[0xED]),
+    ('ì', &[0xEC]),
+    ('ỉ', &[0xEF]),
+    ('ĩ', &[0xEE]),
+    ('ị', &[0xB8]),
+    ('ó', &[0xF3]),
+    ('ò', &[0xF2]),
+    ('ỏ', &[0xF6]),
+    ('õ', &[0xF5]),
+    ('ọ', &[0xF7]),
+    ('ô', &[0xF4]),
+    ('ố', &[0xAF]),
+    ('ồ', &[0xB0]),
+    ('ổ', &[0xB1]),
+    ('ỗ', &[0xB2]),
+    ('ộ', &[0xB5]),
+    ('ơ', &[0xBD]),
+    ('ớ', &[0xBE]),
+    ('ờ', &[0xB6]),
+    ('ở', &[0xB7]),
+    ('ỡ', &[0xDE]),
+    ('ợ', &[0xFE]),
+    ('ú', &[0xFA]),
+    ('ù', &[0xF9]),
+    ('ủ', &[0xFC]),
+    ('ũ', &[0xFB]),
+    ('ụ', &[0xF8]),
+    ('ư', &[0xDF]),
+    ('ứ', &[0xD1]),
+    ('ừ', &[0xD7]),
+    ('ử', &[0xD8]),
+    ('ữ', &[0xE6]),
+    ('ự', &[0xF1]),
+    ('ý', &[0xFD]),
+    ('ỳ', &[0xCF]),
+    ('ỷ', &[0xD6]),
+    ('ỹ', &[0xDB]),
+    ('ỵ', &[0xDC]),
+    ('đ', &[0xF0]),
+    ('Á', &[0xC1]),
+    ('À', &[0xC0]),
+    ('Ả', &[0xC4]),
+    ('Ã', &[0xC3]),
+    ('Ạ', &[0x80]),
+    ('Ă', &[0xC5]),
+    ('Ắ', &[0x81]),
+    ('Ằ', &[0x82]),
+    ('Ẳ', &[0x02]),
+    ('Ẵ', &[0x05]),
+    ('Ặ', &[0x83]),
+    ('Â', &[0xC2]),
+    ('Ấ', &[0x84]),
+    ('Ầ', &[0x85]),
+    ('Ẩ', &[0x86]),
+    ('Ẫ', &[0x06]),
+    ('Ậ', &[0x87]),
+    ('É', &[0xC9]),
+    ('È', &[0xC8]),
+    ('Ẻ', &[0xCB]),
+    ('Ẽ', &[0x88]),
+    ('Ẹ', &[0x89]),
+    ('Ê', &[0xCA]),
+    ('Ế', &[0x8A]),
+    ('Ề', &[0x8B]),
+    ('Ể', &[0x8C]),
+    ('Ễ', &[0x8D]),
+    ('Ệ', &[0x8E]),
+    ('Í', &[0xCD]),
+    ('Ì', &[0xCC]),
+    ('Ỉ', &[0x9B]),
+    ('Ĩ', &[0xCE]),
+    ('Ị', &[0x98]),
+    ('Ó', &[0xD3]),
+    ('Ò', &[0xD2]),
+    ('Ỏ', &[0x99]),
+    ('Õ', &[0xA0]),
+    ('Ọ', &[0x9A]),
+    ('Ô', &[0xD4]),
+    ('Ố', &[0x8F]),
+    ('Ồ', &[0x90]),
+    ('Ổ', &[0x91]),
+    ('Ỗ', &[0x92]),
+    ('Ộ', &[0x93]),
+    ('Ơ', &[0xB4]),
+    ('Ớ', &[0x95]),
+    ('Ờ', &[0x96]),
+    ('Ở', &[0x97]),
+    ('Ỡ', &[0xB3]),
+    ('Ợ', &[0x94]),
+    ('Ú', &[0xDA]),
+    ('Ù', &[0xD9]),
+    ('Ủ', &[0x9C]),
+    ('Ũ', &[0x9D]),
+    ('Ụ', &[0x9E]),
+    ('Ư', &[0xBF]),
+    ('Ứ', &[0xBA]),
+    ('Ừ', &[0xBB]),
+    ('Ử', &[0xBC]),
+    ('Ữ', &[0xFF]),
+    ('Ự', &[0xB9]),
+    ('Ý', &[0xDD]),
+    ('Ỳ', &[0x9F]),
+    ('Ỷ', &[0x14]),
+    ('Ỹ', &[0x19]),
+    ('Ỵ', &[0x1E]),
+    ('Đ', &[0xD0]),
+];
+
+/// Windows-1258: precomposed byte when the code page has one, otherwise
+/// base letter + combining tone mark (ấ = â + 0xEC)
+pub const CP1258: &[(char, &[u8])] = &[
+    ('á', &[0xE1]),
+    ('à', &[0xE0]),
+    ('ả', &[0x61, 0xD2]),
+    ('ã', &[0x61, 0xDE]),
+    ('ạ', &[0x61, 0xF2]),
+    ('ă', &[0xE3]),
+    ('ắ', &[0xE3, 0xEC]),
+    ('ằ', &[0xE3, 0xCC]),
+    ('ẳ', &[0xE3, 0xD2]),
+    ('ẵ', &[0xE3, 0xDE]),
+    ('ặ', &[0xE3, 0xF2]),
+    ('â', &[0xE2]),
+    ('ấ', &[0xE2, 0xEC]),
+    ('ầ', &[0xE2, 0xCC]),
+    ('ẩ', &[0xE2, 0xD2]),
+    ('ẫ', &[0xE2, 0xDE]),
+    ('ậ', &[0xE2, 0xF2]),
+    ('é', &[0xE9]),
+    ('è', &[0xE8]),
+    ('ẻ', &[0x65, 0xD2]),
+    ('ẽ', &[0x65, 0xDE]),
+    ('ẹ', &[0x65, 0xF2]),
+    ('ê', &[0xEA]),
+    ('ế', &[0xEA, 0xEC]),
+    ('ề', &[0xEA, 0xCC]),
+    ('ể', &[0xEA, 0xD2]),
+    ('ễ', &[0xEA, 0xDE]),
+    ('ệ', &[0xEA, 0xF2]),
+    ('í', &[0xED]),
+    ('ì', &[0x69, 0xCC]),
+    ('ỉ', &[0x69, 0xD2]),
+    ('ĩ', &[0x69, 0xDE]),
+    ('ị', &[0x69, 0xF2]),
+    ('ó', &[0xF3]),
+    ('ò', &[0x6F, 0xCC]),
+    ('ỏ', &[0x6F, 0xD2]),
+    ('õ', &[0x6F, 0xDE]),
+    ('ọ', &[0x6F, 0xF2]),
+    ('ô', &[0xF4]),
+    ('ố', &[0xF4, 0xEC]),
+    ('ồ', &[0xF4, 0xCC]),
+    ('ổ', &[0xF4, 0xD2]),
+    ('ỗ', &[0xF4, 0xDE]),
+    ('ộ', &[0xF4, 0xF2]),
+    ('ơ', &[0xF5]),
+    ('ớ', &[0xF5, 0xEC]),
+    ('ờ', &[0xF5, 0xCC]),
+    ('ở', &[0xF5, 0xD2]),
+    ('ỡ', &[0xF5, 0xDE]),
+    ('ợ', &[0xF5, 0xF2]),
+    ('ú', &[0xFA]),
+    ('ù', &[0xF9]),
+    ('ủ', &[0x75, 0xD2]),
+    ('ũ', &[0x75, 0xDE]),
+    ('ụ', &[0x75, 0xF2]),
+    ('ư', &[0xFD]),
+    ('ứ', &[0xFD, 0xEC]),
+    ('ừ', &[0xFD, 0xCC]),
+    ('ử', &[0xFD, 0xD2]),
+    ('ữ', &[0xFD, 0xDE]),
+    ('ự', &[0xFD, 0xF2]),
+    ('ý', &[0x79, 0xEC]),
+    ('ỳ', &[0x79, 0xCC]),
+    ('ỷ', &[0x79, 0xD2]),
+    ('ỹ', &[0x79, 0xDE]),
+    ('ỵ', &[0x79, 0xF2]),
+    ('đ', &[0xF0]),
+    ('Á', &[0xC1]),
+    ('À', &[0xC0]),
+    ('Ả', &[0x41, 0xD2]),
+    ('Ã', &[0x41, 0xDE]),
+    ('Ạ', &[0x41, 0xF2]),
+    ('Ă', &[0xC3]),
+    ('Ắ', &[0xC3, 0xEC]),
+    ('Ằ', &[0xC3, 0xCC]),
+    ('Ẳ', &[0xC3, 0xD2]),
+    ('Ẵ', &[0xC3, 0xDE]),
+    ('Ặ', &[0xC3, 0xF2]),
+    ('Â', &[0xC2]),
+    ('Ấ', &[0xC2, 0xEC]),
+    ('Ầ', &[0xC2, 0xCC]),
+    ('Ẩ', &[0xC2, 0xD2]),
+    ('Ẫ', &[0xC2, 0xDE]),
+    ('Ậ', &[0xC2, 0xF2]),
+    ('É', &[0xC9]),
+    ('È', &[0xC8]),
+    ('Ẻ', &[0x45, 0xD2]),
+    ('Ẽ', &[0x45, 0xDE]),
+    ('Ẹ', &[0x45, 0xF2]),
+    ('Ê', &[0xCA]),
+    ('Ế', &[0xCA, 0xEC]),
+    ('Ề', &[0xCA, 0xCC]),
+    ('Ể', &[0xCA, 0xD2]),
+    ('Ễ', &[0xCA, 0xDE]),
+    ('Ệ', &[0xCA, 0xF2]),
+    ('Í', &[0xCD]),
+    ('Ì', &[0x49, 0xCC]),
+    ('Ỉ', &[0x49, 0xD2]),
+    ('Ĩ', &[0x49, 0xDE]),
+    ('Ị', &[0x49, 0xF2]),
+    ('Ó', &[0xD3]),
+    ('Ò', &[0x4F, 0xCC]),
+    ('Ỏ', &[0x4F, 0xD2]),
+    ('Õ', &[0x4F, 0xDE]),
+    ('Ọ', &[0x4F, 0xF2]),
+    ('Ô', &[0xD4]),
+    ('Ố', &[0xD4, 0xEC]),
+    ('Ồ', &[0xD4, 0xCC]),
+    ('Ổ', &[0xD4, 0xD2]),
+    ('Ỗ', &[0xD4, 0xDE]),
+    ('Ộ', &[0xD4, 0xF2]),
+    ('Ơ', &[0xD5]),
+    ('Ớ', &[0xD5, 0xEC]),
+    ('Ờ', &[0xD5, 0xCC]),
+    ('Ở', &[0xD5, 0xD2]),
+    ('Ỡ', &[0xD5, 0xDE]),
+    ('Ợ', &[0xD5, 0xF2]),
+    ('Ú', &[0xDA]),
+    ('Ù', &[0xD9]),
+    ('Ủ', &[0x55, 0xD2]),
+    ('Ũ', &[0x55, 0xDE]),
+    ('Ụ', &[0x55, 0xF2]),
+    ('Ư', &[0xDD]),
+    ('Ứ', &[0xDD, 0xEC]),
+    ('Ừ', &[0xDD, 0xCC]),
+    ('Ử', &[0xDD, 0xD2]),
+    ('Ữ', &[0xDD, 0xDE]),
+    ('Ự', &[0xDD, 0xF2]),
+    ('Ý', &[0x59, 0xEC]),
+    ('Ỳ', &[0x59, 0xCC]),
+    ('Ỷ', &[0x59, 0xD2]),
+    ('Ỹ', &[0x59, 0xDE]),
+    ('Ỵ', &[0x59, 0xF2]),
+    ('Đ', &[0xD0]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Charset; 4] = [
+        Charset::Tcvn3,
+        Charset::VniWindows,
+        Charset::Viscii,
+        Charset::Cp1258,
+    ];
+
+    #[test]
+    fn test_tables_cover_all_letters() {
+        // 12 vowels × 6 marks + đ, lower and upper, minus 12 plain ASCII vowels each case
+        for charset in ALL {
+            assert_eq!(charset.table().len(), 134, "{:?}", charset);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("Tiếng Việt", Charset::Tcvn3), b"Ti\xd5ng Vi\xd6t");
+        assert_eq!(
+            encode("Tiếng Việt", Charset::VniWindows),
+            b"Tie\xe1ng Vie\xe4t"
+        );
+        assert_eq!(encode("Tiếng Việt", Charset::Viscii), b"Ti\xaang Vi\xaet");
+        assert_eq!(
+            encode("Tiếng Việt", Charset::Cp1258),
+            b"Ti\xea\xecng Vi\xea\xf2t"
+        );
+        assert_eq!(encode("Đường", Charset::VniWindows), b"\xd1\xf6\xf4\xf8ng");
+        assert_eq!(encode("→", Charset::Tcvn3), b"?");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for charset in ALL {
+            for &(c, bytes) in charset.table() {
+                let decoded = decode(bytes, charset);
+                // TCVN3 uppercase may share the lowercase byte
+                let shared = charset == Charset::Tcvn3 && decoded == c.to_lowercase().to_string();
+                assert!(decoded == c.to_string() || shared, "{:?} {}", charset, c);
+            }
+        }
+        let text = "Cộng hòa xã hội chủ nghĩa Việt Nam";
+        for charset in ALL {
+            assert_eq!(
+                decode(&encode(text, charset), charset),
+                text,
+                "{:?}",
+                charset
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_id() {
+        for id in 0..6 {
+            assert_eq!(Charset::from_id(id).unwrap().id(), id);
+        }
+        assert_eq!(Charset::from_id(6), None);
+    }
+}
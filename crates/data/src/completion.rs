@@ -0,0 +1,374 @@
+//! Word Completion
+//!
+//! Completes the word being typed and predicts what follows, using the
+//! words committed before it as context: "ngh" → "nghiệp", "nghĩ";
+//! "Việt" + "" → "nam"; "cộng h" → "hoà xã".
+//!
+//! Candidates come from three sources, in this order:
+//! - `Phrases`, the runs of words the user committed, as they typed them;
+//! - the bundled syllable bigrams (`ngram`): what usually follows the last
+//!   word of the context, most frequent first;
+//! - the dictionary syllables of one style, most frequent first. A syllable
+//!   mostly followed by the same one also completes to the pair
+//!   ("vi" → "việt nam").
+
+use crate::case::match_case;
+use crate::chars::{self, mark};
+use crate::ngram;
+use crate::vietnamese_spellcheck::words;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::LazyLock;
+
+/// Longest learned phrase, in words
+pub const MAX_PHRASE_WORDS: usize = 3;
+
+/// Maximum number of phrases kept
+pub const MAX_PHRASES: usize = 4096;
+
+/// Runs of committed words, for multi-syllable completion and prediction
+///
+/// Memory is bounded: at most `MAX_PHRASES` phrases of 2 to
+/// `MAX_PHRASE_WORDS` words. When full, the least used phrase is forgotten,
+/// the least recently learned among equals.
+#[derive(Clone, Debug, Default)]
+pub struct Phrases {
+    /// Lowercase words joined by spaces → phrase
+    entries: HashMap<String, Phrase>,
+    /// (count, learned at, key) of every phrase, next to forget first
+    by_use: BTreeSet<(u32, u64, String)>,
+    /// Calls to `learn` so far
+    tick: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Phrase {
+    /// Words as last committed
+    forms: Vec<String>,
+    count: u32,
+    /// `tick` when last learned
+    learned: u64,
+}
+
+impl Phrases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Learn the phrases ending at the last of `words`
+    ///
+    /// `words` are the words committed so far in the sentence, oldest first:
+    /// ["cộng", "hoà", "xã"] learns "hoà xã" and "cộng hoà xã".
+    pub fn learn(&mut self, words: &[&str]) {
+        self.tick += 1;
+        for len in 2..=words.len().min(MAX_PHRASE_WORDS) {
+            let phrase = &words[words.len() - len..];
+            let key = phrase.join(" ").to_lowercase();
+            let entry = self.entries.entry(key.clone()).or_insert(Phrase {
+                forms: Vec::new(),
+                count: 0,
+                learned: 0,
+            });
+            self.by_use
+                .remove(&(entry.count, entry.learned, key.clone()));
+            entry.forms = phrase.iter().map(|w| w.to_string()).collect();
+            entry.count = entry.count.saturating_add(1);
+            entry.learned = self.tick;
+            self.by_use.insert((entry.count, entry.learned, key));
+        }
+        while self.entries.len() > MAX_PHRASES {
+            let Some((_, _, key)) = self.by_use.pop_first() else {
+                break;
+            };
+            self.entries.remove(&key);
+        }
+    }
+
+    /// Times a phrase was committed ("việt nam"), case-insensitive
+    pub fn count(&self, phrase: &str) -> u32 {
+        self.entries
+            .get(&phrase.to_lowercase())
+            .map_or(0, |p| p.count)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.by_use.clear();
+    }
+}
+
+/// Dictionary syllables, most frequent first
+fn build_index(use_modern: bool) -> Vec<&'static str> {
+    let ngrams = ngram::bundled();
+    let mut index: Vec<(&'static str, u32)> = words(use_modern)
+        .iter()
+        .map(|&w| (w, ngrams.count(w)))
+        .collect();
+    index.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    index.into_iter().map(|(w, _)| w).collect()
+}
+
+/// The syllable following `word` at least half the time ("việt" → "nam")
+fn collocation(word: &str, use_modern: bool) -> Option<&'static str> {
+    let ngrams = ngram::bundled();
+    let (next, pair) = *ngrams.next(word).first()?;
+    if pair * 2 < ngrams.count(word) {
+        return None;
+    }
+    ngram::spell(next, use_modern)
+}
+
+static INDEX_MODERN: LazyLock<Vec<&'static str>> = LazyLock::new(|| build_index(true));
+static INDEX_TRADITIONAL: LazyLock<Vec<&'static str>> = LazyLock::new(|| build_index(false));
+
+/// Can `word` still become `candidate` by typing more keys?
+///
+/// Letters must match; diacritics already typed must be there, missing
+/// ones may come later. A mark may still move to another vowel.
+fn extends(word: &str, candidate: &str) -> bool {
+    let mut rest = candidate.chars();
+    let mut word_mark = mark::NONE;
+    for c in word.chars() {
+        let (Some(p), Some(q)) = (
+            chars::parse_char(c),
+            rest.next().and_then(chars::parse_char),
+        ) else {
+            return false;
+        };
+        if p.key != q.key || (p.tone != 0 && p.tone != q.tone) || (p.stroke && !q.stroke) {
+            return false;
+        }
+        if p.mark != mark::NONE {
+            word_mark = p.mark;
+        }
+    }
+    word_mark == mark::NONE
+        || candidate
+            .chars()
+            .any(|c| chars::parse_char(c).is_some_and(|q| q.mark == word_mark))
+}
+
+/// Up to `limit` completions of `prefix`, best first
+///
+/// `context` is the words committed before it in the sentence, oldest
+/// first (empty at the start of a sentence). A completion may span several
+/// words ("Vi" → "Việt Nam"); its first word keeps the prefix's case
+/// ("Ng" → "Nghiệp") and a completion never repeats the prefix. An empty
+/// prefix predicts what follows the context.
+pub fn complete(
+    context: &[&str],
+    prefix: &str,
+    phrases: &Phrases,
+    use_modern: bool,
+    limit: usize,
+) -> Vec<String> {
+    let lower = prefix.to_lowercase();
+    let context: Vec<String> = context.iter().map(|w| w.to_lowercase()).collect();
+    if lower.is_empty() && context.is_empty() {
+        return Vec::new();
+    }
+
+    // (context words matched, count, words, text)
+    let mut learned: Vec<(usize, u32, usize, String)> = Vec::new();
+    for (key, phrase) in &phrases.entries {
+        let keys: Vec<&str> = key.split(' ').collect();
+        // Longest end of the context the phrase starts with, leaving a word
+        let longest = context.len().min(keys.len() - 1);
+        let matched = (0..=longest)
+            .rev()
+            .find(|&n| keys[..n].iter().eq(&context[context.len() - n..]))
+            .unwrap_or(0);
+        if matched == 0 && lower.is_empty() {
+            continue;
+        }
+        let first = keys[matched];
+        let repeats = first == lower && matched + 1 == keys.len();
+        if repeats || !extends(&lower, first) {
+            continue;
+        }
+        let rest = &phrase.forms[matched..];
+        let mut text = if lower.is_empty() {
+            rest[0].clone()
+        } else {
+            prefix_case(prefix, &rest[0].to_lowercase())
+        };
+        for word in &rest[1..] {
+            text.push(' ');
+            text.push_str(word);
+        }
+        learned.push((matched, phrase.count, rest.len(), text));
+    }
+    learned.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(b.1.cmp(&a.1))
+            .then(b.2.cmp(&a.2))
+            .then(a.3.cmp(&b.3))
+    });
+
+    let predicted = context
+        .last()
+        .map(|prev| ngram::bundled().next(prev))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(w, _)| ngram::spell(w, use_modern))
+        .filter(|&w| w != lower && extends(&lower, w))
+        .map(|w| prefix_case(prefix, w));
+
+    let index = if use_modern {
+        &INDEX_MODERN
+    } else {
+        &INDEX_TRADITIONAL
+    };
+    let dictionary = index
+        .iter()
+        .filter(|&&w| !lower.is_empty() && extends(&lower, w))
+        .flat_map(|&w| {
+            let word = prefix_case(prefix, w);
+            let pair = collocation(w, use_modern).map(|next| format!("{} {}", word, next));
+            pair.into_iter().chain((w != lower).then_some(word))
+        });
+
+    let mut out: Vec<String> = Vec::new();
+    let learned = learned.into_iter().map(|(_, _, _, t)| t);
+    for text in learned.chain(predicted).chain(dictionary) {
+        if out.len() == limit {
+            break;
+        }
+        if !out.iter().any(|o| o.to_lowercase() == text.to_lowercase()) {
+            out.push(text);
+        }
+    }
+    out
+}
+
+/// `word` in the case of the typed prefix: one capital is "Nam", not "NAM"
+fn prefix_case(prefix: &str, word: &str) -> String {
+    let mut letters = prefix.chars();
+    match (letters.next(), letters.next()) {
+        (Some(first), None) if first.is_uppercase() => match_case("Aa", word),
+        (Some(_), Some(_)) => match_case(prefix, word),
+        _ => word.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extends() {
+        assert!(extends("ngh", "nghiệp"));
+        assert!(extends("vie", "việt"));
+        assert!(extends("viê", "việt"));
+        assert!(extends("hoá", "hóa"));
+        assert!(!extends("vo", "việt"));
+        assert!(!extends("vâ", "việt"));
+        // dd can still be typed
+        assert!(extends("dươ", "đường"));
+        assert!(!extends("đươ", "dương"));
+        assert!(!extends("viét", "việt"));
+    }
+
+    #[test]
+    fn test_complete_prefix() {
+        let none = Phrases::new();
+        let completions = complete(&[], "ngh", &none, true, 5);
+        assert_eq!(completions.len(), 5);
+        assert!(completions.iter().all(|w| w.starts_with("ngh")));
+        assert_eq!(
+            complete(&[], "Ngh", &none, true, 1)[0].chars().next(),
+            Some('N')
+        );
+        assert!(complete(&[], "xyz", &none, true, 5).is_empty());
+        assert!(complete(&[], "", &none, true, 5).is_empty());
+    }
+
+    #[test]
+    fn test_complete_bundled() {
+        let none = Phrases::new();
+        // Next syllable from the bundled bigrams
+        assert_eq!(complete(&["việt"], "", &none, true, 1), ["nam"]);
+        assert_eq!(complete(&["Việt"], "N", &none, true, 1), ["Nam"]);
+        assert_eq!(complete(&["hôm"], "", &none, true, 2), ["nay", "qua"]);
+        // Frequent syllables first, with their usual next syllable
+        assert_eq!(complete(&[], "tậ", &none, true, 2), ["tập tin", "tập"]);
+        assert!(complete(&[], "vi", &none, true, 5).contains(&"việt nam".to_string()));
+        // Spelled in the requested style
+        assert!(complete(&[], "hoa", &none, false, 5).contains(&"hòa".to_string()));
+        assert!(!complete(&[], "hoa", &none, false, 5).contains(&"hoà".to_string()));
+    }
+
+    #[test]
+    fn test_learn() {
+        let mut phrases = Phrases::new();
+        phrases.learn(&["Việt"]);
+        assert!(phrases.is_empty());
+        phrases.learn(&["cộng", "hoà", "xã"]);
+        assert_eq!(phrases.count("hoà xã"), 1);
+        assert_eq!(phrases.count("cộng hoà xã"), 1);
+        assert_eq!(phrases.count("cộng hoà"), 0);
+        phrases.learn(&["Hoà", "Xã"]);
+        assert_eq!(phrases.count("hoà xã"), 2);
+        assert_eq!(phrases.len(), 2);
+        phrases.clear();
+        assert!(phrases.is_empty());
+    }
+
+    #[test]
+    fn test_learn_evicts_least_used() {
+        let mut phrases = Phrases::new();
+        phrases.learn(&["việt", "nam"]);
+        phrases.learn(&["việt", "nam"]);
+        for i in 0..MAX_PHRASES {
+            phrases.learn(&["số", &i.to_string()]);
+        }
+        assert_eq!(phrases.len(), MAX_PHRASES);
+        // Used twice, kept; the oldest of the phrases used once is gone
+        assert_eq!(phrases.count("việt nam"), 2);
+        assert_eq!(phrases.count("số 0"), 0);
+        assert_eq!(phrases.count("số 1"), 1);
+        let last = format!("số {}", MAX_PHRASES - 1);
+        assert_eq!(phrases.count(&last), 1);
+    }
+
+    #[test]
+    fn test_predict_next() {
+        let mut phrases = Phrases::new();
+        phrases.learn(&["Việt", "Nam"]);
+        phrases.learn(&["hôm", "nay"]);
+        assert_eq!(complete(&["Việt"], "", &phrases, true, 1), ["Nam"]);
+        assert_eq!(complete(&["việt"], "n", &phrases, true, 1), ["nam"]);
+        assert_eq!(complete(&["xin", "hôm"], "n", &phrases, true, 1), ["nay"]);
+        // Dictionary syllables follow the learned ones
+        let completions = complete(&["hôm"], "n", &phrases, true, 3);
+        assert_eq!(completions[0], "nay");
+        assert_eq!(completions.len(), 3);
+    }
+
+    #[test]
+    fn test_complete_phrase() {
+        let mut phrases = Phrases::new();
+        phrases.learn(&["Việt", "Nam"]);
+        phrases.learn(&["cộng", "hoà"]);
+        phrases.learn(&["cộng", "hoà", "xã"]);
+        assert_eq!(complete(&[], "Vi", &phrases, true, 1), ["Việt Nam"]);
+        assert_eq!(complete(&[], "viê", &phrases, true, 1), ["việt Nam"]);
+        assert_eq!(complete(&[], "VIỆT", &phrases, true, 1), ["VIỆT Nam"]);
+        // Longer learned phrases first, the typed word is not repeated
+        assert_eq!(
+            complete(&["cộng"], "h", &phrases, true, 2),
+            ["hoà xã", "hoà"]
+        );
+        assert_eq!(
+            complete(&[], "cộng", &phrases, true, 2),
+            ["cộng hoà xã", "cộng hoà"]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Diacritic Restoration
+//!
+//! "toi di hoc" → "tôi đi học". Each plain syllable expands to the accented
+//! syllables of the spellcheck dictionary (`vi_daumoi.dic`/`vi_daucu.dic`)
+//! that strip to it. The most likely sequence is decoded (Viterbi) with the
+//! bundled syllable bigrams (see `ngram`). Fully offline.
+//!
+//! A run of words decodes together while only whitespace separates them;
+//! punctuation and words without a Vietnamese reading start a new run.
+//! Words typed with diacritics are kept and act as context.
+
+use crate::case::match_case;
+use crate::chars::{self, remove_diacritics};
+use crate::ngram;
+use crate::vietnamese_spellcheck;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Ranked alternatives for one word of the input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Restoration {
+    /// Byte range of the word in the input text
+    pub range: Range<usize>,
+    /// Candidates in the input's case, most likely first
+    pub candidates: Vec<String>,
+}
+
+/// Plain syllable → accented dictionary syllables, per style
+static CANDIDATES_MODERN: LazyLock<HashMap<String, Vec<&'static str>>> =
+    LazyLock::new(|| candidate_index(true));
+static CANDIDATES_TRADITIONAL: LazyLock<HashMap<String, Vec<&'static str>>> =
+    LazyLock::new(|| candidate_index(false));
+
+fn candidate_index(modern: bool) -> HashMap<String, Vec<&'static str>> {
+    let mut index: HashMap<String, Vec<&'static str>> = HashMap::new();
+    for &word in vietnamese_spellcheck::words(modern) {
+        if ngram::is_syllable(word) {
+            index.entry(remove_diacritics(word)).or_default().push(word);
+        }
+    }
+    for candidates in index.values_mut() {
+        candidates.sort_unstable();
+    }
+    index
+}
+
+/// Restore diacritics, keeping the most likely reading of every word
+///
+/// Words that already carry diacritics and words with no Vietnamese
+/// reading (English, numbers) are kept unchanged.
+pub fn restore_diacritics(text: &str, modern: bool) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut last = 0;
+    for r in restore_alternatives(text, modern) {
+        out.push_str(&text[last..r.range.start]);
+        out.push_str(&r.candidates[0]);
+        last = r.range.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// A word of a run: its readings, or itself as context
+struct Slot {
+    range: Range<usize>,
+    /// Lowercase readings
+    readings: Vec<String>,
+    /// Plain word to restore, or a word typed with diacritics
+    restore: bool,
+}
+
+/// Ranked alternatives for every plain word that has Vietnamese readings
+///
+/// The first candidate of each word is the restored text. The others are
+/// ranked by the best sentence they appear in.
+pub fn restore_alternatives(text: &str, modern: bool) -> Vec<Restoration> {
+    let index = if modern {
+        &*CANDIDATES_MODERN
+    } else {
+        &*CANDIDATES_TRADITIONAL
+    };
+    let mut result = Vec::new();
+    let mut run: Vec<Slot> = Vec::new();
+    let mut start = None;
+    let mut last_end = 0;
+    let boundaries = text
+        .char_indices()
+        .chain(std::iter::once((text.len(), '\0')));
+    for (i, c) in boundaries {
+        if i < text.len() && chars::parse_char(c).is_some() {
+            start.get_or_insert(i);
+            continue;
+        }
+        let Some(s) = start.take() else {
+            continue;
+        };
+        let word = &text[s..i];
+        if !text[last_end..s].chars().all(char::is_whitespace) {
+            decode(&mut run, text, &mut result);
+        }
+        last_end = i;
+        let plain = word.is_ascii();
+        let options = if plain {
+            index.get(&word.to_lowercase())
+        } else {
+            None
+        };
+        match options {
+            Some(options) => run.push(Slot {
+                range: s..i,
+                readings: options.iter().map(|o| o.to_string()).collect(),
+                restore: true,
+            }),
+            None if !plain && ngram::is_known(word) => run.push(Slot {
+                range: s..i,
+                readings: vec![word.to_lowercase()],
+                restore: false,
+            }),
+            None => decode(&mut run, text, &mut result),
+        }
+    }
+    decode(&mut run, text, &mut result);
+    result
+}
+
+/// Rank the readings of a run, then clear it
+///
+/// Each reading scores the log probability of the best sequence through
+/// it: forward (Viterbi) plus backward best scores. The top reading of
+/// every word is then the decoded sequence.
+fn decode(run: &mut Vec<Slot>, text: &str, result: &mut Vec<Restoration>) {
+    let ngrams = ngram::bundled();
+    // Best score reaching `word` from the previous word's readings
+    let from = |scores: &[f32], prev: &[String], word: &str| {
+        scores
+            .iter()
+            .zip(prev)
+            .map(|(score, p)| score + ngrams.log_prob(Some(p), word))
+            .fold(f32::NEG_INFINITY, f32::max)
+    };
+    // Best score of the rest of the run after `word`
+    let to = |scores: &[f32], next: &[String], word: &str| {
+        scores
+            .iter()
+            .zip(next)
+            .map(|(score, n)| score + ngrams.log_prob(Some(word), n))
+            .fold(f32::NEG_INFINITY, f32::max)
+    };
+
+    let mut forward: Vec<Vec<f32>> = Vec::with_capacity(run.len());
+    for (i, slot) in run.iter().enumerate() {
+        let scores = slot
+            .readings
+            .iter()
+            .map(|r| match i {
+                0 => ngrams.log_prob(None, r),
+                _ => from(&forward[i - 1], &run[i - 1].readings, r),
+            })
+            .collect();
+        forward.push(scores);
+    }
+    let mut backward: Vec<Vec<f32>> = vec![Vec::new(); run.len()];
+    for i in (0..run.len()).rev() {
+        backward[i] = run[i]
+            .readings
+            .iter()
+            .map(|r| match run.get(i + 1) {
+                None => 0.0,
+                Some(next) => to(&backward[i + 1], &next.readings, r),
+            })
+            .collect();
+    }
+
+    for (i, slot) in run.drain(..).enumerate() {
+        if !slot.restore {
+            continue;
+        }
+        let mut ranked: Vec<(f32, u32, String)> = slot
+            .readings
+            .into_iter()
+            .zip(forward[i].iter().zip(&backward[i]))
+            .map(|(r, (f, b))| (f + b, ngrams.count(&r), r))
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.0.total_cmp(&a.0)
+                .then(b.1.cmp(&a.1))
+                .then_with(|| a.2.cmp(&b.2))
+        });
+        let word = &text[slot.range.clone()];
+        result.push(Restoration {
+            range: slot.range,
+            candidates: ranked.iter().map(|(_, _, r)| match_case(word, r)).collect(),
+        });
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_sentences() {
+        let cases = [
+            ("toi di hoc", "tôi đi học"),
+            ("xin chao cac ban", "xin chào các bạn"),
+            ("nguoi viet nam", "người việt nam"),
+            ("cam on ban rat nhieu", "cảm ơn bạn rất nhiều"),
+            ("hom nay troi dep", "hôm nay trời đẹp"),
+            ("khong biet", "không biết"),
+            ("ban co the giup toi khong", "bạn có thể giúp tôi không"),
+            ("tep tin khong ton tai", "tệp tin không tồn tại"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(restore_diacritics(input, true), expected);
+        }
+    }
+
+    #[test]
+    fn test_restore_context() {
+        // Same plain word, read from its neighbours
+        assert_eq!(restore_diacritics("cac ban", true), "các bạn");
+        assert_eq!(restore_diacritics("phien ban", true), "phiên bản");
+        // Accented words are context too
+        assert_eq!(restore_diacritics("phiên ban", true), "phiên bản");
+        // Punctuation ends the context
+        assert_eq!(restore_diacritics("phiên, ban", true), "phiên, bạn");
+    }
+
+    #[test]
+    fn test_restore_keeps_case_and_punctuation() {
+        assert_eq!(
+            restore_diacritics("Khong biet, KHONG BIET.", true),
+            "Không biết, KHÔNG BIẾT."
+        );
+        // Accented and unknown words are kept
+        assert_eq!(restore_diacritics("tôi di hello", true), "tôi đi hello");
+    }
+
+    #[test]
+    fn test_restore_style() {
+        let modern = &restore_alternatives("hoa", true)[0].candidates;
+        let traditional = &restore_alternatives("hoa", false)[0].candidates;
+        assert!(modern.contains(&"hoà".to_string()));
+        assert!(!modern.contains(&"hòa".to_string()));
+        assert!(traditional.contains(&"hòa".to_string()));
+        assert!(!traditional.contains(&"hoà".to_string()));
+    }
+
+    #[test]
+    fn test_alternatives() {
+        let text = "toi di hoc";
+        let alternatives = restore_alternatives(text, true);
+        assert_eq!(alternatives.len(), 3);
+        assert_eq!(&text[alternatives[2].range.clone()], "hoc");
+        assert_eq!(alternatives[2].candidates.len(), 4);
+        assert_eq!(alternatives[2].candidates[0], "học");
+        assert!(alternatives[2].candidates.contains(&"hóc".to_string()));
+        assert_eq!(alternatives[0].candidates[0], "tôi");
+        assert_eq!(alternatives[1].candidates[0], "đi");
+    }
+}
@@ -3,16 +3,29 @@
 //! This crate provides Vietnamese language data, dictionaries, and
 //! data structures for the input method engine.
 
+pub mod case;
 pub mod chars;
+pub mod charset;
+pub mod completion;
 pub mod constants;
+pub mod diacritics;
 pub mod english_dict;
 pub mod keys;
+pub mod ngram;
+pub mod orthography;
+pub mod slug;
+pub mod suggest;
 pub mod telex_doubles;
 pub mod vietnamese_spellcheck;
 pub mod vowel;
 
 // Re-export commonly used items for convenience
-pub use chars::{get_d, mark, to_char, tone};
+pub use case::{convert_case, Case};
+pub use chars::{get_d, mark, remove_diacritics, to_char, tone};
 pub use constants::*;
+pub use diacritics::{restore_alternatives, restore_diacritics, Restoration};
 pub use keys::{is_break, is_letter, is_vowel};
+pub use orthography::{normalize_tone_style, Normalized};
+pub use slug::{slugify, slugify_with, SlugOptions};
+pub use suggest::suggest;
 pub use vowel::{Modifier, Phonology, Role, Vowel};
@@ -0,0 +1,349 @@
+//! Syllable Frequencies
+//!
+//! Unigram and bigram counts of Vietnamese syllables, for ranking diacritic
+//! restoration, completion and suggestions. The bundled table
+//! (`dictionaries/vi_ngrams.txt`) is counted from the Vietnamese translations
+//! of free software message catalogs, the Vim tutor and a short everyday text
+//! (`corpus/vi_everyday.txt`); its header records the exact sources. Rebuild
+//! it with `cargo run -p data --example ngrams`.
+//!
+//! Only dictionary syllables are counted. Bigrams pair syllables separated by
+//! whitespace alone: punctuation and other words break the chain.
+//!
+//! Table format, one syllable per line, most frequent first:
+//!
+//! ```text
+//! tôi 3052 đi:460 là:360 không:240
+//! ```
+//!
+//! Syllables are keyed without tone position ("hoà" and "hòa" share a
+//! count), so one table serves modern and traditional output.
+
+use crate::chars::{self, mark};
+use crate::vietnamese_spellcheck;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+const BUNDLED: &str = include_str!("dictionaries/vi_ngrams.txt");
+
+/// Absolute discount taken from every seen bigram (Kneser-Ney style)
+const DISCOUNT: f32 = 0.75;
+/// Add-α smoothing of unigrams, so unseen syllables keep a small share
+const ALPHA: f32 = 0.5;
+
+static NGRAMS: LazyLock<Ngrams> = LazyLock::new(|| Ngrams::parse(BUNDLED));
+
+/// Dictionary syllables by key, per style
+static SYLLABLES: LazyLock<HashMap<String, &'static str>> = LazyLock::new(|| syllables(true));
+static SYLLABLES_TRADITIONAL: LazyLock<HashMap<String, &'static str>> =
+    LazyLock::new(|| syllables(false));
+
+fn syllables(use_modern: bool) -> HashMap<String, &'static str> {
+    vietnamese_spellcheck::words(use_modern)
+        .iter()
+        .filter(|w| is_syllable(w))
+        .map(|&w| (key(w), w))
+        .collect()
+}
+
+/// Counts of one syllable
+#[derive(Clone, Debug, Default)]
+struct Entry {
+    /// Spelling as stored in the table
+    word: String,
+    count: u32,
+    /// Following syllables (keys) with pair counts, most frequent first
+    next: Vec<(String, u32)>,
+    /// Sum of `next` counts
+    next_total: u32,
+}
+
+/// Syllable unigram and bigram counts
+#[derive(Clone, Debug, Default)]
+pub struct Ngrams {
+    entries: HashMap<String, Entry>,
+    total: u64,
+}
+
+/// The bundled table
+pub fn bundled() -> &'static Ngrams {
+    &NGRAMS
+}
+
+impl Ngrams {
+    /// Empty table, to count text into
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a table (see module docs); `#` lines are comments
+    ///
+    /// Malformed fields are skipped.
+    pub fn parse(text: &str) -> Self {
+        let mut ngrams = Self::new();
+        for line in text.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(word), Some(Ok(count))) =
+                (fields.next(), fields.next().map(str::parse::<u32>))
+            else {
+                continue;
+            };
+            let entry = ngrams.entries.entry(key(word)).or_default();
+            entry.word = word.to_string();
+            entry.count += count;
+            ngrams.total += count as u64;
+            for field in fields {
+                let Some((next, n)) = field.split_once(':') else {
+                    continue;
+                };
+                if let Ok(n) = n.parse::<u32>() {
+                    entry.next.push((key(next), n));
+                    entry.next_total += n;
+                }
+            }
+        }
+        for entry in ngrams.entries.values_mut() {
+            entry
+                .next
+                .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        }
+        ngrams
+    }
+
+    /// Count the dictionary syllables of `text`, each occurrence `weight` times
+    ///
+    /// Case is ignored. Tokens that aren't dictionary syllables (English,
+    /// numbers, typos) are skipped and break the bigram chain, as does
+    /// punctuation around a token.
+    pub fn add_text(&mut self, text: &str, weight: u32) {
+        let mut prev: Option<String> = None;
+        for token in text.split_whitespace() {
+            let core = token.trim_matches(|c: char| !c.is_alphanumeric());
+            let lower = core.to_lowercase();
+            let Some(&word) = SYLLABLES.get(&key(&lower)).filter(|_| is_syllable(&lower)) else {
+                prev = None;
+                continue;
+            };
+            if !token.starts_with(core) {
+                prev = None;
+            }
+            let k = key(word);
+            let entry = self.entries.entry(k.clone()).or_default();
+            entry.word = word.to_string();
+            entry.count += weight;
+            self.total += weight as u64;
+            if let Some(p) = prev.take() {
+                let entry = self.entries.get_mut(&p).expect("counted before");
+                entry.next_total += weight;
+                match entry.next.iter_mut().find(|(n, _)| *n == k) {
+                    Some((_, n)) => *n += weight,
+                    None => entry.next.push((k.clone(), weight)),
+                }
+            }
+            if token.ends_with(core) {
+                prev = Some(k);
+            }
+        }
+    }
+
+    /// Write the table, dropping pairs seen fewer than `min_pair` times
+    pub fn to_text(&self, min_pair: u32) -> String {
+        let mut entries: Vec<&Entry> = self.entries.values().collect();
+        entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+        let mut out = String::new();
+        for entry in entries {
+            out.push_str(&format!("{} {}", entry.word, entry.count));
+            let mut next: Vec<&(String, u32)> =
+                entry.next.iter().filter(|(_, n)| *n >= min_pair).collect();
+            next.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            for (k, n) in next {
+                let word = self.entries.get(k).map_or(k.as_str(), |e| &e.word);
+                out.push_str(&format!(" {}:{}", word, n));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Occurrences of a syllable, either tone style, any case
+    pub fn count(&self, syllable: &str) -> u32 {
+        self.entry(syllable).map_or(0, |e| e.count)
+    }
+
+    /// Occurrences of `second` right after `first`
+    pub fn pair(&self, first: &str, second: &str) -> u32 {
+        self.entry(first).map_or(0, |e| self.pair_in(e, second))
+    }
+
+    /// Syllables seen after `syllable` with their pair counts, most frequent
+    /// first, spelled as in the table (modern style)
+    pub fn next(&self, syllable: &str) -> Vec<(&str, u32)> {
+        let Some(entry) = self.entry(syllable) else {
+            return Vec::new();
+        };
+        entry
+            .next
+            .iter()
+            .filter_map(|(k, n)| Some((self.entries.get(k)?.word.as_str(), *n)))
+            .collect()
+    }
+
+    /// Smoothed log probability of `syllable`, after `prev` if given
+    ///
+    /// Interpolated absolute discounting: a seen pair keeps its count less
+    /// `DISCOUNT`, and what was taken (plus pairs dropped from the table)
+    /// goes to the unigram estimate. Unseen syllables get a small share.
+    pub fn log_prob(&self, prev: Option<&str>, syllable: &str) -> f32 {
+        let vocabulary = self.entries.len().max(1) as f32;
+        let unigram =
+            (self.count(syllable) as f32 + ALPHA) / (self.total as f32 + ALPHA * vocabulary);
+        let Some(entry) = prev.and_then(|p| self.entry(p)).filter(|e| e.count > 0) else {
+            return unigram.ln();
+        };
+        let c = entry.count as f32;
+        let seen = (self.pair_in(entry, syllable) as f32 - DISCOUNT).max(0.0) / c;
+        let backoff = (c - entry.next_total as f32 + DISCOUNT * entry.next.len() as f32) / c;
+        (seen + backoff * unigram).ln()
+    }
+
+    fn pair_in(&self, entry: &Entry, second: &str) -> u32 {
+        let k = key(&second.to_lowercase());
+        entry
+            .next
+            .iter()
+            .find(|(n, _)| *n == k)
+            .map_or(0, |&(_, n)| n)
+    }
+
+    fn entry(&self, syllable: &str) -> Option<&Entry> {
+        self.entries.get(&key(&syllable.to_lowercase()))
+    }
+}
+
+/// A lowercase word made of Vietnamese letters only
+pub(crate) fn is_syllable(word: &str) -> bool {
+    !word.is_empty()
+        && word
+            .chars()
+            .all(|c| !c.is_uppercase() && chars::parse_char(c).is_some())
+}
+
+/// Syllable key without tone position: base letters with their tone
+/// modifiers, then the mark digit ("hòa" and "hoà" → "hoa2")
+pub(crate) fn key(syllable: &str) -> String {
+    let mut key = String::with_capacity(syllable.len() + 1);
+    let mut found = mark::NONE;
+    for c in syllable.chars() {
+        match chars::parse_char(c) {
+            Some(p) if p.stroke => key.push(chars::get_d(false)),
+            Some(p) => {
+                if p.mark != mark::NONE {
+                    found = p.mark;
+                }
+                match chars::to_char(p.key, false, p.tone, mark::NONE) {
+                    Some(base) => key.push(base),
+                    None => key.extend(c.to_lowercase()),
+                }
+            }
+            None => key.extend(c.to_lowercase()),
+        }
+    }
+    if found != mark::NONE {
+        key.push((b'0' + found) as char);
+    }
+    key
+}
+
+/// A dictionary syllable, either tone style, any case
+pub(crate) fn is_known(syllable: &str) -> bool {
+    SYLLABLES.contains_key(&key(&syllable.to_lowercase()))
+}
+
+/// A syllable as the dictionary of one style spells it ("hoà" → "hòa")
+pub(crate) fn spell(syllable: &str, use_modern: bool) -> Option<&'static str> {
+    let syllables = if use_modern {
+        &*SYLLABLES
+    } else {
+        &*SYLLABLES_TRADITIONAL
+    };
+    syllables.get(&key(&syllable.to_lowercase())).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key() {
+        assert_eq!(key("hòa"), "hoa2");
+        assert_eq!(key("hoà"), "hoa2");
+        assert_eq!(key("Đường"), "đương2");
+        assert_eq!(key("tôi"), "tôi");
+    }
+
+    #[test]
+    fn test_spell() {
+        assert_eq!(spell("hoà", false), Some("hòa"));
+        assert_eq!(spell("Hòa", true), Some("hoà"));
+        assert_eq!(spell("hello", true), None);
+    }
+
+    #[test]
+    fn test_add_text() {
+        let mut ngrams = Ngrams::new();
+        ngrams.add_text("Tôi đi học. Tôi đi làm, hello đi chơi", 2);
+        assert_eq!(ngrams.count("tôi"), 4);
+        assert_eq!(ngrams.count("đi"), 6);
+        assert_eq!(ngrams.pair("tôi", "đi"), 4);
+        assert_eq!(ngrams.pair("đi", "học"), 2);
+        // Punctuation and unknown words break pairs
+        assert_eq!(ngrams.pair("học", "tôi"), 0);
+        assert_eq!(ngrams.pair("làm", "hello"), 0);
+        assert_eq!(ngrams.pair("hello", "đi"), 0);
+        assert_eq!(ngrams.count("hello"), 0);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut ngrams = Ngrams::new();
+        ngrams.add_text("hoà bình, hòa bình; tôi đi học đi", 1);
+        let text = ngrams.to_text(2);
+        assert_eq!(text.lines().next(), Some("bình 2"));
+        assert!(text.contains("hoà 2 bình:2"));
+        assert!(text.contains("\nđi 2\n"));
+
+        let parsed = Ngrams::parse(&text);
+        assert_eq!(parsed.count("hòa"), 2);
+        assert_eq!(parsed.pair("hòa", "bình"), 2);
+        // Pairs under the minimum are dropped
+        assert_eq!(parsed.pair("đi", "học"), 0);
+        assert_eq!(parsed.next("hoà"), [("bình", 2)]);
+    }
+
+    #[test]
+    fn test_log_prob() {
+        let ngrams = Ngrams::parse("tôi 10 đi:6 là:2\nđi 8\ntới 20\nlà 2\n");
+        // Context beats a more frequent syllable
+        assert!(ngrams.log_prob(Some("tôi"), "đi") > ngrams.log_prob(Some("tôi"), "tới"));
+        assert!(ngrams.log_prob(None, "tới") > ngrams.log_prob(None, "đi"));
+        // Unseen syllables stay possible
+        assert!(ngrams.log_prob(Some("tôi"), "xyz").is_finite());
+        // Sums to one over the table's syllables
+        let total: f32 = ["tôi", "đi", "tới", "là"]
+            .iter()
+            .map(|w| ngrams.log_prob(Some("tôi"), w).exp())
+            .sum();
+        assert!((total - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_bundled() {
+        let ngrams = bundled();
+        assert!(ngrams.count("không") > ngrams.count("khống"));
+        assert!(ngrams.pair("việt", "nam") > 0);
+        assert_eq!(ngrams.count("hoà"), ngrams.count("hòa"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Tone Style Normalization
+//!
+//! Rewrites a whole text to one tone placement style:
+//! modern "hoà, thuý, khoẻ" or traditional "hòa, thúy, khỏe".
+//! The mark position comes from `Phonology::find_tone_position`, the same
+//! rule the engine uses while typing. Only words found in one of the
+//! embedded dictionaries are touched, so English and names stay as written.
+
+use crate::chars::{self, mark, tone, ParsedChar};
+use crate::keys;
+use crate::vietnamese_spellcheck::words;
+use crate::vowel::{Modifier, Phonology, Vowel};
+
+/// Result of `normalize_tone_style`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Normalized {
+    pub text: String,
+    /// Number of words whose mark moved
+    pub changes: usize,
+}
+
+/// Move every mark in `text` to its `modern` (or traditional) position
+///
+/// Example: `normalize_tone_style("Hòa bình, sức khỏe", true)` →
+/// text "Hoà bình, sức khoẻ", 2 changes.
+pub fn normalize_tone_style(text: &str, modern: bool) -> Normalized {
+    let mut out = String::with_capacity(text.len());
+    let mut changes = 0;
+    let mut word: Vec<(char, ParsedChar)> = Vec::new();
+
+    let mut flush = |word: &mut Vec<(char, ParsedChar)>, out: &mut String| {
+        match restyle(word, modern) {
+            Some(restyled) => {
+                changes += 1;
+                out.push_str(&restyled);
+            }
+            None => out.extend(word.iter().map(|&(c, _)| c)),
+        }
+        word.clear();
+    };
+    // Inside a word with combining marks: decomposed text is left alone
+    let mut decomposed = false;
+    for c in text.chars() {
+        match chars::parse_char(c) {
+            Some(_) if decomposed => out.push(c),
+            Some(p) => word.push((c, p)),
+            None if chars::is_combining(c) => {
+                decomposed = true;
+                out.extend(word.drain(..).map(|(c, _)| c));
+                out.push(c);
+            }
+            None => {
+                decomposed = false;
+                flush(&mut word, &mut out);
+                out.push(c);
+            }
+        }
+    }
+    flush(&mut word, &mut out);
+    Normalized { text: out, changes }
+}
+
+/// The word with its mark moved, None if it is already in place or not a
+/// Vietnamese syllable
+fn restyle(word: &[(char, ParsedChar)], modern: bool) -> Option<String> {
+    let (marked, value) = word
+        .iter()
+        .enumerate()
+        .find(|(_, (_, p))| p.mark != mark::NONE)
+        .map(|(i, (_, p))| (i, p.mark))?;
+    let lower: String = word.iter().flat_map(|(c, _)| c.to_lowercase()).collect();
+    if !words(true).contains(lower.as_str()) && !words(false).contains(lower.as_str()) {
+        return None;
+    }
+
+    let vowels: Vec<Vowel> = word
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, p))| keys::is_vowel(p.key))
+        .map(|(pos, (_, p))| {
+            let modifier = match p.tone {
+                tone::CIRCUMFLEX => Modifier::Circumflex,
+                tone::HORN => Modifier::Horn,
+                _ => Modifier::None,
+            };
+            Vowel::new(p.key, modifier, pos)
+        })
+        .collect();
+    let last_vowel = vowels.last()?.pos;
+    let has_final = word[last_vowel + 1..]
+        .iter()
+        .any(|(_, p)| keys::is_consonant(p.key));
+    let has_qu = word
+        .windows(2)
+        .find(|w| w[1].1.key == keys::U)
+        .is_some_and(|w| w[0].1.key == keys::Q);
+    let has_gi = word.len() >= 3
+        && word[0].1.key == keys::G
+        && word[1].1.key == keys::I
+        && keys::is_vowel(word[2].1.key);
+    let target = Phonology::find_tone_position(&vowels, has_final, modern, has_qu, has_gi);
+    if target == marked {
+        return None;
+    }
+
+    word.iter()
+        .enumerate()
+        .map(|(i, &(c, p))| {
+            let new_mark = match i {
+                _ if i == target => value,
+                _ if i == marked => mark::NONE,
+                _ => return Some(c),
+            };
+            chars::to_char(p.key, p.caps, p.tone, new_mark)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_modern() {
+        let result = normalize_tone_style("hòa thúy khỏe", true);
+        assert_eq!(result.text, "hoà thuý khoẻ");
+        assert_eq!(result.changes, 3);
+    }
+
+    #[test]
+    fn test_to_traditional() {
+        let result = normalize_tone_style("Hoà bình, sức KHOẺ!", false);
+        assert_eq!(result.text, "Hòa bình, sức KHỎE!");
+        assert_eq!(result.changes, 2);
+    }
+
+    #[test]
+    fn test_unchanged() {
+        // Already in style, marks that never move, final consonants
+        let text = "hoàng quý già toán thuở";
+        let result = normalize_tone_style(text, true);
+        assert_eq!(result.text, text);
+        assert_eq!(result.changes, 0);
+        // Not Vietnamese: left as written
+        let result = normalize_tone_style("Pokémon café hòa", true);
+        assert_eq!(result.text, "Pokémon café hoà");
+        assert_eq!(result.changes, 1);
+        // Decomposed words are skipped whole
+        let text = "ho\u{0300}a\u{0301}";
+        assert_eq!(normalize_tone_style(text, true).text, text);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let modern = "Mùa thu hoà bình, hoạ sĩ tuỳ ý thuỷ thủ khoẻ mạnh";
+        let traditional = normalize_tone_style(modern, false);
+        assert_eq!(
+            traditional.text,
+            "Mùa thu hòa bình, họa sĩ tùy ý thủy thủ khỏe mạnh"
+        );
+        assert_eq!(traditional.changes, 5);
+        assert_eq!(normalize_tone_style(&traditional.text, true).text, modern);
+    }
+}
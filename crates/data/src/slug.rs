@@ -0,0 +1,166 @@
+//! Slug Generation
+//!
+//! Turns text into URL/filename-safe slugs: "Tiếng Việt có dấu!" → "tieng-viet-co-dau".
+//! Vietnamese diacritics are stripped with `chars::remove_diacritics`;
+//! everything that isn't a letter or digit becomes a single separator.
+
+use crate::chars::remove_diacritics;
+
+/// Slug formatting options
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlugOptions {
+    /// Placed between words (may be empty or several characters)
+    pub separator: String,
+    /// Lowercase the result
+    pub lowercase: bool,
+    /// Keep only ASCII: common Latin letters are transliterated (ñ → n,
+    /// ß → ss), other scripts are dropped. When off, letters and digits
+    /// of any script are kept.
+    pub ascii_only: bool,
+}
+
+impl Default for SlugOptions {
+    fn default() -> Self {
+        Self {
+            separator: "-".to_string(),
+            lowercase: true,
+            ascii_only: true,
+        }
+    }
+}
+
+/// Latin letters outside Vietnamese and their ASCII spelling
+const LATIN_FALLBACK: &[(char, &str)] = &[
+    ('ä', "a"),
+    ('å', "a"),
+    ('ā', "a"),
+    ('ą', "a"),
+    ('æ', "ae"),
+    ('ç', "c"),
+    ('č', "c"),
+    ('ć', "c"),
+    ('ð', "d"),
+    ('ď', "d"),
+    ('ë', "e"),
+    ('ē', "e"),
+    ('ę', "e"),
+    ('ě', "e"),
+    ('ğ', "g"),
+    ('î', "i"),
+    ('ï', "i"),
+    ('ī', "i"),
+    ('ı', "i"),
+    ('ł', "l"),
+    ('ñ', "n"),
+    ('ń', "n"),
+    ('ň', "n"),
+    ('ö', "o"),
+    ('ø', "o"),
+    ('ō', "o"),
+    ('ő', "o"),
+    ('œ', "oe"),
+    ('ř', "r"),
+    ('ß', "ss"),
+    ('ś', "s"),
+    ('š', "s"),
+    ('ş', "s"),
+    ('ť', "t"),
+    ('þ', "th"),
+    ('û', "u"),
+    ('ü', "u"),
+    ('ū', "u"),
+    ('ů', "u"),
+    ('ű', "u"),
+    ('ÿ', "y"),
+    ('ž', "z"),
+    ('ź', "z"),
+    ('ż', "z"),
+];
+
+/// ASCII spelling of a Latin letter, None for other characters
+fn latin_fallback(c: char) -> Option<String> {
+    let lower = c.to_lowercase().next()?;
+    let (_, ascii) = LATIN_FALLBACK.iter().find(|(l, _)| *l == lower)?;
+    Some(if c.is_uppercase() {
+        ascii.to_uppercase()
+    } else {
+        ascii.to_string()
+    })
+}
+
+/// Slug with default options: lowercase, "-" separator, ASCII only
+pub fn slugify(s: &str) -> String {
+    slugify_with(s, &SlugOptions::default())
+}
+
+/// Slug with custom options
+///
+/// Example: `slugify_with("Hà Nội 2024", &SlugOptions { separator: "_".into(), ..Default::default() })`
+/// → "ha_noi_2024"
+pub fn slugify_with(s: &str, options: &SlugOptions) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut pending_separator = false;
+
+    for c in remove_diacritics(s).chars() {
+        let part = if c.is_ascii_alphanumeric() || (!options.ascii_only && c.is_alphanumeric()) {
+            c.to_string()
+        } else if let Some(ascii) = options.ascii_only.then(|| latin_fallback(c)).flatten() {
+            ascii
+        } else {
+            // Other scripts are dropped in place; anything else separates words
+            pending_separator |= !c.is_alphanumeric();
+            continue;
+        };
+        if pending_separator && !out.is_empty() {
+            out.push_str(&options.separator);
+        }
+        pending_separator = false;
+        if options.lowercase {
+            out.extend(part.chars().flat_map(char::to_lowercase));
+        } else {
+            out.push_str(&part);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify_default() {
+        assert_eq!(slugify("Tiếng Việt có dấu"), "tieng-viet-co-dau");
+        assert_eq!(slugify("  Đường   Lê Lợi, Q.1!  "), "duong-le-loi-q-1");
+        assert_eq!(slugify("---"), "");
+    }
+
+    #[test]
+    fn test_slugify_options() {
+        let keep_case = SlugOptions {
+            separator: "_".to_string(),
+            lowercase: false,
+            ..SlugOptions::default()
+        };
+        assert_eq!(slugify_with("Hà Nội 2024", &keep_case), "Ha_Noi_2024");
+
+        let no_separator = SlugOptions {
+            separator: String::new(),
+            ..SlugOptions::default()
+        };
+        assert_eq!(slugify_with("Sài Gòn", &no_separator), "saigon");
+    }
+
+    #[test]
+    fn test_slugify_other_scripts() {
+        assert_eq!(slugify("Straße Ñandú Łódź"), "strasse-nandu-lodz");
+        assert_eq!(slugify("Hà Nội 東京"), "ha-noi");
+
+        let unicode = SlugOptions {
+            ascii_only: false,
+            ..SlugOptions::default()
+        };
+        assert_eq!(slugify_with("Hà Nội 東京", &unicode), "ha-noi-東京");
+        assert_eq!(slugify_with("Straße", &unicode), "straße");
+    }
+}
@@ -0,0 +1,256 @@
+//! Spelling Suggestions
+//!
+//! Ranked corrections for words that fail `vietnamese_spellcheck`:
+//! "nghành" → "ngành", "hòa" → "hoà" (modern style).
+//!
+//! Distance is an edit distance over letters where diacritics are cheap:
+//! a syllable is split into its letters with modifiers (â, ơ, đ) and its
+//! mark (sắc, huyền, ...) with the position it sits on. Changing a modifier
+//! or a mark, or moving the mark to another vowel, costs less than changing
+//! a letter. Equal distances rank by the longest common start with the
+//! word (typos are rarely in the first letters), then by how common the
+//! word is (`ngram` counts).
+//!
+//! Dictionary words are split once and grouped by length, so a query only
+//! compares words that can be within `MAX_DISTANCE`.
+
+use crate::case::match_case;
+use crate::chars::{self, mark};
+use crate::keys;
+use crate::ngram;
+use crate::vietnamese_spellcheck::words;
+use std::sync::LazyLock;
+
+/// Inserting, deleting or replacing a letter
+const LETTER_COST: f32 = 1.0;
+/// Letters commonly confused in spelling (c/t, s/x, i/y, d/r)
+const SIMILAR_COST: f32 = 0.6;
+/// Same letter with another modifier: a/â/ă, o/ô/ơ, u/ư, e/ê, d/đ
+const MODIFIER_COST: f32 = 0.3;
+/// Different mark, or a mark added or removed
+const MARK_COST: f32 = 0.3;
+/// Same mark on another vowel (hòa ↔ hoà)
+const MARK_POSITION_COST: f32 = 0.1;
+
+/// Candidates further than this are not suggested
+pub const MAX_DISTANCE: f32 = 2.0;
+
+const SIMILAR: &[(u16, u16)] = &[
+    (keys::C, keys::T),
+    (keys::S, keys::X),
+    (keys::I, keys::Y),
+    (keys::D, keys::R),
+];
+
+/// One letter without its mark
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Letter { key: u16, tone: u8, stroke: bool },
+    Other(char),
+}
+
+/// A word split for comparison
+#[derive(Clone)]
+struct Split {
+    units: Vec<Unit>,
+    mark: u8,
+    /// Which vowel carries the mark (0 = first)
+    mark_at: usize,
+}
+
+fn split(word: &str) -> Split {
+    let mut units = Vec::with_capacity(word.len());
+    let (mut found, mut mark_at, mut vowels) = (mark::NONE, 0, 0);
+    for c in word.chars() {
+        match chars::parse_char(c) {
+            Some(p) => {
+                if p.mark != mark::NONE {
+                    found = p.mark;
+                    mark_at = vowels;
+                }
+                vowels += usize::from(keys::is_vowel(p.key));
+                units.push(Unit::Letter {
+                    key: p.key,
+                    tone: p.tone,
+                    stroke: p.stroke,
+                });
+            }
+            None => units.extend(c.to_lowercase().map(Unit::Other)),
+        }
+    }
+    Split {
+        units,
+        mark: found,
+        mark_at,
+    }
+}
+
+fn substitution_cost(a: Unit, b: Unit) -> f32 {
+    match (a, b) {
+        _ if a == b => 0.0,
+        (Unit::Letter { key: ka, .. }, Unit::Letter { key: kb, .. }) if ka == kb => MODIFIER_COST,
+        (Unit::Letter { key: ka, .. }, Unit::Letter { key: kb, .. })
+            if SIMILAR.contains(&(ka, kb)) || SIMILAR.contains(&(kb, ka)) =>
+        {
+            SIMILAR_COST
+        }
+        _ => LETTER_COST,
+    }
+}
+
+/// Vietnamese-aware edit distance between two words (case-insensitive)
+///
+/// 0 for equal words; a wrong mark or modifier costs 0.3, a misplaced
+/// mark 0.1, and a wrong letter up to 1.
+pub fn distance(a: &str, b: &str) -> f32 {
+    split_distance(&split(a), &split(b), f32::INFINITY)
+}
+
+/// Distance between split words, or infinity once it exceeds `limit`
+fn split_distance(a: &Split, b: &Split, limit: f32) -> f32 {
+    let marks = if a.mark != b.mark {
+        MARK_COST
+    } else if a.mark != mark::NONE && a.mark_at != b.mark_at {
+        MARK_POSITION_COST
+    } else {
+        0.0
+    };
+    if marks > limit {
+        return f32::INFINITY;
+    }
+
+    let (n, m) = (a.units.len(), b.units.len());
+    if (n.abs_diff(m) as f32) * LETTER_COST + marks > limit {
+        return f32::INFINITY;
+    }
+    let mut prev: Vec<f32> = (0..=m).map(|j| j as f32 * LETTER_COST).collect();
+    let mut row = vec![0.0; m + 1];
+    for i in 1..=n {
+        row[0] = i as f32 * LETTER_COST;
+        for j in 1..=m {
+            row[j] = (prev[j] + LETTER_COST)
+                .min(row[j - 1] + LETTER_COST)
+                .min(prev[j - 1] + substitution_cost(a.units[i - 1], b.units[j - 1]));
+        }
+        if row.iter().fold(f32::INFINITY, |x, &y| x.min(y)) + marks > limit {
+            return f32::INFINITY;
+        }
+        std::mem::swap(&mut prev, &mut row);
+    }
+    prev[m] + marks
+}
+
+/// A dictionary word ready to compare
+struct Indexed {
+    word: &'static str,
+    split: Split,
+    count: u32,
+}
+
+/// Dictionary words by number of units, per style
+static INDEX_MODERN: LazyLock<Vec<Vec<Indexed>>> = LazyLock::new(|| build_index(true));
+static INDEX_TRADITIONAL: LazyLock<Vec<Vec<Indexed>>> = LazyLock::new(|| build_index(false));
+
+fn build_index(use_modern: bool) -> Vec<Vec<Indexed>> {
+    let ngrams = ngram::bundled();
+    let mut index: Vec<Vec<Indexed>> = Vec::new();
+    for &word in words(use_modern) {
+        let split = split(word);
+        let len = split.units.len();
+        if index.len() <= len {
+            index.resize_with(len + 1, Vec::new);
+        }
+        index[len].push(Indexed {
+            word,
+            split,
+            count: ngrams.count(word),
+        });
+    }
+    index
+}
+
+/// Up to `limit` dictionary words close to `word`, best first
+///
+/// The word itself is never suggested. Candidates keep the input's case
+/// ("Nghành" → "Ngành"). `use_modern` picks the orthography style, so
+/// "hòa" suggests "hoà" first in modern style.
+pub fn suggest(word: &str, use_modern: bool, limit: usize) -> Vec<String> {
+    let lower = word.to_lowercase();
+    let target = split(&lower);
+    let common_start = |w: &str| {
+        w.chars()
+            .zip(lower.chars())
+            .take_while(|(a, b)| a == b)
+            .count()
+    };
+    let index = if use_modern {
+        &*INDEX_MODERN
+    } else {
+        &*INDEX_TRADITIONAL
+    };
+    // Each unit of length difference costs a whole letter
+    let reach = (MAX_DISTANCE / LETTER_COST) as usize;
+    let len = target.units.len();
+    let end = (len + reach + 1).min(index.len());
+    let mut scored: Vec<(f32, usize, u32, &str)> = index[len.saturating_sub(reach).min(end)..end]
+        .iter()
+        .flatten()
+        .filter(|e| e.word != lower)
+        .filter_map(|e| {
+            let d = split_distance(&target, &e.split, MAX_DISTANCE);
+            (d <= MAX_DISTANCE).then(|| (d, common_start(e.word), e.count, e.word))
+        })
+        .collect();
+    // Round so float noise doesn't beat the tie-breaks
+    let key = |d: f32| (d * 100.0).round() as u32;
+    scored.sort_by(|a, b| {
+        key(a.0)
+            .cmp(&key(b.0))
+            .then(b.1.cmp(&a.1))
+            .then(b.2.cmp(&a.2))
+            .then(a.3.cmp(b.3))
+    });
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, _, w)| match_case(word, w))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("hoa", "hoa"), 0.0);
+        assert!((distance("hòa", "hoà") - MARK_POSITION_COST).abs() < 1e-6);
+        assert!((distance("ha", "hà") - MARK_COST).abs() < 1e-6);
+        assert!((distance("ha", "hâ") - MODIFIER_COST).abs() < 1e-6);
+        assert!((distance("da", "đa") - MODIFIER_COST).abs() < 1e-6);
+        assert!((distance("nghành", "ngành") - LETTER_COST).abs() < 1e-6);
+        assert!((distance("giặc", "giặt") - SIMILAR_COST).abs() < 1e-6);
+        assert!((distance("Hoà", "hoà")).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_suggest() {
+        assert_eq!(suggest("nghành", true, 3)[0], "ngành");
+        assert_eq!(suggest("hòa", true, 3)[0], "hoà");
+        assert_eq!(suggest("hoà", false, 3)[0], "hòa");
+        assert_eq!(suggest("giặc", true, 5)[0], "giặt");
+        assert_eq!(suggest("thưong", true, 5)[0], "thương");
+        assert!(!suggest("tôi", true, 10).contains(&"tôi".to_string()));
+        assert_eq!(suggest("Nghành", true, 1), vec!["Ngành"]);
+        assert!(suggest("xyzxyzxyz", true, 5).is_empty());
+        assert!(suggest(&"a".repeat(40), true, 5).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_frequency() {
+        // Same distance: the more common word first
+        assert_eq!(suggest("ban", true, 2), ["bạn", "bản"]);
+        assert_eq!(suggest("viêt", true, 2), ["việt", "viết"]);
+        assert_eq!(suggest("khong", true, 1), ["không"]);
+    }
+}
@@ -27,6 +27,15 @@ static DICT_DAUMOI: LazyLock<HashSet<&'static str>> =
 static DICT_DAUCU: LazyLock<HashSet<&'static str>> =
     LazyLock::new(|| parse_dic_to_hashset(DIC_DAUCU));
 
+/// All dictionary words of one orthography style (lowercase)
+pub(crate) fn words(use_modern: bool) -> &'static HashSet<&'static str> {
+    if use_modern {
+        &DICT_DAUMOI
+    } else {
+        &DICT_DAUCU
+    }
+}
+
 /// Check if word starts with foreign consonant (z, w, j, f)
 /// These consonants are not part of standard Vietnamese alphabet
 fn starts_with_foreign_consonant(word: &str) -> bool {
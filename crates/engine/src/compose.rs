@@ -0,0 +1,164 @@
+//! Composition Mode - preedit text and commit events instead of backspaces
+//!
+//! The default output (`Engine::on_key`) edits text already in the
+//! application: delete `backspace` chars, insert `chars`. Input frameworks
+//! with a preedit (IBus, Fcitx5, Wayland text-input) can use
+//! `Engine::on_key_compose` instead: the word being typed stays in the
+//! preedit, shown at the cursor but not part of the document, and text
+//! enters the document only through commits:
+//!
+//! ```text
+//! keys     preedit   commit
+//! v        v
+//! i        vi
+//! e e      viê
+//! j        việ
+//! t        việt
+//! space              "việt "
+//! ```
+//!
+//! The preedit is committed when the engine's word ends (space, punctuation,
+//! a shortcut or auto-restore replacing it) and before any key the
+//! application has to handle itself. Both modes produce the same document
+//! text for the same keystrokes.
+
+use crate::buffer::MAX;
+use crate::engine::{Action, Result};
+use crate::utils;
+use data::keys;
+
+/// How a frontend should draw the preedit
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PreeditHint {
+    /// Plain underline
+    #[default]
+    Underline = 0,
+    /// Not a word auto-restore keeps: ending it now commits the keystrokes
+    /// as typed ("tẽt" → "text")
+    Restore = 1,
+}
+
+/// Output of one key in composition mode, applied in field order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Composition {
+    /// Chars before the cursor to delete from the document: the key edits
+    /// text already committed (backspace into the previous word, "->" → "→")
+    pub delete: usize,
+    /// Text to insert into the document
+    pub commit: String,
+    /// Text being composed, replacing the previous preedit
+    pub preedit: String,
+    /// Cursor position in `preedit`, in chars
+    pub cursor: usize,
+    pub hint: PreeditHint,
+    /// The key was not handled: forward it to the application
+    pub pass_through: bool,
+}
+
+/// Uncommitted text in composition mode
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Preedit {
+    text: Vec<char>,
+}
+
+impl Preedit {
+    pub(crate) fn as_string(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    /// Commit everything, e.g. on focus out
+    pub(crate) fn take(&mut self) -> String {
+        self.text.drain(..).collect()
+    }
+
+    /// Commit everything and let the application handle the key
+    pub(crate) fn pass_through(&mut self) -> Composition {
+        Composition {
+            commit: self.take(),
+            pass_through: true,
+            ..Composition::default()
+        }
+    }
+
+    /// Apply a key result to the preedit
+    ///
+    /// `r` is the key's Send-mode result, `typed` the character the key
+    /// types (None for keys without one), `word_done` whether the engine's
+    /// word ended with this key.
+    pub(crate) fn apply(
+        &mut self,
+        key: u16,
+        shift: bool,
+        r: &Result,
+        typed: Option<char>,
+        word_done: bool,
+    ) -> Composition {
+        let mut out = Composition::default();
+        let send = r.action == Action::Send as u8;
+        if send {
+            let backspace = r.backspace as usize;
+            let kept = self.text.len().saturating_sub(backspace);
+            out.delete = backspace - (self.text.len() - kept);
+            self.text.truncate(kept);
+            let chars = r.chars[..r.count as usize].iter();
+            self.text.extend(chars.filter_map(|&c| char::from_u32(c)));
+        }
+
+        // Where the key's own effect goes when the result doesn't cover it
+        let types_key = match key {
+            keys::DELETE => {
+                if !send && self.text.pop().is_none() {
+                    out.pass_through = true;
+                }
+                false
+            }
+            keys::ESC => {
+                out.pass_through = !send;
+                false
+            }
+            keys::SPACE => !send,
+            _ if send => keys::is_break_ext(key, shift) && !r.key_consumed(),
+            _ => true,
+        };
+        if types_key {
+            match typed {
+                Some(c) => self.text.push(c),
+                None => out.pass_through = true,
+            }
+        }
+
+        // The application sees the key after everything before it
+        if word_done || out.pass_through {
+            out.commit = self.take();
+        } else if self.text.len() > MAX {
+            let excess = self.text.len() - MAX;
+            out.commit = self.text.drain(..excess).collect();
+        }
+        out.cursor = self.text.len();
+        out.preedit = self.as_string();
+        out
+    }
+}
+
+/// Character a key types into the preedit
+pub(crate) fn typed_char(key: u16, caps: bool, shift: bool) -> Option<char> {
+    if let Some(c) = utils::key_to_char_ext(key, caps, shift) {
+        return Some(c);
+    }
+    Some(match key {
+        keys::SPACE => ' ',
+        keys::COMMA => ',',
+        keys::SEMICOLON => ';',
+        keys::MINUS => '-',
+        keys::EQUAL => '=',
+        keys::LBRACKET => '[',
+        keys::RBRACKET => ']',
+        keys::BACKSLASH => '\\',
+        keys::SLASH => '/',
+        keys::QUOTE => '\'',
+        keys::BACKQUOTE => '`',
+        keys::DOT => '.',
+        _ => return None,
+    })
+}
@@ -0,0 +1,495 @@
+//! Engine Config - all settings in one value, with a shared file format
+//!
+//! Frontends used to persist each toggle their own way. `EngineConfig`
+//! holds every setting `Engine` exposes plus the shortcut table, and reads
+//! and writes one text file that all platforms share:
+//!
+//! ```text
+//! # Gõ Nhanh settings
+//! method = telex
+//! modern_tone = true
+//! quick_consonants = f j w
+//! charset = unicode
+//!
+//! [shortcuts]
+//! vn = Việt Nam
+//!
+//! [shortcuts immediate]
+//! -> = →
+//! ```
+//!
+//! Settings are `key = value` lines; missing keys keep their default.
+//! Shortcut sections list `trigger = replacement` lines. Words after
+//! `shortcuts` in the header set options for the whole section:
+//! `immediate` (no word boundary needed), `exact` (no case matching),
+//! `disabled`, and `telex`/`vni`/`viqr` (one input method only).
+//! In replacements `\n` is a newline and `\\` a backslash.
+//!
+//! Custom input methods and the user lexicon have their own files.
+
+use crate::engine::QUICK_CONSONANTS;
+use crate::input::custom::ParseError;
+use crate::shortcut::{CaseMode, InputMethod, Shortcut, TriggerCondition, MAX_REPLACEMENT_LEN};
+use crate::utils;
+use data::charset::Charset;
+use std::io;
+use std::path::Path;
+
+/// Setting keys, in file order (`version` is written by `migrate`)
+pub(crate) const SETTINGS: &[&str] = &[
+    "version",
+    "enabled",
+    "method",
+    "skip_w_shortcut",
+    "bracket_shortcut",
+    "esc_restore",
+    "free_tone",
+    "modern_tone",
+    "english_auto_restore",
+    "lexicon_learning",
+    "auto_capitalize",
+    "allow_foreign_consonants",
+    "completion",
+    "quick_telex",
+    "quick_consonants",
+    "charset",
+];
+
+const METHODS: &[(u8, &str)] = &[(0, "telex"), (1, "vni"), (2, "viqr")];
+
+const CHARSETS: &[(Charset, &str)] = &[
+    (Charset::Unicode, "unicode"),
+    (Charset::Tcvn3, "tcvn3"),
+    (Charset::VniWindows, "vni-windows"),
+    (Charset::Viscii, "viscii"),
+    (Charset::Cp1258, "cp1258"),
+    (Charset::UnicodeNfd, "unicode-nfd"),
+];
+
+/// Every engine setting, see the `Engine::set_*` methods
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineConfig {
+    pub enabled: bool,
+    /// 0 = Telex, 1 = VNI, 2 = VIQR
+    pub method: u8,
+    pub skip_w_shortcut: bool,
+    pub bracket_shortcut: bool,
+    pub esc_restore: bool,
+    pub free_tone: bool,
+    pub modern_tone: bool,
+    pub english_auto_restore: bool,
+    pub lexicon_learning: bool,
+    pub auto_capitalize: bool,
+    pub allow_foreign_consonants: bool,
+    pub completion: bool,
+    pub quick_telex: bool,
+    /// Enabled quick consonant shorthands by letter: f j w (initials), g h k (finals)
+    pub quick_consonants: Vec<char>,
+    pub charset: Charset,
+    pub shortcuts: Vec<Shortcut>,
+}
+
+impl Default for EngineConfig {
+    /// Same settings as `Engine::new`
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            method: 0,
+            skip_w_shortcut: false,
+            bracket_shortcut: false,
+            esc_restore: false,
+            free_tone: false,
+            modern_tone: true,
+            english_auto_restore: false,
+            lexicon_learning: false,
+            auto_capitalize: false,
+            allow_foreign_consonants: false,
+            completion: false,
+            quick_telex: false,
+            quick_consonants: Vec::new(),
+            charset: Charset::Unicode,
+            shortcuts: Vec::new(),
+        }
+    }
+}
+
+/// Whole-config error (line 0, see `ParseError`)
+fn invalid(message: String) -> ParseError {
+    ParseError { line: 0, message }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            '\\' => out.push('\\'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Section header words for a shortcut's options
+fn section_header(s: &Shortcut) -> String {
+    let mut header = String::from("[shortcuts");
+    if s.condition == TriggerCondition::Immediate {
+        header.push_str(" immediate");
+    }
+    if s.case_mode == CaseMode::Exact {
+        header.push_str(" exact");
+    }
+    if !s.enabled {
+        header.push_str(" disabled");
+    }
+    match s.input_method {
+        InputMethod::All => {}
+        InputMethod::Telex => header.push_str(" telex"),
+        InputMethod::Vni => header.push_str(" vni"),
+        InputMethod::Viqr => header.push_str(" viqr"),
+    }
+    header.push(']');
+    header
+}
+
+/// Options of a `[shortcuts ...]` section
+struct Section {
+    condition: TriggerCondition,
+    case_mode: CaseMode,
+    enabled: bool,
+    input_method: InputMethod,
+}
+
+fn parse_section(header: &str) -> Option<Section> {
+    let mut words = header.split_whitespace();
+    if words.next()? != "shortcuts" {
+        return None;
+    }
+    let mut section = Section {
+        condition: TriggerCondition::OnWordBoundary,
+        case_mode: CaseMode::MatchCase,
+        enabled: true,
+        input_method: InputMethod::All,
+    };
+    for word in words {
+        match word {
+            "immediate" => section.condition = TriggerCondition::Immediate,
+            "exact" => section.case_mode = CaseMode::Exact,
+            "disabled" => section.enabled = false,
+            "telex" => section.input_method = InputMethod::Telex,
+            "vni" => section.input_method = InputMethod::Vni,
+            "viqr" => section.input_method = InputMethod::Viqr,
+            _ => return None,
+        }
+    }
+    Some(section)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+impl EngineConfig {
+    /// Check that every value is one the engine accepts
+    ///
+    /// Rejects unknown methods, letters that are not quick consonant
+    /// shorthands, and empty, duplicate, over-long or whitespace triggers.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        if !METHODS.iter().any(|&(id, _)| id == self.method) {
+            return Err(invalid(format!("unknown method {}", self.method)));
+        }
+        for &c in &self.quick_consonants {
+            let key = utils::char_to_key(c);
+            if !c.is_ascii_lowercase() || !QUICK_CONSONANTS.iter().any(|&(k, _, _)| k == key) {
+                return Err(invalid(format!("'{}' is not a quick consonant", c)));
+            }
+        }
+        for (i, s) in self.shortcuts.iter().enumerate() {
+            if s.trigger.is_empty() || s.trigger.chars().any(char::is_whitespace) {
+                return Err(invalid(format!("invalid shortcut trigger {:?}", s.trigger)));
+            }
+            if s.trigger != s.trigger.to_lowercase() {
+                return Err(invalid(format!(
+                    "shortcut trigger {:?} is not lowercase",
+                    s.trigger
+                )));
+            }
+            if s.replacement.chars().count() > MAX_REPLACEMENT_LEN {
+                return Err(invalid(format!("shortcut {:?} is too long", s.trigger)));
+            }
+            if self.shortcuts[..i].iter().any(|o| o.trigger == s.trigger) {
+                return Err(invalid(format!("duplicate shortcut {:?}", s.trigger)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize in the file format
+    pub fn to_text(&self) -> String {
+        let method = METHODS
+            .iter()
+            .find(|&&(id, _)| id == self.method)
+            .map_or("telex", |&(_, name)| name);
+        let charset = CHARSETS
+            .iter()
+            .find(|&&(c, _)| c == self.charset)
+            .map_or("unicode", |&(_, name)| name);
+        let quick: Vec<String> = self.quick_consonants.iter().map(char::to_string).collect();
+
+        let mut out = String::from("# Gõ Nhanh settings\n");
+        out.push_str(&format!("enabled = {}\n", self.enabled));
+        out.push_str(&format!("method = {}\n", method));
+        out.push_str(&format!("skip_w_shortcut = {}\n", self.skip_w_shortcut));
+        out.push_str(&format!("bracket_shortcut = {}\n", self.bracket_shortcut));
+        out.push_str(&format!("esc_restore = {}\n", self.esc_restore));
+        out.push_str(&format!("free_tone = {}\n", self.free_tone));
+        out.push_str(&format!("modern_tone = {}\n", self.modern_tone));
+        out.push_str(&format!(
+            "english_auto_restore = {}\n",
+            self.english_auto_restore
+        ));
+        out.push_str(&format!("lexicon_learning = {}\n", self.lexicon_learning));
+        out.push_str(&format!("auto_capitalize = {}\n", self.auto_capitalize));
+        out.push_str(&format!(
+            "allow_foreign_consonants = {}\n",
+            self.allow_foreign_consonants
+        ));
+        out.push_str(&format!("completion = {}\n", self.completion));
+        out.push_str(&format!("quick_telex = {}\n", self.quick_telex));
+        out.push_str(format!("quick_consonants = {}", quick.join(" ")).trim_end());
+        out.push('\n');
+        out.push_str(&format!("charset = {}\n", charset));
+
+        // One section per option set, sections and triggers in a stable order
+        let mut shortcuts: Vec<(String, &Shortcut)> = self
+            .shortcuts
+            .iter()
+            .map(|s| (section_header(s), s))
+            .collect();
+        shortcuts
+            .sort_by(|a, b| (a.0.len(), &a.0, &a.1.trigger).cmp(&(b.0.len(), &b.0, &b.1.trigger)));
+        let mut current = None;
+        for (header, s) in shortcuts {
+            if current.as_ref() != Some(&header) {
+                out.push_str(&format!("\n{}\n", header));
+                current = Some(header);
+            }
+            out.push_str(&format!("{} = {}\n", s.trigger, escape(&s.replacement)));
+        }
+        out
+    }
+
+    /// Parse the file format; the result is validated
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut config = Self::default();
+        let mut section: Option<Section> = None;
+        for (idx, raw_line) in text.lines().enumerate() {
+            let err = |message: &str| ParseError {
+                line: idx + 1,
+                message: message.to_string(),
+            };
+            let line = raw_line.trim_end_matches('\r');
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = trimmed.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| err("expected `]`"))?;
+                section = Some(parse_section(header).ok_or_else(|| err("unknown section"))?);
+                continue;
+            }
+
+            if let Some(options) = &section {
+                // Replacement is kept as written, spaces included
+                let (trigger, replacement) = line
+                    .trim_start()
+                    .split_once(" = ")
+                    .ok_or_else(|| err("expected `trigger = replacement`"))?;
+                let replacement =
+                    unescape(replacement).ok_or_else(|| err("invalid escape in replacement"))?;
+                let mut shortcut = Shortcut::new(trigger.trim_end(), &replacement);
+                shortcut.condition = options.condition;
+                shortcut.case_mode = options.case_mode;
+                shortcut.enabled = options.enabled;
+                shortcut.input_method = options.input_method;
+                config.shortcuts.retain(|s| s.trigger != shortcut.trigger);
+                config.shortcuts.push(shortcut);
+                continue;
+            }
+
+            let (key, value) = trimmed
+                .split_once('=')
+                .ok_or_else(|| err("expected `key = value`"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let flag = || parse_bool(value).ok_or_else(|| err("expected `true` or `false`"));
+            match key {
+                // App version that wrote the file, see `migrate`
+                "version" => {}
+                "enabled" => config.enabled = flag()?,
+                "method" => {
+                    config.method = METHODS
+                        .iter()
+                        .find(|&&(_, name)| name == value)
+                        .map(|&(id, _)| id)
+                        .ok_or_else(|| err("unknown method"))?;
+                }
+                "skip_w_shortcut" => config.skip_w_shortcut = flag()?,
+                "bracket_shortcut" => config.bracket_shortcut = flag()?,
+                "esc_restore" => config.esc_restore = flag()?,
+                "free_tone" => config.free_tone = flag()?,
+                "modern_tone" => config.modern_tone = flag()?,
+                "english_auto_restore" => config.english_auto_restore = flag()?,
+                "lexicon_learning" => config.lexicon_learning = flag()?,
+                "auto_capitalize" => config.auto_capitalize = flag()?,
+                "allow_foreign_consonants" => config.allow_foreign_consonants = flag()?,
+                "completion" => config.completion = flag()?,
+                "quick_telex" => config.quick_telex = flag()?,
+                "quick_consonants" => {
+                    config.quick_consonants = Vec::new();
+                    for letter in value.split_whitespace() {
+                        let mut chars = letter.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(c), None) => config.quick_consonants.push(c),
+                            _ => return Err(err("expected single letters")),
+                        }
+                    }
+                }
+                "charset" => {
+                    config.charset = CHARSETS
+                        .iter()
+                        .find(|&&(_, name)| name == value)
+                        .map(|&(c, _)| c)
+                        .ok_or_else(|| err("unknown charset"))?;
+                }
+                _ => return Err(err("unknown setting")),
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read a settings file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Write the settings to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> EngineConfig {
+        let mut arrow = Shortcut::immediate("->", "→");
+        arrow.case_mode = CaseMode::Exact;
+        EngineConfig {
+            method: 1,
+            modern_tone: false,
+            english_auto_restore: true,
+            lexicon_learning: true,
+            quick_consonants: vec!['f', 'g'],
+            charset: Charset::VniWindows,
+            shortcuts: vec![
+                Shortcut::new("vn", "Việt Nam"),
+                Shortcut::new("sig", "Thân ái,\n  Gõ Nhanh \\o/ "),
+                arrow,
+                Shortcut::telex("w", "ư"),
+            ],
+            ..EngineConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let config = sample();
+        let text = config.to_text();
+        assert!(text.contains("method = vni\n"));
+        assert!(text.contains("quick_consonants = f g\n"));
+        assert!(text.contains("charset = vni-windows\n"));
+        assert!(
+            text.contains("\n[shortcuts]\nsig = Thân ái,\\n  Gõ Nhanh \\\\o/ \nvn = Việt Nam\n")
+        );
+        assert!(text.contains("\n[shortcuts immediate exact]\n-> = →\n"));
+        assert!(text.contains("\n[shortcuts immediate telex]\nw = ư\n"));
+
+        let parsed = EngineConfig::parse(&text).unwrap();
+        let sorted = |c: &EngineConfig| {
+            let mut s = c.shortcuts.clone();
+            s.sort_by(|a, b| a.trigger.cmp(&b.trigger));
+            s
+        };
+        assert_eq!(sorted(&parsed), sorted(&config));
+        assert_eq!(parsed.to_text(), text);
+        assert_eq!(EngineConfig::default().to_text().lines().count(), 16);
+    }
+
+    #[test]
+    fn test_missing_keys_default() {
+        let config = EngineConfig::parse("# empty\nfree_tone = true\n").unwrap();
+        assert_eq!(
+            config,
+            EngineConfig {
+                free_tone: true,
+                ..EngineConfig::default()
+            }
+        );
+        assert_eq!(EngineConfig::parse("").unwrap(), EngineConfig::default());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = EngineConfig::parse("enabled = true\nmodern_tone = yes").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(EngineConfig::parse("method = dvorak").is_err());
+        assert!(EngineConfig::parse("charset = latin1").is_err());
+        assert!(EngineConfig::parse("no_such_setting = true").is_err());
+        assert!(EngineConfig::parse("quick_consonants = ph").is_err());
+        assert!(EngineConfig::parse("[shortcuts sometimes]").is_err());
+        assert!(EngineConfig::parse("[shortcuts]\nvn Việt Nam").is_err());
+        assert!(EngineConfig::parse("[shortcuts]\nx = a\\tb").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(EngineConfig::default().validate().is_ok());
+        let check = |f: fn(&mut EngineConfig)| {
+            let mut config = EngineConfig::default();
+            f(&mut config);
+            config.validate()
+        };
+        assert!(check(|c| c.method = 3).is_err());
+        assert!(check(|c| c.quick_consonants = vec!['b']).is_err());
+        assert!(check(|c| c.quick_consonants = vec!['F']).is_err());
+        assert!(check(|c| c.shortcuts = vec![Shortcut::new("", "x")]).is_err());
+        assert!(check(|c| c.shortcuts = vec![Shortcut::new("a b", "x")]).is_err());
+        assert!(
+            check(|c| c.shortcuts = vec![Shortcut::new("vn", "a"), Shortcut::new("VN", "b")])
+                .is_err()
+        );
+        // Duplicate triggers in a file: the last one wins
+        let config =
+            EngineConfig::parse("[shortcuts]\nvn = a\n[shortcuts immediate]\nvn = b").unwrap();
+        assert_eq!(config.shortcuts.len(), 1);
+        assert_eq!(config.shortcuts[0].replacement, "b");
+    }
+}
@@ -0,0 +1,227 @@
+//! Offline Text Conversion
+//!
+//! Converts text typed with the IME off ("Tieesng Vieejt") to Vietnamese by
+//! replaying it through `Engine` one key at a time, exactly like live typing:
+//! punctuation and line breaks are word boundaries, auto-restore and
+//! auto-capitalize apply when enabled, and shortcuts expand.
+//!
+//! Characters without a key (already-Vietnamese letters, emoji, ...) are
+//! copied through unchanged and end the current word.
+
+use crate::engine::{Action, Engine};
+use crate::utils::char_to_key;
+use data::keys;
+use std::io::{self, BufRead, Write};
+
+/// Options for offline conversion
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConvertOptions {
+    /// Input method: 0 = Telex, 1 = VNI, 2 = VIQR
+    pub method: u8,
+    /// Modern tone placement (hoà) instead of traditional (hòa)
+    pub modern_tone: bool,
+    /// Restore English words that are invalid Vietnamese
+    pub english_auto_restore: bool,
+    /// Capitalize the first letter of each sentence
+    pub auto_capitalize: bool,
+    /// Place tones without validating the syllable
+    pub free_tone: bool,
+    /// Accept z, w, j, f as initial consonants
+    pub allow_foreign_consonants: bool,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            method: 0,
+            modern_tone: true,
+            english_auto_restore: false,
+            auto_capitalize: false,
+            free_tone: false,
+            allow_foreign_consonants: false,
+        }
+    }
+}
+
+impl ConvertOptions {
+    /// Apply these options to an engine
+    pub fn apply(&self, engine: &mut Engine) {
+        engine.set_method(self.method);
+        engine.set_modern_tone(self.modern_tone);
+        engine.set_english_auto_restore(self.english_auto_restore);
+        engine.set_auto_capitalize(self.auto_capitalize);
+        engine.set_free_tone(self.free_tone);
+        engine.set_allow_foreign_consonants(self.allow_foreign_consonants);
+    }
+}
+
+/// Streaming converter
+///
+/// Feed text in chunks of any size with `push_str`; each call returns the
+/// output that can no longer change. Call `finish` at the end of input to
+/// get the rest.
+pub struct Converter {
+    engine: Engine,
+    /// Output since the last word boundary (may still be rewritten)
+    pending: Vec<char>,
+    /// Last input char was '\r' (a following '\n' belongs to the same break)
+    after_cr: bool,
+}
+
+impl Converter {
+    /// Converter with a fresh engine configured from `options`
+    pub fn new(options: &ConvertOptions) -> Self {
+        let mut engine = Engine::new();
+        options.apply(&mut engine);
+        Self::with_engine(engine)
+    }
+
+    /// Converter driving a preconfigured engine (shortcuts, custom methods, ...)
+    pub fn with_engine(mut engine: Engine) -> Self {
+        engine.clear_all();
+        Self {
+            engine,
+            pending: Vec::new(),
+            after_cr: false,
+        }
+    }
+
+    /// The engine used for conversion
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Mutable access to the engine, e.g. to add shortcuts
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Convert a chunk, returning the output that is final
+    pub fn push_str(&mut self, text: &str) -> String {
+        let mut out = String::new();
+        for c in text.chars() {
+            self.push_char(c);
+            if c.is_whitespace() {
+                out.extend(self.pending.drain(..));
+            }
+        }
+        out
+    }
+
+    /// End of input: returns the remaining output and resets the engine
+    pub fn finish(&mut self) -> String {
+        self.engine.clear_all();
+        self.after_cr = false;
+        self.pending.drain(..).collect()
+    }
+
+    fn push_char(&mut self, c: char) {
+        let after_cr = std::mem::replace(&mut self.after_cr, c == '\r');
+        match c {
+            '\n' if after_cr => self.pending.push(c),
+            '\n' | '\r' => {
+                // Line breaks are kept as typed, whatever the engine does with Enter
+                let r = self.engine.on_key_ext(keys::RETURN, false, false, false);
+                self.apply(&r);
+                self.pending.push(c);
+            }
+            ' ' => {
+                let r = self.engine.on_key_ext(keys::SPACE, false, false, false);
+                if r.action == Action::Send as u8 {
+                    self.apply(&r);
+                } else {
+                    self.pending.push(' ');
+                }
+            }
+            _ => match key_for_char(c) {
+                Some((key, shift)) => {
+                    let r = self.engine.on_key_ext(key, c.is_uppercase(), false, shift);
+                    if r.action == Action::Send as u8 {
+                        self.apply(&r);
+                        if keys::is_break_ext(key, shift) && !r.key_consumed() {
+                            self.pending.push(c);
+                        }
+                    } else {
+                        self.pending.push(c);
+                    }
+                }
+                None => {
+                    self.engine.clear();
+                    self.pending.push(c);
+                }
+            },
+        }
+    }
+
+    fn apply(&mut self, r: &crate::engine::Result) {
+        let keep = self.pending.len().saturating_sub(r.backspace as usize);
+        self.pending.truncate(keep);
+        self.pending.extend(
+            r.chars[..r.count as usize]
+                .iter()
+                .filter_map(|&c| char::from_u32(c)),
+        );
+    }
+}
+
+/// Key and shift state that types `c` on a US layout
+pub(crate) fn key_for_char(c: char) -> Option<(u16, bool)> {
+    let shifted = match c {
+        '!' => keys::N1,
+        '@' => keys::N2,
+        '#' => keys::N3,
+        '$' => keys::N4,
+        '%' => keys::N5,
+        '^' => keys::N6,
+        '&' => keys::N7,
+        '*' => keys::N8,
+        '(' => keys::N9,
+        ')' => keys::N0,
+        '_' => keys::MINUS,
+        '+' => keys::EQUAL,
+        ':' => keys::SEMICOLON,
+        '"' => keys::QUOTE,
+        '<' => keys::COMMA,
+        '>' => keys::DOT,
+        '?' => keys::SLASH,
+        '|' => keys::BACKSLASH,
+        '{' => keys::LBRACKET,
+        '}' => keys::RBRACKET,
+        '~' => keys::BACKQUOTE,
+        '\t' => return Some((keys::TAB, false)),
+        // char_to_key maps these to editing keys
+        ' ' | '\x1b' => return None,
+        _ if !c.is_ascii() => return None,
+        _ => match char_to_key(c) {
+            255 => return None,
+            key => return Some((key, false)),
+        },
+    };
+    Some((shifted, true))
+}
+
+/// Convert a whole string
+///
+/// Example: `convert("Tieesng Vieejt", &ConvertOptions::default())` → "Tiếng Việt"
+pub fn convert(text: &str, options: &ConvertOptions) -> String {
+    let mut converter = Converter::new(options);
+    let mut out = converter.push_str(text);
+    out.push_str(&converter.finish());
+    out
+}
+
+/// Convert text from a reader line by line, writing the result to `writer`
+pub fn convert_reader<R: BufRead, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    options: &ConvertOptions,
+) -> io::Result<()> {
+    let mut converter = Converter::new(options);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        writer.write_all(converter.push_str(&line).as_bytes())?;
+        line.clear();
+    }
+    writer.write_all(converter.finish().as_bytes())?;
+    writer.flush()
+}
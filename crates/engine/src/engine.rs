@@ -11,9 +11,17 @@
 //! 4. **Longest-Match-First**: For diacritic placement
 
 use crate::buffer::{Buffer, Char, MAX};
-use crate::input::{self, ToneType};
+use crate::compose::{self, Composition, Preedit, PreeditHint};
+use crate::config::EngineConfig;
+use crate::convert::{key_for_char, ConvertOptions};
+use crate::input::custom::ParseError;
+use crate::input::{self, CustomMethod, MethodRef, ToneType};
+use crate::lexicon::{Language, UserLexicon};
+use crate::reverse::to_keystrokes;
 use crate::shortcut::{InputMethod, ShortcutTable};
+use crate::surrounding::{self, TextEdit};
 use crate::syllable;
+use crate::trace::{self, KeyTrace, Recorder};
 use crate::utils;
 use crate::validation::{
     self, is_foreign_word_pattern, is_valid, is_valid_for_transform_with_foreign,
@@ -21,9 +29,12 @@ use crate::validation::{
 };
 use data::{
     chars::{self, mark, tone},
+    charset::{self, Charset},
+    completion::{self, Phrases, MAX_PHRASE_WORDS},
     constants, english_dict, keys, telex_doubles, vietnamese_spellcheck,
     vowel::{Phonology, Vowel},
 };
+use std::sync::Arc;
 
 /// Engine action result
 #[repr(u8)]
@@ -95,6 +106,27 @@ impl Result {
     }
 }
 
+/// Where a candidate from `Engine::candidates` comes from
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CandidateKind {
+    /// The Vietnamese transform on screen ("tẽt")
+    Vietnamese = 0,
+    /// English word spelled by the keystrokes ("text")
+    English = 1,
+    /// Nearest dictionary word when the transform isn't a real word
+    Dictionary = 2,
+    /// Keystrokes exactly as typed ("dd")
+    Raw = 3,
+}
+
+/// One interpretation of the word being typed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub text: String,
+    pub kind: CandidateKind,
+}
+
 /// Transform type for revert tracking
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum Transform {
@@ -114,8 +146,22 @@ enum Transform {
     WShortcutSkipped,
     /// Bracket as vowel: ] → ư, [ → ơ (Issue #159)
     BracketAsVowel,
+    /// Quick Telex doubled consonant: cc → ch, nn → ng (for revert: ccc → cc)
+    QuickTelex(u16),
 }
 
+/// Quick consonant shorthands: (key, is_initial, expansion)
+/// Initials: f → ph, j → gi, w → qu. Finals: g → ng, h → nh, k → ch.
+/// Index in this table is the bit used by `Engine::quick_consonants`.
+pub const QUICK_CONSONANTS: &[(u16, bool, &[u16])] = &[
+    (keys::F, true, &[keys::P, keys::H]),
+    (keys::J, true, &[keys::G, keys::I]),
+    (keys::W, true, &[keys::Q, keys::U]),
+    (keys::G, false, &[keys::N, keys::G]),
+    (keys::H, false, &[keys::N, keys::H]),
+    (keys::K, false, &[keys::C, keys::H]),
+];
+
 /// Word history ring buffer capacity (stores last N committed words)
 const HISTORY_CAPACITY: usize = 10;
 
@@ -158,12 +204,38 @@ impl WordHistory {
         Some(self.data[self.head].clone())
     }
 
+    /// Up to `n` most recent words, oldest first
+    fn recent(&self, n: usize) -> Vec<String> {
+        let n = n.min(self.len);
+        (0..n)
+            .rev()
+            .map(|i| {
+                let idx = (self.head + HISTORY_CAPACITY - 1 - i) % HISTORY_CAPACITY;
+                self.data[idx].to_full_string()
+            })
+            .collect()
+    }
+
     fn clear(&mut self) {
         self.len = 0;
         self.head = 0;
     }
 }
 
+/// Both forms of a committed word that went through the restore decision
+///
+/// Kept for the last commit so that when backspace brings the word back,
+/// going back to the other form can be learned as an exception.
+#[derive(Clone, Debug)]
+struct CommitForms {
+    /// Form with the Vietnamese transforms ("tẽt")
+    vietnamese: String,
+    /// Keys as typed ("text")
+    english: String,
+    /// Auto-restore chose the English form
+    restored: bool,
+}
+
 /// Check if key is sentence-ending punctuation (. ! ?) but NOT Enter
 /// Issue #185: Only set pending_capitalize after punctuation + space
 #[inline]
@@ -295,6 +367,22 @@ fn break_key_to_char(key: u16, shift: bool) -> Option<char> {
     }
 }
 
+/// Buffer holding `word` as shown on screen, letters only
+fn word_buffer(word: &str) -> Buffer {
+    let mut buf = Buffer::new();
+    // Decomposed text (base + combining marks) parses like precomposed
+    for c in chars::compose(word).chars() {
+        if let Some(parsed) = chars::parse_char(c) {
+            let mut ch = Char::new(parsed.key, parsed.caps);
+            ch.tone = parsed.tone;
+            ch.mark = parsed.mark;
+            ch.stroke = parsed.stroke;
+            buf.push(ch);
+        }
+    }
+    buf
+}
+
 /// Main Vietnamese IME engine
 pub struct Engine {
     buf: Buffer,
@@ -404,6 +492,44 @@ pub struct Engine {
     /// Allow foreign consonants (z, w, j, f) as valid initial consonants
     /// When true, these letters are accepted as Vietnamese consonants for loanwords
     allow_foreign_consonants: bool,
+    /// Symbol modifiers (VIQR, custom methods): backslash was just typed after a word
+    /// The next modifier symbol is typed literally and replaces the backslash ("a\'" → "a'")
+    symbol_escape_pending: bool,
+    /// Custom methods registered at runtime, id = CUSTOM_METHOD_BASE + index
+    custom_methods: Vec<Arc<CustomMethod>>,
+    /// Selected custom method (method then holds its base: 0 = Telex, 1 = VNI)
+    custom_method: Option<Arc<CustomMethod>>,
+    /// Quick Telex: doubled consonants expand to digraphs (cc → ch, nn → ng)
+    quick_telex: bool,
+    /// Tracks if a Quick Telex expansion was reverted in current word (ccc → cc)
+    /// Same role as stroke_reverted: further doubles stay plain letters
+    quick_telex_reverted: bool,
+    /// Enabled quick consonant shorthands, one bit per QUICK_CONSONANTS entry
+    quick_consonants: u8,
+    /// Output character set for Result.chars (Unicode or a legacy encoding)
+    charset: Charset,
+    /// Per-user words that override auto-restore ("always English/Vietnamese")
+    lexicon: UserLexicon,
+    /// Add committed words to the lexicon (opt-in); pinned words and
+    /// exceptions apply either way
+    lexicon_learning: bool,
+    /// Restore decision of the last committed word (see `CommitForms`)
+    last_commit: Option<CommitForms>,
+    /// Restore decision of the word brought back by backspace, until it ends
+    undo_forms: Option<CommitForms>,
+    /// Vietnamese form and buffer length when the word was restored mid-word
+    /// ("tẽt" → "text" on the final t), for `CommitForms`
+    restored_mid_word: Option<(String, usize)>,
+    /// Offer word completions and next-syllable predictions (opt-in)
+    /// When false, `completions` returns nothing, the index is never loaded
+    /// and no phrases are learned
+    completion: bool,
+    /// Runs of committed words, learned while completion is enabled
+    phrases: Phrases,
+    /// Uncommitted text of composition mode (`on_key_compose`)
+    preedit: Preedit,
+    /// Decision trace, off unless `set_trace` (see `trace`)
+    trace: Recorder,
 }
 
 impl Default for Engine {
@@ -450,11 +576,124 @@ impl Engine {
             auto_capitalize_used: false,
             saw_sentence_ending: false,
             allow_foreign_consonants: false, // Default: OFF
+            symbol_escape_pending: false,
+            custom_methods: Vec::new(),
+            custom_method: None,
+            quick_telex: false, // Default: OFF
+            quick_telex_reverted: false,
+            quick_consonants: 0, // Default: OFF
+            charset: Charset::Unicode,
+            lexicon: UserLexicon::new(),
+            lexicon_learning: false, // Default: OFF
+            last_commit: None,
+            undo_forms: None,
+            restored_mid_word: None,
+            completion: false, // Default: OFF
+            phrases: Phrases::new(),
+            preedit: Preedit::default(),
+            trace: Recorder::default(),
         }
     }
 
+    /// Select input method: 0 = Telex, 1 = VNI, 2 = VIQR, or an id from `register_method`
+    /// Unknown custom ids fall back to Telex.
     pub fn set_method(&mut self, method: u8) {
-        self.method = method;
+        self.custom_method = None;
+        if method < input::CUSTOM_METHOD_BASE {
+            self.method = method;
+            return;
+        }
+        self.method = 0;
+        if let Some(custom) = self
+            .custom_methods
+            .get((method - input::CUSTOM_METHOD_BASE) as usize)
+        {
+            self.method = custom.base().method_id();
+            self.custom_method = Some(Arc::clone(custom));
+        }
+    }
+
+    /// Register a custom input method, returns its id for `set_method`
+    ///
+    /// Registering a method with the same name again replaces it and keeps
+    /// its id, so the definition can be edited and reloaded.
+    /// Returns None when all ids are taken.
+    pub fn register_method(&mut self, method: CustomMethod) -> Option<u8> {
+        let method = Arc::new(method);
+        let index = match self
+            .custom_methods
+            .iter()
+            .position(|m| m.name() == method.name())
+        {
+            Some(index) => {
+                // Reselect if the replaced method is active
+                if let Some(active) = &self.custom_method {
+                    if Arc::ptr_eq(active, &self.custom_methods[index]) {
+                        self.custom_method = Some(Arc::clone(&method));
+                    }
+                }
+                self.custom_methods[index] = method;
+                index
+            }
+            None => {
+                if self.custom_methods.len() >= (u8::MAX - input::CUSTOM_METHOD_BASE) as usize {
+                    return None;
+                }
+                self.custom_methods.push(method);
+                self.custom_methods.len() - 1
+            }
+        };
+        Some(input::CUSTOM_METHOD_BASE + index as u8)
+    }
+
+    /// Key mappings of the active input method
+    fn input_method(&self) -> MethodRef {
+        match &self.custom_method {
+            Some(custom) => MethodRef::Custom(Arc::clone(custom)),
+            None => MethodRef::Builtin(input::get(self.method)),
+        }
+    }
+
+    /// Character of a raw key when rebuilding the word as typed
+    ///
+    /// Unshifted ' ` . stay in a word only as modifiers (VIQR a' → á), so they
+    /// map back to text only for methods that use them that way.
+    fn raw_char(&self, key: u16, caps: bool, shift: bool) -> Option<char> {
+        if !shift {
+            let symbol = match key {
+                keys::QUOTE => Some('\''),
+                keys::BACKQUOTE => Some('`'),
+                keys::DOT => Some('.'),
+                _ => None,
+            };
+            if let Some(ch) = symbol {
+                let method = self.input_method();
+                if method.mark_with_shift(key, false).is_some()
+                    || method.tone_with_shift(key, false).is_some()
+                {
+                    return Some(ch);
+                }
+            }
+        }
+        utils::key_to_char_ext(key, caps, shift)
+    }
+
+    /// Active method types the horn on `vowel` with w, as Telex does
+    ///
+    /// Gates the Telex extras built on w (w → ư, brackets → ơ/ư), which
+    /// custom methods only get if their mapping keeps w.
+    fn w_types_horn(&self, vowel: u16) -> bool {
+        let m = self.input_method();
+        m.tone(keys::W) == Some(ToneType::Horn) && m.tone_targets(keys::W).contains(&vowel)
+    }
+
+    /// `key` is a mark, tone, stroke or remove key in the active method
+    fn is_method_key(&self, key: u16, shift: bool) -> bool {
+        let m = self.input_method();
+        m.mark_with_shift(key, shift).is_some()
+            || m.tone_with_shift(key, shift).is_some()
+            || m.stroke(key)
+            || m.remove(key)
     }
 
     pub fn set_enabled(&mut self, enabled: bool) {
@@ -510,6 +749,85 @@ impl Engine {
         self.allow_foreign_consonants = enabled;
     }
 
+    /// Set whether word completion and next-syllable prediction are enabled
+    pub fn set_completion(&mut self, enabled: bool) {
+        self.completion = enabled;
+    }
+
+    /// Get whether word completion is enabled
+    pub fn completion(&self) -> bool {
+        self.completion
+    }
+
+    /// Set whether Quick Telex is enabled (Telex only)
+    /// cc → ch, gg → gi, kk → kh, nn → ng, pp → ph, qq → qu, tt → th
+    pub fn set_quick_telex(&mut self, enabled: bool) {
+        self.quick_telex = enabled;
+    }
+
+    /// Get whether Quick Telex is enabled
+    pub fn quick_telex(&self) -> bool {
+        self.quick_telex
+    }
+
+    /// Enable or disable one quick consonant shorthand by its key
+    ///
+    /// Initials: f → ph, j → gi, w → qu. Finals: g → ng, h → nh, k → ch.
+    /// Expansion only happens when the resulting syllable is valid Vietnamese.
+    /// Returns false if the key is not a shorthand.
+    pub fn set_quick_consonant(&mut self, key: u16, enabled: bool) -> bool {
+        let Some(bit) = QUICK_CONSONANTS.iter().position(|&(k, _, _)| k == key) else {
+            return false;
+        };
+        if enabled {
+            self.quick_consonants |= 1 << bit;
+        } else {
+            self.quick_consonants &= !(1 << bit);
+        }
+        true
+    }
+
+    /// Enable or disable all initial shorthands (f → ph, j → gi, w → qu)
+    pub fn set_quick_start_consonants(&mut self, enabled: bool) {
+        for &(key, is_initial, _) in QUICK_CONSONANTS {
+            if is_initial {
+                self.set_quick_consonant(key, enabled);
+            }
+        }
+    }
+
+    /// Enable or disable all final shorthands (g → ng, h → nh, k → ch)
+    pub fn set_quick_end_consonants(&mut self, enabled: bool) {
+        for &(key, is_initial, _) in QUICK_CONSONANTS {
+            if !is_initial {
+                self.set_quick_consonant(key, enabled);
+            }
+        }
+    }
+
+    /// Get whether a quick consonant shorthand is enabled
+    pub fn quick_consonant(&self, key: u16) -> bool {
+        QUICK_CONSONANTS
+            .iter()
+            .position(|&(k, _, _)| k == key)
+            .is_some_and(|bit| self.quick_consonants & (1 << bit) != 0)
+    }
+
+    /// Set output character set
+    ///
+    /// Legacy charsets emit one `Result.chars` entry per byte (as Latin-1
+    /// codepoints), and backspace counts are in bytes: VNI-Windows "á" is
+    /// "a" + 0xF9, so deleting it takes 2 backspaces. NFD works the same way
+    /// in codepoints: "á" is "a" + U+0301.
+    pub fn set_charset(&mut self, charset: Charset) {
+        self.charset = charset;
+    }
+
+    /// Get output character set
+    pub fn charset(&self) -> Charset {
+        self.charset
+    }
+
     /// Get whether foreign consonants are allowed
     pub fn allow_foreign_consonants(&self) -> bool {
         self.allow_foreign_consonants
@@ -523,6 +841,119 @@ impl Engine {
         &mut self.shortcuts
     }
 
+    /// User lexicon consulted by English auto-restore
+    pub fn lexicon(&self) -> &UserLexicon {
+        &self.lexicon
+    }
+
+    /// Mutable access to the user lexicon (pin words, load a saved one)
+    pub fn lexicon_mut(&mut self) -> &mut UserLexicon {
+        &mut self.lexicon
+    }
+
+    /// Set whether committed words are learned into the user lexicon
+    pub fn set_lexicon_learning(&mut self, enabled: bool) {
+        self.lexicon_learning = enabled;
+    }
+
+    /// Get whether committed words are learned into the user lexicon
+    pub fn lexicon_learning(&self) -> bool {
+        self.lexicon_learning
+    }
+
+    /// Phrases learned for completion
+    pub fn phrases(&self) -> &Phrases {
+        &self.phrases
+    }
+
+    /// Mutable access to the learned phrases (e.g. to forget them)
+    pub fn phrases_mut(&mut self) -> &mut Phrases {
+        &mut self.phrases
+    }
+
+    /// Current settings and shortcuts
+    ///
+    /// A selected custom method is reported as the built-in method it
+    /// extends; custom methods are registered separately.
+    pub fn config(&self) -> EngineConfig {
+        let mut shortcuts: Vec<_> = self.shortcuts.iter().cloned().collect();
+        shortcuts.sort_by(|a, b| a.trigger.cmp(&b.trigger));
+        EngineConfig {
+            enabled: self.enabled,
+            method: self.method,
+            skip_w_shortcut: self.skip_w_shortcut,
+            bracket_shortcut: self.bracket_shortcut,
+            esc_restore: self.esc_restore_enabled,
+            free_tone: self.free_tone_enabled,
+            modern_tone: self.modern_tone,
+            english_auto_restore: self.english_auto_restore,
+            lexicon_learning: self.lexicon_learning,
+            auto_capitalize: self.auto_capitalize,
+            allow_foreign_consonants: self.allow_foreign_consonants,
+            completion: self.completion,
+            quick_telex: self.quick_telex,
+            quick_consonants: QUICK_CONSONANTS
+                .iter()
+                .filter(|&&(key, _, _)| self.quick_consonant(key))
+                .filter_map(|&(key, _, _)| utils::key_to_char(key, false))
+                .collect(),
+            charset: self.charset,
+            shortcuts,
+        }
+    }
+
+    /// Replace all settings and shortcuts
+    ///
+    /// The config is validated first; on error nothing changes.
+    pub fn apply_config(&mut self, config: &EngineConfig) -> std::result::Result<(), ParseError> {
+        config.validate()?;
+        self.set_enabled(config.enabled);
+        self.set_method(config.method);
+        self.set_skip_w_shortcut(config.skip_w_shortcut);
+        self.set_bracket_shortcut(config.bracket_shortcut);
+        self.set_esc_restore(config.esc_restore);
+        self.set_free_tone(config.free_tone);
+        self.set_modern_tone(config.modern_tone);
+        self.set_english_auto_restore(config.english_auto_restore);
+        self.set_lexicon_learning(config.lexicon_learning);
+        self.set_auto_capitalize(config.auto_capitalize);
+        self.set_allow_foreign_consonants(config.allow_foreign_consonants);
+        self.set_completion(config.completion);
+        self.set_quick_telex(config.quick_telex);
+        self.quick_consonants = 0;
+        for &c in &config.quick_consonants {
+            self.set_quick_consonant(utils::char_to_key(c), true);
+        }
+        self.set_charset(config.charset);
+        self.shortcuts.clear();
+        for shortcut in &config.shortcuts {
+            self.shortcuts.add(shortcut.clone());
+        }
+        Ok(())
+    }
+
+    /// Record every key's decisions from now on, or stop (see `trace`)
+    ///
+    /// Turning tracing on starts an empty trace.
+    pub fn set_trace(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled);
+    }
+
+    /// Keys recorded since tracing was turned on, oldest first
+    pub fn trace(&self) -> impl Iterator<Item = KeyTrace> {
+        self.trace.keys().into_iter()
+    }
+
+    /// The trace as a JSON array, to attach to bug reports
+    pub fn trace_json(&self) -> String {
+        trace::to_json(&self.trace.keys())
+    }
+
+    /// Forget the recorded keys and keep tracing
+    pub fn clear_trace(&mut self) {
+        self.trace.clear();
+    }
+
     /// Debug: get buffer length
     pub fn debug_buffer_len(&self) -> usize {
         self.buf.len()
@@ -574,6 +1005,7 @@ impl Engine {
         match self.method {
             0 => InputMethod::Telex,
             1 => InputMethod::Vni,
+            2 => InputMethod::Viqr,
             _ => InputMethod::All,
         }
     }
@@ -611,27 +1043,323 @@ impl Engine {
         ctrl: bool,
         shift: bool,
         ch: Option<char>,
+    ) -> Result {
+        if self.charset == Charset::Unicode {
+            return self.handle_key_with_char(key, caps, ctrl, shift, ch);
+        }
+        let before = self.rendered();
+        let result = self.handle_key_with_char(key, caps, ctrl, shift, ch);
+        self.encode_or_pass(result, &before, key)
+    }
+
+    /// Handle key event in composition mode (see `compose`)
+    ///
+    /// Same arguments as `on_key_with_char`, but the word being typed is
+    /// reported as a preedit and text reaches the document through commits.
+    /// Text is always Unicode (NFC), whatever the output charset.
+    pub fn on_key_compose(
+        &mut self,
+        key: u16,
+        caps: bool,
+        ctrl: bool,
+        shift: bool,
+        ch: Option<char>,
+    ) -> Composition {
+        let r = self.handle_key_with_char(key, caps, ctrl, shift, ch);
+        if ctrl {
+            return self.preedit.pass_through();
+        }
+        let typed = ch.or_else(|| compose::typed_char(key, caps, shift));
+        let mut out = self
+            .preedit
+            .apply(key, shift, &r, typed, self.buf.is_empty());
+        if !out.preedit.is_empty() && self.should_auto_restore(true).is_some() {
+            out.hint = PreeditHint::Restore;
+        }
+        out
+    }
+
+    /// Text being composed in composition mode
+    pub fn preedit(&self) -> String {
+        self.preedit.as_string()
+    }
+
+    /// End composition, e.g. on focus out or a click elsewhere
+    ///
+    /// Returns the preedit text to commit and clears the current word.
+    pub fn finish_composition(&mut self) -> String {
+        self.clear();
+        self.preedit.take()
+    }
+
+    /// Rebuild the engine state from the text around the cursor (see `surrounding`)
+    ///
+    /// Call when the cursor moves or the document changes outside the
+    /// engine, not before every key: a word typed since keeps its keystrokes.
+    pub fn set_surrounding_text(&mut self, before: &str, after: &str) {
+        let Some(ctx) = surrounding::context(before, after) else {
+            self.clear_all();
+            return;
+        };
+        if ctx.word.is_empty() || self.buf.to_full_string() != ctx.word {
+            self.clear_all();
+            self.replay_word(ctx.word);
+        } else {
+            self.word_history.clear();
+            self.pending_capitalize = false;
+            self.saw_sentence_ending = false;
+            self.symbol_escape_pending = false;
+        }
+
+        for word in &ctx.history {
+            self.word_history.push(word_buffer(word));
+        }
+        if !ctx.history.is_empty() {
+            self.spaces_after_commit = ctx.spaces.min(u8::MAX as usize) as u8;
+        }
+        if self.auto_capitalize && ctx.word.is_empty() {
+            self.pending_capitalize = ctx.line_start || (ctx.sentence_end && ctx.spaces > 0);
+            self.saw_sentence_ending = ctx.sentence_end;
+        }
+    }
+
+    /// Handle key event as a replace-range edit (see `surrounding`)
+    ///
+    /// Same arguments as `on_key_with_char`. Text is always Unicode (NFC),
+    /// whatever the output charset.
+    pub fn on_key_surrounding(
+        &mut self,
+        key: u16,
+        caps: bool,
+        ctrl: bool,
+        shift: bool,
+        ch: Option<char>,
+    ) -> TextEdit {
+        let before = self.rendered();
+        let r = self.handle_key_with_char(key, caps, ctrl, shift, ch);
+        if r.action == Action::None as u8 {
+            return TextEdit {
+                pass_through: true,
+                ..TextEdit::default()
+            };
+        }
+        let chars: Vec<char> = r.chars[..r.count as usize]
+            .iter()
+            .filter_map(|&c| char::from_u32(c))
+            .collect();
+        let mut edit = TextEdit::replace(&before, r.backspace as usize, &chars);
+        edit.pass_through =
+            key != keys::SPACE && keys::is_break_ext(key, shift) && !r.key_consumed();
+        edit
+    }
+
+    /// Type `word` into the empty buffer as if its keys were pressed
+    ///
+    /// Keys come from `to_keystrokes`, so raw input (ESC, candidates)
+    /// matches the buffer. Words they don't reproduce, or that auto-restore
+    /// would now undo, are loaded like `restore_word`: the word on screen
+    /// already went through the restore decision.
+    fn replay_word(&mut self, word: &str) {
+        if word.is_empty() {
+            return;
+        }
+        let options = ConvertOptions {
+            method: self.method,
+            modern_tone: self.modern_tone,
+            english_auto_restore: self.english_auto_restore,
+            auto_capitalize: false,
+            free_tone: self.free_tone_enabled,
+            allow_foreign_consonants: self.allow_foreign_consonants,
+        };
+        let typeable = self.enabled && self.custom_method.is_none() && word.chars().count() <= MAX;
+        if let Some(keystrokes) = typeable
+            .then(|| to_keystrokes(word, &options).ok())
+            .flatten()
+        {
+            let auto_capitalize = std::mem::replace(&mut self.auto_capitalize, false);
+            for c in keystrokes.chars() {
+                if let Some((key, shift)) = key_for_char(c) {
+                    self.handle_key(key, c.is_uppercase(), false, shift);
+                }
+            }
+            self.auto_capitalize = auto_capitalize;
+        }
+        if self.buf.to_full_string() != word || self.should_auto_restore(true).is_some() {
+            self.restore_word(word);
+        }
+    }
+
+    /// on_key_with_char before output encoding (Unicode result)
+    fn handle_key_with_char(
+        &mut self,
+        key: u16,
+        caps: bool,
+        ctrl: bool,
+        shift: bool,
+        ch: Option<char>,
+    ) -> Result {
+        self.traced(key, caps, ctrl, shift, ch, |e| {
+            e.process_key_with_char(key, caps, ctrl, shift, ch)
+        })
+    }
+
+    /// Run one key, recording it if tracing (nested calls are part of it)
+    fn traced(
+        &mut self,
+        key: u16,
+        caps: bool,
+        ctrl: bool,
+        shift: bool,
+        ch: Option<char>,
+        f: impl FnOnce(&mut Self) -> Result,
+    ) -> Result {
+        if !self.trace.is_enabled() {
+            return f(self);
+        }
+        let event = KeyTrace {
+            key,
+            caps,
+            ctrl,
+            shift,
+            ch,
+            ..KeyTrace::default()
+        };
+        let started = self.trace.begin(event.clone(), self.trace_flags());
+        let result = f(self);
+        if started {
+            let validation = (!self.buf.is_empty()).then(|| {
+                let snap = validation::BufferSnapshot {
+                    keys: self.buf.iter().map(|c| c.key).collect(),
+                    tones: self.buf.iter().map(|c| c.tone).collect(),
+                    has_tone_info: true,
+                    allow_foreign_consonants: self.allow_foreign_consonants,
+                };
+                format!("{:?}", validation::validate(&snap))
+            });
+            let after = KeyTrace {
+                buffer: self.buf.to_full_string(),
+                raw: self.typed_chars().into_iter().collect(),
+                validation,
+                action: result.action,
+                backspace: result.backspace,
+                output: result.chars[..result.count as usize]
+                    .iter()
+                    .filter_map(|&c| char::from_u32(c))
+                    .collect(),
+                ..event
+            };
+            self.trace.end(self.trace_flags(), after);
+        }
+        result
+    }
+
+    /// State flags shown in the trace when a key changes them
+    fn trace_flags(&self) -> trace::Flags {
+        vec![
+            ("had_any_transform", format!("{:?}", self.had_any_transform)),
+            ("had_mark_revert", format!("{:?}", self.had_mark_revert)),
+            (
+                "pending_mark_revert_pop",
+                format!("{:?}", self.pending_mark_revert_pop),
+            ),
+            ("pending_breve_pos", format!("{:?}", self.pending_breve_pos)),
+            (
+                "pending_u_horn_pos",
+                format!("{:?}", self.pending_u_horn_pos),
+            ),
+            ("stroke_reverted", format!("{:?}", self.stroke_reverted)),
+            (
+                "had_vowel_triggered_circumflex",
+                format!("{:?}", self.had_vowel_triggered_circumflex),
+            ),
+            (
+                "had_circumflex_revert",
+                format!("{:?}", self.had_circumflex_revert),
+            ),
+            (
+                "reverted_circumflex_key",
+                format!("{:?}", self.reverted_circumflex_key),
+            ),
+            (
+                "had_telex_transform",
+                format!("{:?}", self.had_telex_transform),
+            ),
+            ("telex_double_raw", format!("{:?}", self.telex_double_raw)),
+            ("last_transform", format!("{:?}", self.last_transform)),
+            (
+                "restored_pending_clear",
+                format!("{:?}", self.restored_pending_clear),
+            ),
+            ("restored_mid_word", format!("{:?}", self.restored_mid_word)),
+            (
+                "has_non_letter_prefix",
+                format!("{:?}", self.has_non_letter_prefix),
+            ),
+            (
+                "quick_telex_reverted",
+                format!("{:?}", self.quick_telex_reverted),
+            ),
+            (
+                "symbol_escape_pending",
+                format!("{:?}", self.symbol_escape_pending),
+            ),
+            ("shortcut_prefix", format!("{:?}", self.shortcut_prefix)),
+            (
+                "spaces_after_commit",
+                format!("{:?}", self.spaces_after_commit),
+            ),
+            ("word_history_len", format!("{:?}", self.word_history.len)),
+            (
+                "pending_capitalize",
+                format!("{:?}", self.pending_capitalize),
+            ),
+            (
+                "saw_sentence_ending",
+                format!("{:?}", self.saw_sentence_ending),
+            ),
+            (
+                "auto_capitalize_used",
+                format!("{:?}", self.auto_capitalize_used),
+            ),
+        ]
+    }
+
+    /// `handle_key_with_char` without tracing
+    fn process_key_with_char(
+        &mut self,
+        key: u16,
+        caps: bool,
+        ctrl: bool,
+        shift: bool,
+        ch: Option<char>,
     ) -> Result {
         // No character provided or control key → fall back to normal processing
         if ch.is_none() || ctrl {
-            return self.on_key_ext(key, caps, ctrl, shift);
+            return self.handle_key(key, caps, ctrl, shift);
         }
 
         let ch = ch.unwrap();
 
+        // Combining diacritic (decomposed input): apply it to the last letter
+        if chars::is_combining(ch) {
+            if let Some(result) = self.try_combining(ch) {
+                return result;
+            }
+        }
+
         // Map character to QWERTY keycode for layout independence
         let mut res = if ch.is_alphanumeric() {
             let qwerty_key = crate::utils::char_to_key(ch.to_ascii_lowercase());
             if qwerty_key != 255 {
                 let is_upper = ch.is_uppercase();
-                self.on_key_ext(qwerty_key, is_upper, ctrl, shift)
+                self.handle_key(qwerty_key, is_upper, ctrl, shift)
             } else {
-                self.on_key_ext(key, caps, ctrl, shift)
+                self.handle_key(key, caps, ctrl, shift)
             }
         } else {
             let mapped_key = char_to_punctuation_key(ch);
             if mapped_key != 255 {
-                self.on_key_ext(mapped_key, caps, ctrl, shift)
+                self.handle_key(mapped_key, caps, ctrl, shift)
             } else {
                 self.process(key, caps, shift)
             }
@@ -695,18 +1423,109 @@ impl Engine {
     /// * `ctrl` - true if Cmd/Ctrl/Alt is pressed (bypasses IME)
     /// * `shift` - true if Shift key is pressed (for symbols like @, #, $)
     pub fn on_key_ext(&mut self, key: u16, caps: bool, ctrl: bool, shift: bool) -> Result {
+        if self.charset == Charset::Unicode {
+            return self.handle_key(key, caps, ctrl, shift);
+        }
+        let before = self.rendered();
+        let result = self.handle_key(key, caps, ctrl, shift);
+        self.encode_or_pass(result, &before, key)
+    }
+
+    /// Output units of one character in the current charset
+    /// Bytes (as Latin-1 chars) for legacy charsets, codepoints for NFD.
+    /// Characters without a legacy form (e.g. shortcut symbols) stay Unicode.
+    fn encode_char(&self, c: char) -> Vec<char> {
+        if self.charset == Charset::UnicodeNfd {
+            return chars::decompose(c);
+        }
+        match charset::encode_char(c, self.charset) {
+            Some(bytes) => bytes.iter().map(|&b| b as char).collect(),
+            None => vec![c],
+        }
+    }
+
+    /// `encode_result`, passing the key through when the output doesn't fit
+    ///
+    /// The app then types the key as is, so the engine starts over from what
+    /// is on screen instead of tracking a transform it never showed.
+    fn encode_or_pass(&mut self, result: Result, before: &[char], key: u16) -> Result {
+        match self.encode_result(result, before, key) {
+            Some(encoded) => encoded,
+            None => {
+                self.clear();
+                Result::none()
+            }
+        }
+    }
+
+    /// Convert a Unicode result to the output charset
+    ///
+    /// `before` is the word on screen before the key, in Unicode. Backspace
+    /// counts become encoded units: each deleted letter counts its length.
+    /// `None` when the encoded output or backspace count exceeds what a
+    /// `Result` holds (255 units), e.g. a long shortcut in VNI-Windows.
+    fn encode_result(&self, result: Result, before: &[char], key: u16) -> Option<Result> {
+        let width = |c: &char| self.encode_char(*c).len();
+
+        if result.action == Action::None as u8 {
+            // App deletes one unit on DELETE; remove the rest of a multi-byte letter
+            if key == keys::DELETE && self.buf.len() < before.len() {
+                let deleted = before.last().map_or(1, width);
+                if deleted > 1 {
+                    return Some(Result::send_consumed(deleted as u8, &[]));
+                }
+            }
+            return Some(result);
+        }
+
+        let deleted = (result.backspace as usize).min(before.len());
+        let backspace = before[before.len() - deleted..]
+            .iter()
+            .map(width)
+            .sum::<usize>()
+            + (result.backspace as usize - deleted);
+
+        let mut output = Vec::with_capacity(result.count as usize * 2);
+        for c in result.chars[..result.count as usize]
+            .iter()
+            .filter_map(|&c| char::from_u32(c))
+        {
+            output.extend(self.encode_char(c));
+        }
+        if output.len() > u8::MAX as usize || backspace > u8::MAX as usize {
+            return None;
+        }
+
+        let mut encoded = Result::send(backspace as u8, &output);
+        encoded.action = result.action;
+        encoded.flags = result.flags;
+        Some(encoded)
+    }
+
+    /// on_key_ext before output encoding (Unicode result)
+    fn handle_key(&mut self, key: u16, caps: bool, ctrl: bool, shift: bool) -> Result {
+        self.traced(key, caps, ctrl, shift, None, |e| {
+            e.process_key(key, caps, ctrl, shift)
+        })
+    }
+
+    /// `handle_key` without tracing
+    fn process_key(&mut self, key: u16, caps: bool, ctrl: bool, shift: bool) -> Result {
         // Issue #129: Process shortcuts even when IME is disabled
         // Only bypass completely for Ctrl/Cmd modifier keys
         if ctrl {
+            self.trace.step("ctrl");
             self.clear();
             self.word_history.clear();
             self.spaces_after_commit = 0;
+            self.symbol_escape_pending = false;
             return Result::none();
         }
 
         // When IME is disabled, process shortcuts but skip Vietnamese transforms
         // This allows both word shortcuts (btw → by the way) and symbol shortcuts (-> → →)
         if !self.enabled {
+            self.trace.step("disabled");
             // Clear Vietnamese state
             self.buf.clear();
             self.raw_input.clear();
@@ -723,6 +1542,7 @@ impl Engine {
                         true, // is_word_boundary = true for word shortcuts
                         input_method,
                     ) {
+                        self.trace.step("shortcut");
                         let output: Vec<char> = m.output.chars().collect();
                         let backspace_count = m.backspace_count as u8;
                         self.shortcut_prefix.clear();
@@ -752,6 +1572,7 @@ impl Engine {
                         false,
                         input_method,
                     ) {
+                        self.trace.step("shortcut");
                         let output: Vec<char> = m.output.chars().collect();
                         let backspace_count = (m.backspace_count as u8).saturating_sub(1);
                         self.shortcut_prefix.clear();
@@ -775,9 +1596,20 @@ impl Engine {
             return Result::none();
         }
 
+        // VIQR: punctuation symbols double as diacritics (a' → á, o+ → ơ, a( → ă)
+        // Custom methods may map punctuation too ([ → ơ). Must run before
+        // break-key handling, which would otherwise end the word
+        if self.method == 2 || (self.custom_method.is_some() && keys::is_break_ext(key, shift)) {
+            if let Some(result) = self.try_symbol_modifier(key, caps, shift) {
+                self.trace.step("symbol_modifier");
+                return result;
+            }
+        }
+
         // Check for word boundary shortcuts ONLY on SPACE
         // Also auto-restore invalid Vietnamese to raw English
         if key == keys::SPACE {
+            self.trace.step("space");
             // Handle pending mark revert pop on space (end of word)
             // When telex_double_raw is set, we use it directly for restore, no pop needed.
             // The telex_double_raw contains the exact original input before any modification.
@@ -795,10 +1627,23 @@ impl Engine {
             // First check for shortcut
             let shortcut_result = self.try_word_boundary_shortcut();
             if shortcut_result.action != 0 {
+                self.trace.step("shortcut");
                 self.clear();
                 return shortcut_result;
             }
 
+            // Vietnamese form before the restore decision, and whether the
+            // word was already restored mid-word
+            let decision =
+                (self.english_auto_restore && self.had_any_transform).then(|| {
+                    match &self.restored_mid_word {
+                        Some((vietnamese, len)) if *len == self.buf.len() => {
+                            (vietnamese.clone(), true)
+                        }
+                        _ => (self.buf.to_full_string(), false),
+                    }
+                });
+
             // Auto-restore: if buffer has transforms but is invalid Vietnamese,
             // restore to raw English (like ESC but triggered by space)
             let restore_result = self.try_auto_restore_on_space();
@@ -808,16 +1653,52 @@ impl Engine {
             // Example: "restore" → buffer was "rếtore" (6 chars), raw_input has 7 keys
             // After this, buffer has "restore" (7 chars) for correct history
             if restore_result.action != 0 {
+                self.trace.step("auto_restore");
                 self.buf.clear();
                 for &(key, caps, _) in &self.raw_input {
                     self.buf.push(Char::new(key, caps));
                 }
             }
 
+            // Words that went through the restore decision teach the lexicon
+            let committed = self.buf.to_full_string();
+            self.learn_from_undo(&committed);
+            self.last_commit = None;
+            if let Some((vietnamese, restored_mid_word)) = decision {
+                // Learn the word as it reached the screen. A reverted mark
+                // ("ass" → "as") says nothing about the word typed without it.
+                if self.lexicon_learning && !self.had_mark_revert {
+                    let shown = if restore_result.action != 0 {
+                        restore_result.chars[..restore_result.count as usize]
+                            .iter()
+                            .filter_map(|&c| char::from_u32(c))
+                            .collect::<String>()
+                            .trim_end()
+                            .to_string()
+                    } else {
+                        committed
+                    };
+                    self.lexicon.learn(&shown);
+                }
+                let english = String::from_iter(self.typed_chars());
+                if vietnamese != english {
+                    self.last_commit = Some(CommitForms {
+                        vietnamese,
+                        english,
+                        restored: restored_mid_word || restore_result.action != 0,
+                    });
+                }
+            }
+
             // Push buffer to history before clearing (for backspace-after-space feature)
             if !self.buf.is_empty() {
                 self.word_history.push(self.buf.clone());
                 self.spaces_after_commit = 1; // First space after word
+                if self.completion {
+                    let words = self.word_history.recent(MAX_PHRASE_WORDS);
+                    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+                    self.phrases.learn(&words);
+                }
             } else if self.spaces_after_commit > 0 {
                 // Additional space after commit - increment counter
                 self.spaces_after_commit = self.spaces_after_commit.saturating_add(1);
@@ -838,6 +1719,7 @@ impl Engine {
         // ESC key: restore to raw ASCII (undo all Vietnamese transforms)
         // Only if esc_restore is enabled by user
         if key == keys::ESC {
+            self.trace.step("esc");
             let result = if self.esc_restore_enabled {
                 self.restore_to_raw()
             } else {
@@ -851,8 +1733,15 @@ impl Engine {
 
         // Issue #159: In Telex mode, `]` → ư and `[` → ơ
         // caps affects revert: ]] → ], uppercase (Shift/CapsLock) → }
-        if self.method == 0 && (key == keys::RBRACKET || key == keys::LBRACKET) {
+        // Custom methods: only when w types the horn and the bracket has no other role
+        let bracket_vowel = match key {
+            keys::RBRACKET => Some(keys::U),
+            keys::LBRACKET => Some(keys::O),
+            _ => None,
+        };
+        if bracket_vowel.is_some_and(|v| self.w_types_horn(v) && !self.is_method_key(key, shift)) {
             if let Some(result) = self.try_bracket_as_vowel(key, caps) {
+                self.trace.step("bracket_vowel");
                 return result;
             }
         }
@@ -861,6 +1750,7 @@ impl Engine {
         // Also trigger auto-restore for invalid Vietnamese before clearing
         // Use is_break_ext to handle shifted symbols like @, !, #, etc.
         if keys::is_break_ext(key, shift) {
+            self.trace.step("break");
             // Issue #107 + Bug #11: When buffer is empty AND we're at true start of input
             // (no word history), accumulate break chars for shortcuts.
             // This allows shortcuts like "#fne", "->", "=>" to work.
@@ -894,6 +1784,7 @@ impl Engine {
                         // Found a match! Send the replacement with key_consumed flag
                         // Note: backspace_count - 1 because current key hasn't been typed yet
                         // Example: "->" trigger has backspace_count=2, but only '-' is on screen
+                        self.trace.step("shortcut");
                         let output: Vec<char> = m.output.chars().collect();
                         let backspace_count = (m.backspace_count as u8).saturating_sub(1);
                         self.shortcut_prefix.clear();
@@ -909,6 +1800,7 @@ impl Engine {
                         self.pending_capitalize = true;
                         self.saw_sentence_ending = false;
                     }
+                    self.trace.step("shortcut_prefix");
                     return Result::none(); // Let the char pass through, keep accumulating
                 }
             }
@@ -940,6 +1832,7 @@ impl Engine {
             if let Some(ch) = trigger_char {
                 let shortcut_result = self.try_word_boundary_shortcut_with_char(ch);
                 if shortcut_result.action != 0 {
+                    self.trace.step("shortcut");
                     self.clear();
                     self.word_history.clear();
                     self.spaces_after_commit = 0;
@@ -948,6 +1841,9 @@ impl Engine {
             }
 
             let restore_result = self.try_auto_restore_on_break();
+            if restore_result.action != 0 {
+                self.trace.step("auto_restore");
+            }
             self.clear();
             self.word_history.clear();
             self.spaces_after_commit = 0;
@@ -963,6 +1859,7 @@ impl Engine {
         }
 
         if key == keys::DELETE {
+            self.trace.step("delete");
             // Backspace-after-space feature: restore previous word when all spaces deleted
             // Track spaces typed after commit, restore word when counter reaches 0
             if self.spaces_after_commit > 0 && self.buf.is_empty() {
@@ -970,12 +1867,15 @@ impl Engine {
                 if self.spaces_after_commit == 0 {
                     // All spaces deleted - restore the word buffer
                     if let Some(restored_buf) = self.word_history.pop() {
+                        self.trace.step("restore_previous_word");
                         // Restore raw_input from buffer (for ESC restore to work)
                         self.restore_raw_input_from_buffer(&restored_buf);
                         self.buf = restored_buf;
                         // Mark that buffer was restored - if user types new letter,
                         // clear buffer first (they want fresh word, not append)
                         self.restored_pending_clear = true;
+                        // Only the newest word's decision is known
+                        self.undo_forms = self.last_commit.take();
                     }
                 }
                 // Delete one space
@@ -1052,6 +1952,7 @@ impl Engine {
             // Reset stroke_reverted on backspace so user can re-trigger stroke
             // e.g., "ddddd" → "dddd", then backspace×3 → "d", then "d" → "đ"
             self.stroke_reverted = false;
+            self.quick_telex_reverted = false;
             // Issue #217: Reset reverted_circumflex_key on backspace so user can re-trigger circumflex
             // e.g., "eee" → "ee", then backspace×2 → "", type "phe" → "phê" (not "phee")
             self.reverted_circumflex_key = None;
@@ -1082,7 +1983,7 @@ impl Engine {
         // For pure ASCII restored words (like "shortcuts"), also clear on vowels
         // unless they're mark/tone keys (allow "ban" + restore + "s" → "bán")
         if self.restored_pending_clear && keys::is_letter(key) {
-            let m = input::get(self.method);
+            let m = self.input_method();
             let is_modifier = m.mark(key).is_some() || m.tone(key).is_some() || m.remove(key);
             // Clear buffer when letter is NOT a modifier (mark/tone/remove):
             // - Vietnamese restored: clear on consonant (vowels may add diacritics)
@@ -1095,6 +1996,7 @@ impl Engine {
                 keys::is_consonant(key) && !is_modifier
             };
             if should_clear {
+                self.trace.step("restored_word_cleared");
                 self.clear();
             }
             // Reset flags regardless - user is now actively typing
@@ -1112,6 +2014,7 @@ impl Engine {
         // Auto-capitalize: force uppercase for first letter after sentence-ending punctuation
         let was_auto_capitalized = self.pending_capitalize && keys::is_letter(key) && !caps;
         let effective_caps = if self.pending_capitalize && keys::is_letter(key) {
+            self.trace.step("auto_capitalize");
             self.pending_capitalize = false;
             self.saw_sentence_ending = false; // Reset after capitalizing
             self.auto_capitalize_used = true; // Track that we used auto-capitalize
@@ -1157,7 +2060,7 @@ impl Engine {
 
     /// Main processing pipeline - pattern-based
     fn process(&mut self, key: u16, caps: bool, shift: bool) -> Result {
-        let m = input::get(self.method);
+        let m = self.input_method();
 
         // Handle pending mark revert pop: if previous key was a mark revert,
         // reset the flag. When telex_double_raw is set, we use it directly for
@@ -1187,8 +2090,8 @@ impl Engine {
         //   e.g., "dod" → "đo" + 'o' → "đô" (user typed d-o-d-o fast, intended "ddoo")
         // - Stroke keys ('d') - handled separately in try_stroke for proper revert behavior
         //   e.g., "dadd" → "dad" (d reverts stroke and adds itself, not "dadd")
-        let is_mark_key = m.mark(key).is_some();
-        let is_tone_key = m.tone(key).is_some();
+        let is_mark_key = m.mark_with_shift(key, shift).is_some();
+        let is_tone_key = m.tone_with_shift(key, shift).is_some();
         let is_stroke_key = m.stroke(key);
 
         if keys::is_letter(key)
@@ -1217,6 +2120,7 @@ impl Engine {
             if !is_valid(&raw_keys) && !is_valid(&buf_keys) && !is_triple_o_word {
                 // Invalid pattern - revert stroke and rebuild from raw_input
                 if let Some(raw_chars) = self.build_raw_chars() {
+                    self.trace.step("short_stroke_revert");
                     // Calculate backspace: screen shows buffer content (e.g., "đe")
                     let backspace = self.buf.len() as u8;
 
@@ -1240,9 +2144,9 @@ impl Engine {
         // Skip this check for tone keys (w, a, e, o in Telex) - they apply tone modifiers
         // Issue: "hojpow" was incorrectly reverting because 'w' was treated as consonant
         // creating invalid "pw" final, but 'w' is a horn modifier that should switch ộ → ợ
-        let is_mark_key = m.mark(key).is_some();
+        let is_mark_key = m.mark_with_shift(key, shift).is_some();
         let is_stroke_key = m.stroke(key);
-        let is_tone_key = m.tone(key).is_some();
+        let is_tone_key = m.tone_with_shift(key, shift).is_some();
         if keys::is_consonant(key)
             && !is_mark_key
             && !is_stroke_key
@@ -1281,6 +2185,7 @@ impl Engine {
                 };
 
                 if would_be_invalid {
+                    self.trace.step("delayed_circumflex_revert");
                     // Invalid pattern detected - revert circumflex but keep existing marks
                     // This handles "expect" → e + x(ngã) + p + e(circumflex) → "ẽp" + c
                     // Result should be "ẽpec" (keep ngã, remove circumflex, add 'c')
@@ -1347,20 +2252,31 @@ impl Engine {
             (key == keys::S || key == keys::F) && self.is_vietnamese_triple_o_word();
         let skip_after_revert = self.had_circumflex_revert && !is_valid_triple_o_tone;
 
+        // Quick initial consonant: expand shorthand when the first vowel arrives
+        // "fa" → "pha", "jo" → "gio", "wa" → "qua" (only if the syllable is valid)
+        if self.quick_consonants != 0 && keys::is_vowel(key) {
+            if let Some(result) = self.try_quick_initial(key, caps, shift) {
+                self.trace.step("quick_initial");
+                return result;
+            }
+        }
+
         // Check modifiers by scanning buffer for patterns
 
         // 1. Stroke modifier (d → đ)
         if !skip_vni_modifiers && m.stroke(key) {
             if let Some(result) = self.try_stroke(key, caps) {
+                self.trace.step("stroke");
                 return result;
             }
         }
 
         // 2. Tone modifier (circumflex, horn, breve)
         if !skip_vni_modifiers && !skip_after_revert {
-            if let Some(tone_type) = m.tone(key) {
+            if let Some(tone_type) = m.tone_with_shift(key, shift) {
                 let targets = m.tone_targets(key);
                 if let Some(result) = self.try_tone(key, caps, tone_type, targets) {
+                    self.trace.step("tone");
                     return result;
                 }
             }
@@ -1368,8 +2284,9 @@ impl Engine {
 
         // 3. Mark modifier
         if !skip_vni_modifiers && !skip_after_revert {
-            if let Some(mark_val) = m.mark(key) {
+            if let Some(mark_val) = m.mark_with_shift(key, shift) {
                 if let Some(result) = self.try_mark(key, caps, mark_val) {
+                    self.trace.step("mark");
                     return result;
                 }
             }
@@ -1380,22 +2297,232 @@ impl Engine {
         // This allows shortcuts like "zz" to work when buffer has no marks/tones to remove
         if !skip_vni_modifiers && m.remove(key) {
             if let Some(result) = self.try_remove() {
+                self.trace.step("remove");
                 return result;
             }
         }
 
         // 5. In Telex: "w" as vowel "ư" when valid Vietnamese context
         // Examples: "w" → "ư", "nhw" → "như", but "kw" → "kw" (invalid)
-        if self.method == 0 && key == keys::W {
+        if key == keys::W && self.w_types_horn(keys::U) {
             if let Some(result) = self.try_w_as_vowel(caps) {
+                self.trace.step("w_as_vowel");
+                return result;
+            }
+        }
+
+        // 6. Quick Telex: doubled consonant → digraph (cc → ch, nn → ng)
+        // Not for letters a custom method uses as modifiers (q as remove key)
+        if self.method == 0 && self.quick_telex && !self.is_method_key(key, shift) {
+            if let Some(result) = self.try_quick_telex(key, caps) {
+                self.trace.step("quick_telex");
+                return result;
+            }
+        }
+
+        // 7. Quick final consonant: g → ng, h → nh, k → ch after a vowel
+        if self.quick_consonants != 0 {
+            if let Some(result) = self.try_quick_final(key, caps) {
+                self.trace.step("quick_final");
                 return result;
             }
         }
 
         // Not a modifier - normal letter
+        self.trace.step("letter");
         self.handle_normal_letter(key, caps)
     }
 
+    /// Expand a quick initial shorthand before the first vowel is processed
+    ///
+    /// Runs the vowel through the normal pipeline on the expanded buffer, then
+    /// replaces the shorthand on screen with the result.
+    /// With allow_foreign_consonants, f/j/w stay foreign initials when their
+    /// shorthand is off or the expansion would be invalid.
+    fn try_quick_initial(&mut self, key: u16, caps: bool, shift: bool) -> Option<Result> {
+        if self.buf.len() != 1 {
+            return None;
+        }
+        let first = *self.buf.get(0)?;
+        if first.tone != tone::NONE || first.mark != mark::NONE || !self.quick_consonant(first.key)
+        {
+            return None;
+        }
+        let &(_, is_initial, expansion) =
+            QUICK_CONSONANTS.iter().find(|&&(k, _, _)| k == first.key)?;
+        if !is_initial {
+            return None;
+        }
+        // "ji" → "gi", not "gii"
+        let expansion = if first.key == keys::J && key == keys::I {
+            &expansion[..1]
+        } else {
+            expansion
+        };
+
+        let mut syllable: Vec<u16> = expansion.to_vec();
+        syllable.push(key);
+        if !is_valid_with_foreign(&syllable, self.allow_foreign_consonants) {
+            return None;
+        }
+
+        let before = self.rendered();
+        self.buf.clear();
+        for (i, &k) in expansion.iter().enumerate() {
+            // "Fa" → "Pha", "FA" → "PHA"
+            self.buf.push(Char::new(
+                k,
+                if i == 0 {
+                    first.caps
+                } else {
+                    first.caps && caps
+                },
+            ));
+        }
+        self.had_any_transform = true;
+        self.process(key, caps, shift);
+        Some(self.rebuild_replacing(&before))
+    }
+
+    /// Expand a quick final shorthand typed right after a vowel
+    /// "ag" → "ang", "ih" → "inh", "ak" → "ach" (only if the syllable is valid)
+    fn try_quick_final(&mut self, key: u16, caps: bool) -> Option<Result> {
+        if !self.quick_consonant(key) {
+            return None;
+        }
+        let &(_, is_initial, expansion) = QUICK_CONSONANTS.iter().find(|&&(k, _, _)| k == key)?;
+        if is_initial || !keys::is_vowel(self.buf.last()?.key) {
+            return None;
+        }
+
+        let mut syllable: Vec<u16> = self.buf.iter().map(|c| c.key).collect();
+        let mut tones: Vec<u8> = self.buf.iter().map(|c| c.tone).collect();
+        syllable.extend_from_slice(expansion);
+        tones.resize(syllable.len(), tone::NONE);
+        if !is_valid_with_tones_and_foreign(&syllable, &tones, self.allow_foreign_consonants) {
+            return None;
+        }
+
+        let before = self.rendered();
+        for &k in expansion {
+            // Normal letter handling repositions the tone mark for the final
+            self.handle_normal_letter(k, caps);
+        }
+        self.had_any_transform = true;
+        Some(self.rebuild_replacing(&before))
+    }
+
+    /// Current buffer as displayed on screen
+    fn rendered(&self) -> Vec<char> {
+        let result = self.rebuild_from(0);
+        result.chars[..result.count as usize]
+            .iter()
+            .filter_map(|&c| char::from_u32(c))
+            .collect()
+    }
+
+    /// Output that turns `before` (the word currently on screen) into the buffer
+    /// Only the changed suffix is replaced.
+    fn rebuild_replacing(&self, before: &[char]) -> Result {
+        let after = self.rendered();
+        let common = before
+            .iter()
+            .zip(&after)
+            .take_while(|(a, b)| a == b)
+            .count();
+        Result::send_consumed((before.len() - common) as u8, &after[common..])
+    }
+
+    /// Apply a combining diacritic typed after a letter ("a" + U+0301 → "á")
+    ///
+    /// Lets decomposed input from the platform edit the buffer like a mark or
+    /// tone key. Returns None when the mark doesn't fit the last letter.
+    fn try_combining(&mut self, ch: char) -> Option<Result> {
+        let pos = self.buf.len().checked_sub(1)?;
+        let last = *self.buf.get(pos)?;
+        let parsed = chars::ParsedChar {
+            key: last.key,
+            caps: last.caps,
+            tone: last.tone,
+            mark: last.mark,
+            stroke: last.stroke,
+        };
+        let applied = chars::apply_combining(parsed, ch)?;
+        if let Some(c) = self.buf.get_mut(pos) {
+            c.tone = applied.tone;
+            c.mark = applied.mark;
+        }
+        self.had_any_transform = true;
+        self.last_transform = None;
+        Some(self.rebuild_from(pos))
+    }
+
+    /// Quick Telex: expand a doubled consonant into its digraph
+    ///
+    /// - Initial: cc → ch, gg → gi, kk → kh, nn → ng, pp → ph, qq → qu, tt → th
+    /// - Final (after a vowel): cc → ch, nn → ng, only if the word stays valid
+    /// - Third press reverts: ccc → cc (same as ddd → dd)
+    ///
+    /// Final "pp"/"tt" never expand, so "apple", "little" type as-is.
+    fn try_quick_telex(&mut self, key: u16, caps: bool) -> Option<Result> {
+        // Revert: ccc → cc
+        if self.last_transform == Some(Transform::QuickTelex(key)) {
+            self.buf.pop();
+            self.buf.push(Char::new(key, caps));
+            self.last_transform = None;
+            self.quick_telex_reverted = true;
+            // raw_input: [c, c, c] → [c, c] (drop the key that triggered expansion)
+            if self.raw_input.len() >= 2 {
+                let current = self.raw_input.pop();
+                self.raw_input.pop();
+                if let Some(c) = current {
+                    self.raw_input.push(c);
+                }
+            }
+            let ch = utils::key_to_char(key, caps)?;
+            return Some(Result::send_consumed(1, &[ch]));
+        }
+
+        if self.quick_telex_reverted {
+            return None;
+        }
+
+        let second = match key {
+            keys::C | keys::K | keys::P | keys::T => keys::H,
+            keys::G => keys::I,
+            keys::N => keys::G,
+            keys::Q => keys::U,
+            _ => return None,
+        };
+        let last = self.buf.last()?;
+        if last.key != key || last.stroke {
+            return None;
+        }
+
+        let is_initial = self.buf.len() == 1;
+        if !is_initial {
+            // Final digraph: only ch/ng, right after a vowel, and the word stays valid
+            if !matches!(key, keys::C | keys::N) {
+                return None;
+            }
+            let prev = self.buf.get(self.buf.len() - 2)?;
+            if !keys::is_vowel(prev.key) {
+                return None;
+            }
+            let mut buffer_keys: Vec<u16> = self.buf.iter().map(|c| c.key).collect();
+            buffer_keys.push(second);
+            if !is_valid_with_foreign(&buffer_keys, self.allow_foreign_consonants) {
+                return None;
+            }
+        }
+
+        self.buf.push(Char::new(second, caps));
+        self.last_transform = Some(Transform::QuickTelex(key));
+        self.had_any_transform = true;
+        let ch = utils::key_to_char(second, caps)?;
+        Some(Result::send_consumed(0, &[ch]))
+    }
+
     /// Try word boundary shortcuts (triggered by space, punctuation, etc.)
     /// The `trigger_char` is appended to the output (space for space, punctuation for punctuation)
     fn try_word_boundary_shortcut_with_char(&mut self, trigger_char: char) -> Result {
@@ -1468,6 +2595,11 @@ impl Engine {
             return None;
         }
 
+        // Quick initial w → qu: keep leading w as a letter so it can expand
+        if self.buf.is_empty() && self.quick_consonant(keys::W) {
+            return None;
+        }
+
         // If shortcut was previously skipped, don't try again
         if matches!(self.last_transform, Some(Transform::WShortcutSkipped)) {
             return None;
@@ -1682,7 +2814,15 @@ impl Engine {
 
         // Find position of un-stroked 'd' to apply stroke
         // Also track if this is a short pattern stroke (revertible)
-        let (pos, is_short_pattern_stroke) = if self.method == 0 {
+        let (pos, is_short_pattern_stroke) = if self.method == 2 {
+            // VIQR: 'd' is a plain letter, so only adjacent "dd" → "đ"
+            let last_pos = self.buf.len().checked_sub(1)?;
+            let last_char = self.buf.get(last_pos)?;
+            if last_char.key != keys::D || last_char.stroke {
+                return None;
+            }
+            (last_pos, false)
+        } else if self.method == 0 {
             // Telex: First try adjacent 'd' (last char is un-stroked d)
             let last_pos = self.buf.len().checked_sub(1)?;
             let last_char = self.buf.get(last_pos)?;
@@ -2645,7 +3785,7 @@ impl Engine {
                         let raw_chars: Vec<char> = self
                             .raw_input
                             .iter()
-                            .filter_map(|&(k, c, s)| utils::key_to_char_ext(k, c, s))
+                            .filter_map(|&(k, c, s)| self.raw_char(k, c, s))
                             .collect();
                         let backspace = self.buf.len() as u8;
                         self.buf.clear();
@@ -2927,7 +4067,7 @@ impl Engine {
                             .filter_map(|&(k, caps, _)| utils::key_to_char(k, caps))
                             .collect::<String>()
                             .to_lowercase();
-                        if english_dict::is_english_word(&raw_str) {
+                        if self.is_english_word(&raw_str) {
                             // Raw input is English - don't apply delayed circumflex
                             // Let the letter be added normally, auto-restore will handle it
                         } else {
@@ -3328,7 +4468,7 @@ impl Engine {
             .filter_map(|&(k, caps, _)| utils::key_to_char(k, caps))
             .collect::<String>()
             .to_lowercase();
-        let is_english_word = english_dict::is_english_word(&raw_str);
+        let is_english_word = self.is_english_word(&raw_str);
 
         // Find vowel with tone mark (sắc/huyền/hỏi/ngã/nặng)
         let tone_info: Option<(usize, u8)> = self
@@ -3503,7 +4643,7 @@ impl Engine {
             .filter_map(|&(key, caps, _)| utils::key_to_char(key, caps))
             .collect::<String>()
             .to_lowercase();
-        if english_dict::is_english_word(&raw_str) {
+        if self.is_english_word(&raw_str) {
             return None;
         }
 
@@ -3648,6 +4788,7 @@ impl Engine {
 
     /// Common revert logic: clear modifier, add key to buffer, rebuild output
     fn revert_and_rebuild(&mut self, pos: usize, key: u16, caps: bool) -> Result {
+        self.trace.step("revert_and_rebuild");
         // Calculate backspace BEFORE adding key (based on old buffer state)
         // Use saturating_sub to prevent underflow if pos > buf.len()
         let backspace = self.buf.len().saturating_sub(pos) as u8;
@@ -3675,6 +4816,7 @@ impl Engine {
 
     /// Revert tone transformation
     fn revert_tone(&mut self, key: u16, caps: bool) -> Result {
+        self.trace.step("revert_tone");
         self.last_transform = None;
         // Issue #211: Track which vowel triggered revert for extended vowel mode
         // After revert, subsequent same-key vowels append raw instead of re-transforming
@@ -3720,6 +4862,7 @@ impl Engine {
     /// Standard behavior: "ass" → "as" (first 's' was modifier, second 's' reverts + outputs one 's')
     /// This matches standard Vietnamese IME behavior (UniKey, ibus-unikey, etc.)
     fn revert_mark(&mut self, key: u16, caps: bool) -> Result {
+        self.trace.step("revert_mark");
         self.last_transform = None;
         self.had_mark_revert = true; // Track for auto-restore
                                      // Set had_telex_transform for whitelist-based auto-restore
@@ -3761,6 +4904,7 @@ impl Engine {
 
     /// Revert stroke transformation at specific position
     fn revert_stroke(&mut self, key: u16, pos: usize) -> Result {
+        self.trace.step("revert_stroke");
         self.last_transform = None;
 
         if let Some(c) = self.buf.get_mut(pos) {
@@ -3906,7 +5050,7 @@ impl Engine {
                     .filter_map(|&(k, caps, _)| utils::key_to_char(k, caps))
                     .collect::<String>()
                     .to_lowercase();
-                if english_dict::is_english_word(&raw_str) {
+                if self.is_english_word(&raw_str) {
                     // Raw input is English - skip circumflex, add vowel normally
                     // The auto-restore will handle restoring the English word
                 } else {
@@ -4211,7 +5355,7 @@ impl Engine {
             // not true consonants. User typing "đườ" + 's' wants to add sắc mark, not restore.
             //
             // Only run if english_auto_restore is enabled (experimental feature)
-            let im = input::get(self.method);
+            let im = self.input_method();
             let is_mark_key = im.mark(key).is_some();
             if self.english_auto_restore
                 && keys::is_consonant(key)
@@ -4225,10 +5369,14 @@ impl Engine {
                 if let Some(prev_char) = self.buf.get(self.buf.len() - 2) {
                     let prev_has_mark = prev_char.mark > 0;
 
-                    if prev_has_mark && self.has_english_modifier_pattern(false) {
+                    if prev_has_mark
+                        && self.has_english_modifier_pattern(false)
+                        && !self.lexicon_keeps_vietnamese()
+                    {
                         // Clear English pattern detected - restore to raw
                         if let Some(raw_chars) = self.build_raw_chars() {
                             let backspace = (self.buf.len() - 1) as u8;
+                            let vietnamese = self.buf.to_full_string();
 
                             // Repopulate buffer with restored content (plain chars, no marks)
                             // IMPORTANT: Use raw_chars (collapsed output) not raw_input
@@ -4243,6 +5391,8 @@ impl Engine {
                             }
 
                             self.last_transform = None;
+                            self.trace.step("auto_restore_mid_word");
+                            self.restored_mid_word = Some((vietnamese, self.buf.len()));
                             return Result::send_consumed(backspace, &raw_chars);
                         }
                     }
@@ -4280,6 +5430,7 @@ impl Engine {
         if !self.has_w_as_vowel_transform() {
             return Result::none();
         }
+        self.trace.step("revert_w_as_vowel");
 
         // Find all horn transforms to revert
         let horn_positions: Vec<usize> = self
@@ -4411,6 +5562,7 @@ impl Engine {
         self.pending_breve_pos = None;
         self.pending_u_horn_pos = None;
         self.stroke_reverted = false;
+        self.quick_telex_reverted = false;
         self.had_mark_revert = false;
         self.pending_mark_revert_pop = false;
         self.had_any_transform = false;
@@ -4423,6 +5575,8 @@ impl Engine {
         self.restored_pending_clear = false;
         self.restored_is_ascii = false;
         self.shortcut_prefix.clear();
+        self.undo_forms = None;
+        self.restored_mid_word = None;
     }
 
     /// Clear everything including word history
@@ -4438,6 +5592,7 @@ impl Engine {
         // This prevents incorrect capitalization after copy-paste
         self.pending_capitalize = false;
         self.saw_sentence_ending = false;
+        self.symbol_escape_pending = false;
     }
 
     /// Get the full composed buffer as a Vietnamese string with diacritics.
@@ -4466,23 +5621,18 @@ impl Engine {
     ///
     /// Used when native app detects cursor at word boundary and wants to edit.
     /// Parses Vietnamese characters back to buffer components.
+    /// Frontends that can read the document should prefer
+    /// `set_surrounding_text`, which also rebuilds keystrokes and history.
     pub fn restore_word(&mut self, word: &str) {
         self.clear();
-        let mut is_ascii = true;
-        for c in word.chars() {
-            if let Some(parsed) = chars::parse_char(c) {
-                let mut ch = Char::new(parsed.key, parsed.caps);
-                ch.tone = parsed.tone;
-                ch.mark = parsed.mark;
-                ch.stroke = parsed.stroke;
-                self.buf.push(ch);
-                self.raw_input.push((parsed.key, parsed.caps, false));
-                // Check if this char has any Vietnamese diacritics
-                if parsed.tone != 0 || parsed.mark != 0 || parsed.stroke {
-                    is_ascii = false;
-                }
-            }
-        }
+        let buf = word_buffer(word);
+        self.restore_raw_input_from_buffer(&buf);
+        self.buf = buf;
+        // Check if any char has Vietnamese diacritics
+        let is_ascii = self
+            .buf
+            .iter()
+            .all(|c| c.tone == 0 && c.mark == 0 && !c.stroke);
         // Mark that buffer was restored from screen - if user types a regular consonant,
         // clear buffer first (they want fresh word, not append to restored word)
         // This allows: click on "shortcuts" → type "Nuw" → get "Nư" (not "shortcutsNuw")
@@ -4493,6 +5643,163 @@ impl Engine {
         }
     }
 
+    /// Alternatives for the word being typed, best first
+    ///
+    /// The first candidate is what is on screen; the rest are the English
+    /// word, the nearest dictionary word and the raw keystrokes, when they
+    /// differ. Empty when no word is being typed.
+    /// Example: "text" in Telex → ["tẽt", "text"]; "dd" → ["đ", "dd"].
+    pub fn candidates(&self) -> Vec<Candidate> {
+        if self.buf.is_empty() || self.raw_input.is_empty() {
+            return Vec::new();
+        }
+        let shown = self.buf.to_full_string();
+        let typed: String = self.typed_chars().into_iter().collect();
+
+        let mut list = vec![Candidate {
+            text: shown.clone(),
+            kind: CandidateKind::Vietnamese,
+        }];
+        let english = [
+            self.build_raw_chars().map(String::from_iter),
+            Some(typed.clone()),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|w| self.is_english_word(&w.to_lowercase()));
+        let valid = vietnamese_spellcheck::check_with_style_and_foreign(
+            &shown,
+            self.modern_tone,
+            self.allow_foreign_consonants,
+        );
+        let nearest = if valid {
+            None
+        } else {
+            data::suggest::suggest(&shown, self.modern_tone, 1).pop()
+        };
+        let others = [
+            (english, CandidateKind::English),
+            (nearest, CandidateKind::Dictionary),
+            (Some(typed), CandidateKind::Raw),
+        ];
+        for (text, kind) in others {
+            if let Some(text) = text {
+                if !list.iter().any(|c| c.text == text) {
+                    list.push(Candidate { text, kind });
+                }
+            }
+        }
+        list
+    }
+
+    /// Replace the word on screen with `candidates()[index]`
+    ///
+    /// The word is finished: the next key starts a new one. Returns
+    /// `Result::none()` for an index out of range.
+    pub fn commit_candidate(&mut self, index: usize) -> Result {
+        let Some(candidate) = self.candidates().into_iter().nth(index) else {
+            return Result::none();
+        };
+        let result = self.replace_word(&candidate.text);
+        self.clear();
+        result
+    }
+
+    /// Output that replaces the word on screen with `text`, in the output charset
+    /// `Result::none()` if the encoded text doesn't fit.
+    fn replace_word(&self, text: &str) -> Result {
+        let before = self.rendered();
+        let chars: Vec<char> = text.chars().collect();
+        let result = Result::send(before.len() as u8, &chars);
+        if self.charset == Charset::Unicode {
+            result
+        } else {
+            self.encode_result(result, &before, 0)
+                .unwrap_or_else(Result::none)
+        }
+    }
+
+    /// Up to `limit` completions for the word being typed, best first
+    ///
+    /// Completes the current word from learned phrases, bundled syllable
+    /// frequencies and the dictionary, with the words committed before it
+    /// as context ("Vi" → "Việt Nam" once typed; "Việt " → "nam").
+    /// Empty unless enabled with `set_completion`.
+    pub fn completions(&self, limit: usize) -> Vec<String> {
+        if !self.completion {
+            return Vec::new();
+        }
+        let context = self.word_history.recent(MAX_PHRASE_WORDS - 1);
+        let context: Vec<&str> = context.iter().map(String::as_str).collect();
+        let prefix = self.buf.to_full_string();
+        completion::complete(&context, &prefix, &self.phrases, self.modern_tone, limit)
+    }
+
+    /// Replace the word being typed with `completions()[index]`
+    ///
+    /// The last word of the completion stays editable like a typed one: a
+    /// mark key changes it, space commits it. Words before it count as
+    /// committed. Returns `Result::none()` for an index out of range.
+    pub fn accept_completion(&mut self, index: usize) -> Result {
+        let Some(text) = self.completions(index + 1).into_iter().nth(index) else {
+            return Result::none();
+        };
+        let result = self.replace_word(&text);
+        if result.action == Action::None as u8 {
+            return result;
+        }
+        let mut words: Vec<&str> = text.split(' ').collect();
+        let last = words.pop().unwrap_or_default();
+        for word in words {
+            self.word_history.push(word_buffer(word));
+        }
+        self.restore_word(last);
+        self.restored_pending_clear = false;
+        result
+    }
+
+    /// Learn an exception when a word brought back by backspace ends in the
+    /// form the restore decision didn't pick
+    ///
+    /// "text " auto-restored, then backspace into it and retype "xt" →
+    /// "tẽt ": "tẽt" stays Vietnamese from now on. "dax " kept as "dã", then backspace + x →
+    /// "dax ": "dax" is restored from now on.
+    fn learn_from_undo(&mut self, word: &str) {
+        let Some(forms) = self.undo_forms.take() else {
+            return;
+        };
+        let word = word.to_lowercase();
+        if forms.restored && word == forms.vietnamese.to_lowercase() {
+            self.lexicon
+                .correct(&forms.vietnamese, Language::Vietnamese);
+        } else if !forms.restored && word == forms.english.to_lowercase() {
+            self.lexicon.correct(&forms.english, Language::English);
+        }
+    }
+
+    /// `english_dict` lookup, recorded in the trace
+    fn is_english_word(&self, word: &str) -> bool {
+        self.trace
+            .lookup("english", word, english_dict::is_english_word(word))
+    }
+
+    /// `telex_doubles` whitelist lookup, recorded in the trace
+    fn is_telex_double(&self, word: &str) -> bool {
+        self.trace
+            .lookup("telex_doubles", word, telex_doubles::contains(word))
+    }
+
+    /// The user lexicon says the buffer should stay Vietnamese
+    fn lexicon_keeps_vietnamese(&self) -> bool {
+        if self.lexicon.is_empty() {
+            return false;
+        }
+        let word = self.buf.to_full_string();
+        let language = self.lexicon.language(&word);
+        self.trace
+            .lookup("lexicon", &word, language == Some(Language::Vietnamese))
+    }
+
     /// Check if buffer has transforms and is invalid Vietnamese
     /// Returns the raw chars if restore is needed, None otherwise
     ///
@@ -4517,6 +5824,22 @@ impl Engine {
             return None;
         }
 
+        // User lexicon overrides the built-in word lists
+        if self.lexicon_keeps_vietnamese() {
+            return None;
+        }
+        if !self.lexicon.is_empty() {
+            let typed = self.typed_chars();
+            let word = String::from_iter(&typed);
+            let language = self.lexicon.language(&word);
+            if self
+                .trace
+                .lookup("lexicon", &word, language == Some(Language::English))
+            {
+                return Some(typed);
+            }
+        }
+
         // Issue #211: Skip auto-restore for extended character patterns
         // When user types "ơiiiiii", "điiii", "ôiiii", "vàooooo", etc.
         // This is intentional Vietnamese (casual messaging) not English.
@@ -4636,14 +5959,14 @@ impl Engine {
                     .raw_input
                     .iter()
                     .skip(subsequent_start)
-                    .filter_map(|&(key, caps, shift)| utils::key_to_char_ext(key, caps, shift))
+                    .filter_map(|&(key, caps, shift)| self.raw_char(key, caps, shift))
                     .collect();
                 format!("{}{}", stored.to_lowercase(), subsequent.to_lowercase())
             } else {
                 self.get_raw_input_string()
             };
 
-            if telex_doubles::contains(&raw_str) {
+            if self.is_telex_double(&raw_str) {
                 // Word is in English telex doubles whitelist
                 // Decision logic with Vietnamese-first principle:
                 //
@@ -4662,7 +5985,7 @@ impl Engine {
 
                 let has_stroke = self.buf.iter().any(|c| c.stroke);
                 let buffer_invalid_vn = self.is_buffer_invalid_vietnamese();
-                let raw_in_english_dict = english_dict::is_english_word(&raw_str);
+                let raw_in_english_dict = self.is_english_word(&raw_str);
 
                 // W at end pattern: foreign words like moscow, warsaw, saw, law
                 let w_at_end = self
@@ -4686,7 +6009,7 @@ impl Engine {
                     // If buffer is a known English word, keep it (e.g., "lissa" → "lisa")
                     // If buffer is NOT a known word, restore original (e.g., "larissa" → "larissa")
                     let buffer_str = self.get_buffer_string().to_lowercase();
-                    if !english_dict::is_english_word(&buffer_str) {
+                    if !self.is_english_word(&buffer_str) {
                         // Buffer not in dict → restore to original English
                         return self.build_raw_chars_exact();
                     }
@@ -4717,7 +6040,7 @@ impl Engine {
 
                         if is_double_ss || is_double_ff {
                             let original_lower = stored.to_lowercase();
-                            if english_dict::is_english_word(&original_lower) {
+                            if self.is_english_word(&original_lower) {
                                 // EXCEPTIONS: certain words should keep reverted form (buffer)
                                 // instead of restoring to raw double letter pattern.
                                 // This handles cases where collapsed buffer is more common:
@@ -4797,7 +6120,7 @@ impl Engine {
                 //    - "herer": e-r-e-r (V-M-V-M) → keep "her"
                 //    - "harare": a-r-a-r-e (different vowels a≠e) → skip fix
                 let raw_input_str = self.get_raw_input_string();
-                let raw_is_english = english_dict::is_english_word(&raw_input_str);
+                let raw_is_english = self.is_english_word(&raw_input_str);
                 let chars: Vec<char> = raw_input_str.chars().collect();
 
                 if !raw_is_english && chars.len() >= 4 {
@@ -5388,7 +6711,7 @@ impl Engine {
     fn get_raw_input_string_preserve_case(&self) -> String {
         self.raw_input
             .iter()
-            .filter_map(|&(key, caps, shift)| utils::key_to_char_ext(key, caps, shift))
+            .filter_map(|&(key, caps, shift)| self.raw_char(key, caps, shift))
             .collect()
     }
 
@@ -5442,7 +6765,7 @@ impl Engine {
                     )
             });
 
-            if has_telex_double && english_dict::is_english_word(&raw_str) {
+            if has_telex_double && self.is_english_word(&raw_str) {
                 return true; // Telex double + Not in VN dict + IS in EN dict → invalid VN
             }
         }
@@ -5668,10 +6991,16 @@ impl Engine {
         }
 
         // All keys must be ASCII letters (A-Z)
-        let all_ascii_letters = self.raw_input.iter().all(|(k, _, _)| {
+        // VIQR diacritic symbols are typed as part of the word too ("don't")
+        let m = self.input_method();
+        let all_ascii_letters = self.raw_input.iter().all(|&(k, _, shift)| {
             // Keys are in range A-Z (from keys.rs)
             // Consonants and vowels are valid English letters
-            keys::is_consonant(*k) || keys::is_vowel(*k)
+            keys::is_consonant(k)
+                || keys::is_vowel(k)
+                || (self.method == 2
+                    && (m.mark_with_shift(k, shift).is_some()
+                        || m.tone_with_shift(k, shift).is_some()))
         });
 
         if !all_ascii_letters {
@@ -5714,7 +7043,7 @@ impl Engine {
                 };
                 for i in subsequent_start..self.raw_input.len() {
                     if let Some(&(key, caps, shift)) = self.raw_input.get(i) {
-                        if let Some(ch) = utils::key_to_char_ext(key, caps, shift) {
+                        if let Some(ch) = self.raw_char(key, caps, shift) {
                             result.push(ch);
                         }
                     }
@@ -5726,7 +7055,7 @@ impl Engine {
         let chars: Vec<char> = self
             .raw_input
             .iter()
-            .filter_map(|&(key, caps, shift)| utils::key_to_char_ext(key, caps, shift))
+            .filter_map(|&(key, caps, shift)| self.raw_char(key, caps, shift))
             .collect();
         if chars.is_empty() {
             None
@@ -5753,7 +7082,7 @@ impl Engine {
             let mut chars: Vec<char> = self
                 .raw_input
                 .iter()
-                .filter_map(|&(key, caps, shift)| utils::key_to_char_ext(key, caps, shift))
+                .filter_map(|&(key, caps, shift)| self.raw_char(key, caps, shift))
                 .collect();
 
             // Collapse vowel patterns for English restore (Telex circumflex patterns)
@@ -5839,7 +7168,7 @@ impl Engine {
             if self.had_circumflex_revert && chars.len() >= 2 {
                 let current_str: String = chars.iter().collect::<String>().trim().to_lowercase();
                 // Only attempt collapse if current (double) form is NOT in dict
-                if !english_dict::is_english_word(&current_str) {
+                if !self.is_english_word(&current_str) {
                     let mut i = 0;
                     while i + 1 < chars.len() {
                         let c = chars[i].to_ascii_lowercase();
@@ -5849,7 +7178,7 @@ impl Engine {
                             collapsed.remove(i);
                             let collapsed_str: String =
                                 collapsed.iter().collect::<String>().trim().to_lowercase();
-                            if english_dict::is_english_word(&collapsed_str) {
+                            if self.is_english_word(&collapsed_str) {
                                 chars = collapsed;
                                 continue; // re-check same position
                             }
@@ -5928,7 +7257,7 @@ impl Engine {
             // This section does NOT require had_circumflex_revert flag
             if chars.len() >= 3 && !is_saas_pattern {
                 let current_str: String = chars.iter().collect::<String>().trim().to_lowercase();
-                if !english_dict::is_english_word(&current_str) {
+                if !self.is_english_word(&current_str) {
                     let mut i = 0;
                     // Skip double vowels at the very end (i + 1 == chars.len() - 1)
                     while i + 2 < chars.len() {
@@ -5940,7 +7269,7 @@ impl Engine {
                             collapsed.remove(i);
                             let collapsed_str: String =
                                 collapsed.iter().collect::<String>().trim().to_lowercase();
-                            if english_dict::is_english_word(&collapsed_str) {
+                            if self.is_english_word(&collapsed_str) {
                                 chars = collapsed;
                                 continue; // re-check same position
                             }
@@ -6396,9 +7725,9 @@ impl Engine {
                         let raw_str: String = self
                             .raw_input
                             .iter()
-                            .filter_map(|&(k, c, s)| utils::key_to_char_ext(k, c, s))
+                            .filter_map(|&(k, c, s)| self.raw_char(k, c, s))
                             .collect();
-                        let raw_in_dict = english_dict::is_english_word(&raw_str);
+                        let raw_in_dict = self.is_english_word(&raw_str);
 
                         // If raw is NOT in English dict AND buffer is valid Vietnamese, keep it
                         if !raw_in_dict && !self.is_buffer_invalid_vietnamese() {
@@ -6973,9 +8302,9 @@ impl Engine {
                             let raw_str: String = self
                                 .raw_input
                                 .iter()
-                                .filter_map(|&(k, c, s)| utils::key_to_char_ext(k, c, s))
+                                .filter_map(|&(k, c, s)| self.raw_char(k, c, s))
                                 .collect();
-                            if english_dict::is_english_word(&raw_str) {
+                            if self.is_english_word(&raw_str) {
                                 return true; // Restore to English
                             }
                             // Not English word, keep Vietnamese
@@ -7059,11 +8388,9 @@ impl Engine {
                                         let raw_str: String = self
                                             .raw_input
                                             .iter()
-                                            .filter_map(|&(k, c, s)| {
-                                                utils::key_to_char_ext(k, c, s)
-                                            })
+                                            .filter_map(|&(k, c, s)| self.raw_char(k, c, s))
                                             .collect();
-                                        if english_dict::is_english_word(&raw_str) {
+                                        if self.is_english_word(&raw_str) {
                                             return true; // English word (param, etc.)
                                         }
                                         // Not in English dict → keep Vietnamese (vận, hận, etc.)
@@ -7148,9 +8475,9 @@ impl Engine {
                                 let raw_str: String = self
                                     .raw_input
                                     .iter()
-                                    .filter_map(|&(k, c, s)| utils::key_to_char_ext(k, c, s))
+                                    .filter_map(|&(k, c, s)| self.raw_char(k, c, s))
                                     .collect();
-                                if !english_dict::is_english_word(&raw_str) {
+                                if !self.is_english_word(&raw_str) {
                                     // Not a common English word, keep Vietnamese
                                     continue;
                                 }
@@ -7196,9 +8523,9 @@ impl Engine {
                                 let raw_str: String = self
                                     .raw_input
                                     .iter()
-                                    .filter_map(|&(k, c, s)| utils::key_to_char_ext(k, c, s))
+                                    .filter_map(|&(k, c, s)| self.raw_char(k, c, s))
                                     .collect();
-                                if !english_dict::is_english_word(&raw_str) {
+                                if !self.is_english_word(&raw_str) {
                                     // Not a common English word, keep Vietnamese
                                     continue;
                                 }
@@ -7554,6 +8881,106 @@ impl Engine {
         Some(Result::send_consumed(0, &[vowel_char]))
     }
 
+    /// Apply punctuation symbol as diacritic on the current word (VIQR, custom methods)
+    ///
+    /// - "a'" → "á", "a^" → "â", "o+" → "ơ", "a(" → "ă"
+    /// - Same symbol again reverts and types it literally: "a''" → "a'"
+    /// - Backslash escapes the next symbol: "a\'" → "a'"
+    ///
+    /// Returns None when the key should be handled as ordinary punctuation.
+    fn try_symbol_modifier(&mut self, key: u16, caps: bool, shift: bool) -> Option<Result> {
+        let escaped = std::mem::take(&mut self.symbol_escape_pending);
+        let m = self.input_method();
+        let mark_val = m.mark_with_shift(key, shift);
+        let tone_type = m.tone_with_shift(key, shift);
+
+        if mark_val.is_none() && tone_type.is_none() {
+            // Backslash right after a word arms escape for the next symbol
+            if key == keys::BACKSLASH && !shift && !self.buf.is_empty() {
+                self.symbol_escape_pending = true;
+            }
+            return None;
+        }
+
+        let symbol = break_key_to_char(key, shift)?;
+
+        // Escaped: replace the backslash on screen with the literal symbol
+        if escaped {
+            return Some(Result::send_consumed(1, &[symbol]));
+        }
+
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        // Same symbol typed again: drop the diacritic, keep the symbol as text
+        let revert_pos = if let Some(mark_val) = mark_val {
+            // Only right after this symbol placed the mark, on the vowel it went to
+            match self.last_transform {
+                Some(Transform::Mark(k, v)) if k == key && v == mark_val => (0..self.buf.len())
+                    .rev()
+                    .find(|&i| self.buf.get(i).is_some_and(|c| c.mark == mark_val)),
+                _ => None,
+            }
+        } else if matches!(self.last_transform, Some(Transform::Tone(k, _)) if k == key) {
+            let targets = m.tone_targets(key);
+            (0..self.buf.len()).rev().find(|&i| {
+                self.buf
+                    .get(i)
+                    .is_some_and(|c| targets.contains(&c.key) && c.tone != tone::NONE)
+            })
+        } else {
+            None
+        };
+        if let Some(pos) = revert_pos {
+            let mut start = pos;
+            if let Some(c) = self.buf.get_mut(pos) {
+                if mark_val.is_some() {
+                    c.mark = mark::NONE;
+                } else {
+                    c.tone = tone::NONE;
+                }
+            }
+            // ươ compound: horn was applied to both vowels
+            if mark_val.is_none() && pos > 0 {
+                if let Some(prev) = self.buf.get_mut(pos - 1) {
+                    if prev.key == keys::U && prev.tone == tone::HORN {
+                        prev.tone = tone::NONE;
+                        start = pos - 1;
+                    }
+                }
+            }
+            let rebuilt = self.rebuild_from(start);
+            let mut output: Vec<char> = rebuilt.chars[..rebuilt.count as usize]
+                .iter()
+                .filter_map(|&c| char::from_u32(c))
+                .collect();
+            output.push(symbol);
+            // Literal symbol ends the word, same as any other punctuation
+            self.clear();
+            self.word_history.clear();
+            self.spaces_after_commit = 0;
+            return Some(Result::send_consumed(rebuilt.backspace, &output));
+        }
+
+        // Mark already there from an earlier symbol: punctuation ("việt.")
+        if mark_val.is_some_and(|v| self.buf.iter().any(|c| c.mark == v)) {
+            return None;
+        }
+
+        self.raw_input.push((key, caps, shift));
+        let result = if let Some(tone_type) = tone_type {
+            self.try_tone(key, caps, tone_type, m.tone_targets(key))
+        } else {
+            mark_val.and_then(|mark_val| self.try_mark(key, caps, mark_val))
+        };
+        if result.is_none() {
+            // Nothing to modify - symbol is plain punctuation
+            self.raw_input.pop();
+        }
+        result
+    }
+
     /// Auto-restore invalid Vietnamese to raw English on space
     ///
     /// Called when SPACE is pressed. If buffer has transforms but result is not
@@ -7599,27 +9026,7 @@ impl Engine {
             return Result::none();
         }
 
-        // Build raw ASCII output from raw_input history
-        // If telex_double_raw is set (revert happened), use it as base and append subsequent chars
-        // This ensures "aww" → ESC → "aww" (not "aw"), "a66" → ESC → "a66" (not "a6")
-        let raw_chars: Vec<char> = if let Some(ref base_raw) = self.telex_double_raw {
-            // Start with the original raw string before revert modification
-            let mut chars: Vec<char> = base_raw.chars().collect();
-            // Append any characters typed after the revert
-            for &(key, caps, shift) in self.raw_input.iter().skip(self.telex_double_raw_len) {
-                if let Some(ch) = utils::key_to_char_ext(key, caps, shift) {
-                    chars.push(ch);
-                }
-            }
-            chars
-        } else {
-            // Normal case: use raw_input directly
-            self.raw_input
-                .iter()
-                .filter_map(|&(key, caps, shift)| utils::key_to_char_ext(key, caps, shift))
-                .collect()
-        };
-
+        let raw_chars = self.typed_chars();
         if raw_chars.is_empty() {
             return Result::none();
         }
@@ -7641,6 +9048,29 @@ impl Engine {
         Result::send(backspace, &raw_chars)
     }
 
+    /// Keys of the current word exactly as typed
+    fn typed_chars(&self) -> Vec<char> {
+        // If telex_double_raw is set (revert happened), use it as base and append subsequent chars
+        // This ensures "aww" → ESC → "aww" (not "aw"), "a66" → ESC → "a66" (not "a6")
+        if let Some(ref base_raw) = self.telex_double_raw {
+            // Start with the original raw string before revert modification
+            let mut chars: Vec<char> = base_raw.chars().collect();
+            // Append any characters typed after the revert
+            for &(key, caps, shift) in self.raw_input.iter().skip(self.telex_double_raw_len) {
+                if let Some(ch) = self.raw_char(key, caps, shift) {
+                    chars.push(ch);
+                }
+            }
+            chars
+        } else {
+            // Normal case: use raw_input directly
+            self.raw_input
+                .iter()
+                .filter_map(|&(key, caps, shift)| self.raw_char(key, caps, shift))
+                .collect()
+        }
+    }
+
     /// Restore raw_input from buffer (for ESC restore to work after backspace-restore)
     fn restore_raw_input_from_buffer(&mut self, buf: &Buffer) {
         self.raw_input.clear();
@@ -8056,6 +9486,7 @@ mod tests {
     /// - "booo" → "boo" (revert), then "s" → "boos" (not "boós")
     /// - "seee" → "see" (revert), then "m" → "seem" (not "seém")
     /// - "booo" + "k" → "book" (consonant also literal)
+    ///
     /// Note: Only works with valid Vietnamese initials (b, c, d, h, l, m, n, p, s, t, etc.)
     #[test]
     fn test_literal_after_circumflex_revert() {
@@ -0,0 +1,465 @@
+//! Custom Input Method
+//!
+//! Data-driven key mappings loaded at runtime from a small TOML subset:
+//!
+//! ```toml
+//! # Telex with [ ] as horn keys and q as remove key
+//! name = "Telex brackets"
+//! base = "telex"
+//!
+//! [marks]
+//! sac = "s"
+//! huyen = "f"
+//! hoi = "r"
+//! nga = "x"
+//! nang = "j"
+//!
+//! [tones]
+//! a = "circumflex:a"
+//! e = "circumflex:e"
+//! o = "circumflex:o"
+//! w = "horn:aou"
+//! "[" = "horn:o"
+//! "]" = "horn:u"
+//!
+//! [keys]
+//! stroke = "d"
+//! remove = "q"
+//! ```
+//!
+//! - `base`: engine heuristics to follow, "telex" (letter modifiers, the
+//!   default) or "vni" (command keys, delayed stroke allowed)
+//! - `[marks]`: each value lists every key that applies the mark
+//! - `[tones]`: key → "circumflex|horn|breve:targets", targets are vowels
+//! - `[keys]`: `stroke` and `remove` list their keys
+//!
+//! Keys are written as the character they type. Letters match regardless
+//! of Shift; digits and punctuation match the Shift state of the character
+//! (`^` is Shift+6, `6` is plain 6).
+
+use super::{Method, ToneType};
+use crate::engine::char_to_punctuation_key;
+use crate::utils;
+use data::keys;
+
+/// All subsets of tone target vowels (a, e, o, u), indexed by bitmask
+/// Lets `tone_targets` hand out static slices for runtime-defined targets.
+const TARGET_SETS: [&[u16]; 16] = [
+    &[],
+    &[keys::A],
+    &[keys::E],
+    &[keys::A, keys::E],
+    &[keys::O],
+    &[keys::A, keys::O],
+    &[keys::E, keys::O],
+    &[keys::A, keys::E, keys::O],
+    &[keys::U],
+    &[keys::A, keys::U],
+    &[keys::E, keys::U],
+    &[keys::A, keys::E, keys::U],
+    &[keys::O, keys::U],
+    &[keys::A, keys::O, keys::U],
+    &[keys::E, keys::O, keys::U],
+    &[keys::A, keys::E, keys::O, keys::U],
+];
+
+/// Bit for a tone target vowel in `TARGET_SETS`
+fn target_bit(c: char) -> Option<usize> {
+    match c.to_ascii_lowercase() {
+        'a' => Some(1),
+        'e' => Some(2),
+        'o' => Some(4),
+        'u' => Some(8),
+        _ => None,
+    }
+}
+
+/// Engine heuristics a custom method follows
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Base {
+    /// Letter modifiers (aa, dd, w): adjacency rules, auto-restore patterns
+    #[default]
+    Telex,
+    /// Command keys (digits, symbols): delayed stroke allowed
+    Vni,
+}
+
+impl Base {
+    /// Built-in method id whose engine behavior this base follows
+    pub fn method_id(self) -> u8 {
+        match self {
+            Base::Telex => 0,
+            Base::Vni => 1,
+        }
+    }
+}
+
+/// Physical key as written in a definition
+/// `shift` is None for letters (case doesn't matter), Some for symbols/digits
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct KeySpec {
+    key: u16,
+    shift: Option<bool>,
+}
+
+impl KeySpec {
+    fn parse(c: char) -> Option<Self> {
+        if c.is_ascii_alphabetic() {
+            return Some(Self {
+                key: utils::char_to_key(c),
+                shift: None,
+            });
+        }
+        if c.is_ascii_digit() {
+            return Some(Self {
+                key: utils::char_to_key(c),
+                shift: Some(false),
+            });
+        }
+        let key = char_to_punctuation_key(c);
+        if key == 255 || c == ' ' || c == '\n' || c == '\r' {
+            return None;
+        }
+        // Shifted symbols: the character differs from the unshifted one
+        let shift = matches!(
+            c,
+            '!' | '@'
+                | '#'
+                | '$'
+                | '%'
+                | '^'
+                | '&'
+                | '*'
+                | '('
+                | ')'
+                | '_'
+                | '+'
+                | ':'
+                | '"'
+                | '<'
+                | '>'
+                | '?'
+                | '|'
+                | '{'
+                | '}'
+                | '~'
+        );
+        Some(Self {
+            key,
+            shift: Some(shift),
+        })
+    }
+
+    fn matches(&self, key: u16) -> bool {
+        self.key == key
+    }
+
+    fn matches_shift(&self, key: u16, shift: bool) -> bool {
+        self.key == key && self.shift.map_or(true, |s| s == shift)
+    }
+}
+
+/// Error from parsing a custom method definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// 1-based line number (0 for whole-definition errors)
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}: {}", self.line, self.message)
+        } else {
+            write!(f, "{}", self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Runtime-defined input method
+#[derive(Debug, Clone, Default)]
+pub struct CustomMethod {
+    name: String,
+    base: Base,
+    /// (key, mark value 1-5)
+    marks: Vec<(KeySpec, u8)>,
+    /// (key, tone type, index into TARGET_SETS)
+    tones: Vec<(KeySpec, ToneType, usize)>,
+    stroke: Vec<KeySpec>,
+    remove: Vec<KeySpec>,
+}
+
+impl CustomMethod {
+    /// Parse a definition (see module docs for the format)
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut method = Self::default();
+        let mut section = String::new();
+
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line_no = idx + 1;
+            let err = |message: String| ParseError {
+                line: line_no,
+                message,
+            };
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                if !matches!(section.as_str(), "marks" | "tones" | "keys") {
+                    return Err(err(format!("unknown section [{}]", section)));
+                }
+                continue;
+            }
+
+            let (name, value) = split_assignment(line)
+                .ok_or_else(|| err("expected `name = \"value\"`".to_string()))?;
+            let name = unquote(name.trim()).ok_or_else(|| err("unterminated quote".into()))?;
+            let value = unquote(value.trim()).ok_or_else(|| err("unterminated quote".into()))?;
+
+            match section.as_str() {
+                "" => match name.as_str() {
+                    "name" => method.name = value,
+                    "base" => {
+                        method.base = match value.to_ascii_lowercase().as_str() {
+                            "telex" => Base::Telex,
+                            "vni" => Base::Vni,
+                            other => return Err(err(format!("unknown base \"{}\"", other))),
+                        }
+                    }
+                    other => return Err(err(format!("unknown setting `{}`", other))),
+                },
+                "marks" => {
+                    let mark = match name.as_str() {
+                        "sac" => 1,
+                        "huyen" => 2,
+                        "hoi" => 3,
+                        "nga" => 4,
+                        "nang" => 5,
+                        other => return Err(err(format!("unknown mark `{}`", other))),
+                    };
+                    for c in value.chars() {
+                        let spec = KeySpec::parse(c)
+                            .ok_or_else(|| err(format!("unsupported key '{}'", c)))?;
+                        method.marks.push((spec, mark));
+                    }
+                }
+                "tones" => {
+                    let mut chars = name.chars();
+                    let spec = match (chars.next(), chars.next()) {
+                        (Some(c), None) => KeySpec::parse(c)
+                            .ok_or_else(|| err(format!("unsupported key '{}'", c)))?,
+                        _ => return Err(err(format!("tone key must be one character: {}", name))),
+                    };
+                    let (kind, targets) = value
+                        .split_once(':')
+                        .ok_or_else(|| err("expected \"<tone>:<targets>\"".to_string()))?;
+                    let tone = match kind.trim() {
+                        "circumflex" => ToneType::Circumflex,
+                        "horn" => ToneType::Horn,
+                        "breve" => ToneType::Breve,
+                        other => return Err(err(format!("unknown tone `{}`", other))),
+                    };
+                    let mut mask = 0;
+                    for c in targets.trim().chars() {
+                        mask |= target_bit(c)
+                            .ok_or_else(|| err(format!("'{}' cannot take a tone", c)))?;
+                    }
+                    if mask == 0 {
+                        return Err(err("tone needs at least one target vowel".to_string()));
+                    }
+                    method.tones.push((spec, tone, mask));
+                }
+                _ => {
+                    let list = match name.as_str() {
+                        "stroke" => &mut method.stroke,
+                        "remove" => &mut method.remove,
+                        other => return Err(err(format!("unknown key setting `{}`", other))),
+                    };
+                    for c in value.chars() {
+                        list.push(
+                            KeySpec::parse(c)
+                                .ok_or_else(|| err(format!("unsupported key '{}'", c)))?,
+                        );
+                    }
+                }
+            }
+        }
+
+        if method.name.is_empty() {
+            return Err(ParseError {
+                line: 0,
+                message: "missing `name`".to_string(),
+            });
+        }
+        Ok(method)
+    }
+
+    /// Display name from the definition
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Engine heuristics this method follows
+    pub fn base(&self) -> Base {
+        self.base
+    }
+}
+
+impl Method for CustomMethod {
+    fn mark(&self, key: u16) -> Option<u8> {
+        self.marks
+            .iter()
+            .find(|(k, _)| k.matches(key))
+            .map(|&(_, m)| m)
+    }
+
+    fn tone(&self, key: u16) -> Option<ToneType> {
+        self.tones
+            .iter()
+            .find(|(k, _, _)| k.matches(key))
+            .map(|&(_, t, _)| t)
+    }
+
+    fn tone_targets(&self, key: u16) -> &'static [u16] {
+        self.tones
+            .iter()
+            .find(|(k, _, _)| k.matches(key))
+            .map_or(&[], |&(_, _, mask)| TARGET_SETS[mask])
+    }
+
+    fn stroke(&self, key: u16) -> bool {
+        self.stroke.iter().any(|k| k.matches(key))
+    }
+
+    fn remove(&self, key: u16) -> bool {
+        self.remove.iter().any(|k| k.matches(key))
+    }
+
+    fn mark_with_shift(&self, key: u16, shift: bool) -> Option<u8> {
+        self.marks
+            .iter()
+            .find(|(k, _)| k.matches_shift(key, shift))
+            .map(|&(_, m)| m)
+    }
+
+    fn tone_with_shift(&self, key: u16, shift: bool) -> Option<ToneType> {
+        self.tones
+            .iter()
+            .find(|(k, _, _)| k.matches_shift(key, shift))
+            .map(|&(_, t, _)| t)
+    }
+}
+
+/// Strip a `#` comment that is not inside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut in_quote = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quote = !in_quote,
+            '#' if !in_quote => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Split `name = value` at the first `=` outside quotes (`"=" = "horn:o"`)
+fn split_assignment(line: &str) -> Option<(&str, &str)> {
+    let mut in_quote = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quote = !in_quote,
+            '=' if !in_quote => return Some((&line[..i], &line[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Remove surrounding double quotes; bare words are accepted as-is
+/// Returns None for an unterminated quote.
+fn unquote(s: &str) -> Option<String> {
+    match s.strip_prefix('"') {
+        Some(rest) => rest
+            .strip_suffix('"')
+            .map(|inner| inner.replace("\\\\", "\\")),
+        None => Some(s.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BRACKETS: &str = r#"
+        # Telex with [ ] as horn keys
+        name = "Telex brackets"
+
+        [marks]
+        sac = "s"
+        huyen = "f"
+        hoi = "r"
+        nga = "x"
+        nang = "j"
+
+        [tones]
+        a = "circumflex:a"
+        "[" = "horn:o"
+        "]" = "horn:u"
+        "^" = "circumflex:aeo"
+
+        [keys]
+        stroke = "d"
+        remove = "q"
+    "#;
+
+    #[test]
+    fn test_parse() {
+        let m = CustomMethod::parse(BRACKETS).unwrap();
+        assert_eq!(m.name(), "Telex brackets");
+        assert_eq!(m.base(), Base::Telex);
+        assert_eq!(m.mark(keys::S), Some(1));
+        assert_eq!(m.mark(keys::J), Some(5));
+        assert_eq!(m.tone(keys::LBRACKET), Some(ToneType::Horn));
+        assert_eq!(m.tone_targets(keys::RBRACKET), &[keys::U]);
+        assert_eq!(m.tone_targets(keys::N6), &[keys::A, keys::E, keys::O]);
+        assert!(m.stroke(keys::D));
+        assert!(m.remove(keys::Q));
+        assert!(!m.remove(keys::Z));
+    }
+
+    #[test]
+    fn test_shift_state() {
+        let m = CustomMethod::parse(BRACKETS).unwrap();
+        // ^ is Shift+6, plain 6 stays a digit
+        assert_eq!(
+            m.tone_with_shift(keys::N6, true),
+            Some(ToneType::Circumflex)
+        );
+        assert_eq!(m.tone_with_shift(keys::N6, false), None);
+        // Letters ignore Shift
+        assert_eq!(m.mark_with_shift(keys::S, true), Some(1));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = CustomMethod::parse("name = \"x\"\n[tones]\nw = \"horn:b\"").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(CustomMethod::parse("[marks]\nsac = \"s\"").is_err()); // missing name
+        assert!(CustomMethod::parse("name = \"x\"\nbase = \"qwerty\"").is_err());
+        assert!(CustomMethod::parse("name = \"x\"\n[colors]").is_err());
+    }
+
+    #[test]
+    fn test_equals_key() {
+        let m = CustomMethod::parse("name = \"x\"\n[tones]\n\"=\" = \"horn:ou\"").unwrap();
+        assert_eq!(m.tone_with_shift(keys::EQUAL, false), Some(ToneType::Horn));
+        assert_eq!(m.tone_targets(keys::EQUAL), &[keys::O, keys::U]);
+    }
+}
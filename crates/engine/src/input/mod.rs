@@ -3,14 +3,19 @@
 //! Defines key mappings for Vietnamese input methods.
 //! Engine handles all pattern matching based on buffer scan.
 
+pub mod custom;
 pub mod telex;
+pub mod viqr;
 pub mod vni;
 
+pub use custom::CustomMethod;
 pub use telex::Telex;
+pub use viqr::Viqr;
 pub use vni::Vni;
 
 use data::chars::tone;
 use data::keys;
+use std::sync::Arc;
 
 /// Shared tone target constants
 pub const CIRCUMFLEX_TARGETS: &[u16] = &[keys::A, keys::E, keys::O];
@@ -25,7 +30,7 @@ pub enum ToneType {
     Circumflex,
     /// Horn: ơ, ư (and ă for Telex)
     Horn,
-    /// Breve: ă (VNI only)
+    /// Breve: ă (VNI and VIQR)
     Breve,
 }
 
@@ -58,16 +63,54 @@ pub trait Method {
 
     /// Check if key removes diacritics
     fn remove(&self, key: u16) -> bool;
+
+    /// Check if key is a mark modifier, taking Shift into account
+    /// Letter/number layouts ignore Shift; VIQR needs it (` = huyền, ~ = ngã)
+    fn mark_with_shift(&self, key: u16, _shift: bool) -> Option<u8> {
+        self.mark(key)
+    }
+
+    /// Check if key is a tone modifier, taking Shift into account
+    /// Letter/number layouts ignore Shift; VIQR needs it (^ vs 6, ( vs 9)
+    fn tone_with_shift(&self, key: u16, _shift: bool) -> Option<ToneType> {
+        self.tone(key)
+    }
 }
 
 /// Static method instances (zero-sized types, no heap allocation)
 static TELEX: Telex = Telex;
 static VNI: Vni = Vni;
+static VIQR: Viqr = Viqr;
+
+/// First id handed out to custom methods registered on an engine
+/// Ids below this are reserved for built-in methods.
+pub const CUSTOM_METHOD_BASE: u8 = 16;
 
 /// Get method by id (returns static reference, no allocation)
+/// 0 = Telex, 1 = VNI, 2 = VIQR
 pub fn get(id: u8) -> &'static dyn Method {
     match id {
         1 => &VNI,
+        2 => &VIQR,
         _ => &TELEX,
     }
 }
+
+/// Active method: built-in static or runtime-defined custom method
+/// Owns its method so callers can keep it while mutating the engine.
+#[derive(Clone)]
+pub enum MethodRef {
+    Builtin(&'static dyn Method),
+    Custom(Arc<CustomMethod>),
+}
+
+impl std::ops::Deref for MethodRef {
+    type Target = dyn Method;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            MethodRef::Builtin(m) => *m,
+            MethodRef::Custom(m) => m.as_ref(),
+        }
+    }
+}
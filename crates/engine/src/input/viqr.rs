@@ -0,0 +1,111 @@
+//! VIQR Input Method
+//!
+//! Key mappings:
+//! - Marks: '=sắc, `=huyền, ?=hỏi, ~=ngã, .=nặng
+//! - Tones: ^=circumflex, +=horn, (=breve
+//! - Stroke: d (dd → đ)
+//! - Escape: \ (a\' → a')
+//!
+//! Modifiers are punctuation symbols, so the same physical key can mean
+//! different things with and without Shift (` is huyền, ~ is ngã).
+
+use super::{Method, ToneType, BREVE_TARGETS, CIRCUMFLEX_TARGETS, HORN_TARGETS_VNI};
+use data::keys;
+
+pub struct Viqr;
+
+impl Method for Viqr {
+    fn mark(&self, key: u16) -> Option<u8> {
+        match key {
+            keys::QUOTE => Some(1),     // ' sắc
+            keys::BACKQUOTE => Some(2), // ` huyền
+            keys::SLASH => Some(3),     // ? hỏi (Shift+/)
+            keys::DOT => Some(5),       // . nặng
+            _ => None,
+        }
+    }
+
+    fn tone(&self, key: u16) -> Option<ToneType> {
+        match key {
+            keys::N6 => Some(ToneType::Circumflex), // ^ (Shift+6)
+            keys::EQUAL => Some(ToneType::Horn),    // + (Shift+=)
+            keys::N9 => Some(ToneType::Breve),      // ( (Shift+9)
+            _ => None,
+        }
+    }
+
+    fn tone_targets(&self, key: u16) -> &'static [u16] {
+        match key {
+            keys::N6 => CIRCUMFLEX_TARGETS,
+            keys::EQUAL => HORN_TARGETS_VNI,
+            keys::N9 => BREVE_TARGETS,
+            _ => &[],
+        }
+    }
+
+    fn stroke(&self, key: u16) -> bool {
+        key == keys::D
+    }
+
+    fn remove(&self, _key: u16) -> bool {
+        false
+    }
+
+    fn mark_with_shift(&self, key: u16, shift: bool) -> Option<u8> {
+        match (key, shift) {
+            (keys::QUOTE, false) => Some(1),
+            (keys::BACKQUOTE, false) => Some(2),
+            (keys::SLASH, true) => Some(3),
+            (keys::BACKQUOTE, true) => Some(4), // ~ ngã
+            (keys::DOT, false) => Some(5),
+            _ => None,
+        }
+    }
+
+    fn tone_with_shift(&self, key: u16, shift: bool) -> Option<ToneType> {
+        if shift {
+            self.tone(key)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marks() {
+        let v = Viqr;
+        assert_eq!(v.mark_with_shift(keys::QUOTE, false), Some(1));
+        assert_eq!(v.mark_with_shift(keys::BACKQUOTE, false), Some(2));
+        assert_eq!(v.mark_with_shift(keys::SLASH, true), Some(3));
+        assert_eq!(v.mark_with_shift(keys::BACKQUOTE, true), Some(4));
+        assert_eq!(v.mark_with_shift(keys::DOT, false), Some(5));
+        // " and / are plain punctuation
+        assert_eq!(v.mark_with_shift(keys::QUOTE, true), None);
+        assert_eq!(v.mark_with_shift(keys::SLASH, false), None);
+    }
+
+    #[test]
+    fn test_tones() {
+        let v = Viqr;
+        assert_eq!(
+            v.tone_with_shift(keys::N6, true),
+            Some(ToneType::Circumflex)
+        );
+        assert_eq!(v.tone_with_shift(keys::EQUAL, true), Some(ToneType::Horn));
+        assert_eq!(v.tone_with_shift(keys::N9, true), Some(ToneType::Breve));
+        // Unshifted digits stay digits
+        assert_eq!(v.tone_with_shift(keys::N6, false), None);
+        assert_eq!(v.tone_with_shift(keys::N9, false), None);
+    }
+
+    #[test]
+    fn test_stroke() {
+        let v = Viqr;
+        assert!(v.stroke(keys::D));
+        assert!(!v.stroke(keys::N9));
+    }
+}
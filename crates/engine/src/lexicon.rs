@@ -0,0 +1,472 @@
+//! User Lexicon - per-user vocabulary for English auto-restore
+//!
+//! The built-in `english_dict` and `telex_doubles` lists can't know team
+//! jargon or product names. The lexicon holds words the user pinned as
+//! "always English" or "always Vietnamese", words learned from being
+//! committed repeatedly (opt-in, `Engine::set_lexicon_learning`), and
+//! exceptions learned when the user undid the restore decision for a word.
+//! Auto-restore asks it first.
+//!
+//! Memory is bounded: at most `MAX_ENTRIES` words of up to `MAX_WORD_LEN`
+//! characters. When full, the least used learned word is forgotten (plain
+//! learned words before exceptions); pinned words are only removed explicitly.
+//!
+//! File format, one word per line (`#` starts a comment):
+//!
+//! ```text
+//! english kubectl        # pinned
+//! vietnamese gõ          # pinned
+//! english 4 ngrok        # learned, committed 4 times
+//! vietnamese corrected tẽt  # exception: user undid the restore
+//! ```
+
+use crate::input::custom::ParseError;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+/// Maximum number of words kept
+pub const MAX_ENTRIES: usize = 4096;
+
+/// Longer words are not stored (in characters)
+pub const MAX_WORD_LEN: usize = 32;
+
+/// Commits before a learned word counts as a preference
+pub const LEARN_THRESHOLD: u32 = 3;
+
+/// Language a word should stay in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Language {
+    /// Keep (or restore to) the keys as typed
+    English = 0,
+    /// Keep the Vietnamese transform
+    Vietnamese = 1,
+}
+
+impl Language {
+    fn name(self) -> &'static str {
+        match self {
+            Language::English => "english",
+            Language::Vietnamese => "vietnamese",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "english" => Some(Language::English),
+            "vietnamese" => Some(Language::Vietnamese),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Entry {
+    language: Language,
+    /// Set by the user: never learned over or evicted
+    pinned: bool,
+    /// Learned from a manual undo: applies at once, not learned over
+    corrected: bool,
+    /// Times committed
+    count: u32,
+    /// Tick of the last use, for eviction
+    last_used: u64,
+}
+
+/// One word of the lexicon, for listing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LexiconWord {
+    pub word: String,
+    pub language: Language,
+    pub pinned: bool,
+    /// Exception learned from a manual undo
+    pub corrected: bool,
+    pub count: u32,
+}
+
+/// Per-user word list consulted by auto-restore
+#[derive(Clone, Debug, Default)]
+pub struct UserLexicon {
+    entries: HashMap<String, Entry>,
+    tick: u64,
+}
+
+/// Lexicon key: lowercase, None if the word can't be stored
+fn normalize(word: &str) -> Option<String> {
+    let word = word.trim();
+    let len = word.chars().count();
+    if len == 0 || len > MAX_WORD_LEN || word.chars().any(char::is_whitespace) {
+        return None;
+    }
+    Some(word.to_lowercase())
+}
+
+impl UserLexicon {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pin a word to a language ("always English" / "always Vietnamese")
+    ///
+    /// Returns false if the word is empty, too long, contains whitespace,
+    /// or the lexicon is full of pinned words.
+    pub fn pin(&mut self, word: &str, language: Language) -> bool {
+        let Some(key) = normalize(word) else {
+            return false;
+        };
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.language = language;
+            entry.pinned = true;
+            entry.last_used = tick;
+            return true;
+        }
+        self.insert(
+            key,
+            Entry {
+                language,
+                pinned: true,
+                corrected: false,
+                count: 0,
+                last_used: tick,
+            },
+        )
+    }
+
+    /// Record that the user undid the restore decision for a word
+    ///
+    /// `language` is what the user went back to. Unlike `learn`, one
+    /// correction is enough; later commits don't overturn it.
+    /// Returns false if the word can't be stored.
+    pub fn correct(&mut self, word: &str, language: Language) -> bool {
+        let Some(key) = normalize(word) else {
+            return false;
+        };
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(&key) {
+            if !entry.pinned {
+                entry.language = language;
+                entry.corrected = true;
+            }
+            entry.last_used = tick;
+            return true;
+        }
+        self.insert(
+            key,
+            Entry {
+                language,
+                pinned: false,
+                corrected: true,
+                count: 0,
+                last_used: tick,
+            },
+        )
+    }
+
+    /// Forget a word, pinned or learned
+    pub fn remove(&mut self, word: &str) -> bool {
+        normalize(word).is_some_and(|key| self.entries.remove(&key).is_some())
+    }
+
+    /// Record a committed word
+    ///
+    /// Words with Vietnamese letters count as Vietnamese, plain ASCII as
+    /// English. After `LEARN_THRESHOLD` commits in the same language the
+    /// word becomes a preference. Pinned words and exceptions keep their
+    /// language.
+    pub fn learn(&mut self, word: &str) {
+        let Some(key) = normalize(word) else {
+            return;
+        };
+        let language = if key.is_ascii() {
+            Language::English
+        } else {
+            Language::Vietnamese
+        };
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.last_used = tick;
+            if entry.pinned || entry.corrected || entry.language == language {
+                entry.count = entry.count.saturating_add(1);
+            } else {
+                entry.language = language;
+                entry.count = 1;
+            }
+            return;
+        }
+        self.insert(
+            key,
+            Entry {
+                language,
+                pinned: false,
+                corrected: false,
+                count: 1,
+                last_used: tick,
+            },
+        );
+    }
+
+    /// Preferred language of a word: pinned, corrected, or learned often enough
+    pub fn language(&self, word: &str) -> Option<Language> {
+        let entry = self.entries.get(&word.to_lowercase())?;
+        (entry.pinned || entry.corrected || entry.count >= LEARN_THRESHOLD)
+            .then_some(entry.language)
+    }
+
+    /// All words: pinned, then exceptions, then learned, each by word
+    pub fn words(&self) -> Vec<LexiconWord> {
+        let mut words: Vec<LexiconWord> = self
+            .entries
+            .iter()
+            .map(|(word, e)| LexiconWord {
+                word: word.clone(),
+                language: e.language,
+                pinned: e.pinned,
+                corrected: e.corrected,
+                count: e.count,
+            })
+            .collect();
+        words.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then(b.corrected.cmp(&a.corrected))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        words
+    }
+
+    /// Exceptions learned from manual undo, by word
+    pub fn exceptions(&self) -> Vec<LexiconWord> {
+        self.words().into_iter().filter(|w| w.corrected).collect()
+    }
+
+    /// Forget all exceptions learned from manual undo
+    ///
+    /// Pinned and plain learned words stay. Returns how many were removed.
+    pub fn clear_exceptions(&mut self) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| e.pinned || !e.corrected);
+        before - self.entries.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.tick = 0;
+    }
+
+    /// Insert a new word, evicting the least used learned word when full
+    fn insert(&mut self, key: String, entry: Entry) -> bool {
+        if self.entries.len() >= MAX_ENTRIES {
+            let victim = self
+                .entries
+                .iter()
+                .filter(|(_, e)| !e.pinned)
+                .min_by_key(|(_, e)| (e.corrected, e.count, e.last_used))
+                .map(|(k, _)| k.clone());
+            match victim {
+                Some(victim) => {
+                    self.entries.remove(&victim);
+                }
+                None => return false,
+            }
+        }
+        self.entries.insert(key, entry);
+        true
+    }
+
+    /// Serialize in the file format
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("# Gõ Nhanh user lexicon\n");
+        for w in self.words() {
+            if w.pinned {
+                out.push_str(&format!("{} {}\n", w.language.name(), w.word));
+            } else if w.corrected {
+                out.push_str(&format!("{} corrected {}\n", w.language.name(), w.word));
+            } else {
+                out.push_str(&format!("{} {} {}\n", w.language.name(), w.count, w.word));
+            }
+        }
+        out
+    }
+
+    /// Parse the file format
+    ///
+    /// Entries beyond `MAX_ENTRIES` evict learned words as usual.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut lexicon = Self::new();
+        for (idx, raw_line) in text.lines().enumerate() {
+            let err = |message: &str| ParseError {
+                line: idx + 1,
+                message: message.to_string(),
+            };
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let language = Language::from_name(fields[0]).ok_or_else(|| err("unknown language"))?;
+            match fields[1..] {
+                [word] => {
+                    if !lexicon.pin(word, language) {
+                        return Err(err("invalid word"));
+                    }
+                }
+                ["corrected", word] => {
+                    if !lexicon.correct(word, language) {
+                        return Err(err("invalid word"));
+                    }
+                }
+                [count, word] => {
+                    let count: u32 = count.parse().map_err(|_| err("invalid count"))?;
+                    let key = normalize(word).ok_or_else(|| err("invalid word"))?;
+                    lexicon.tick += 1;
+                    let entry = Entry {
+                        language,
+                        pinned: false,
+                        corrected: false,
+                        count,
+                        last_used: lexicon.tick,
+                    };
+                    lexicon.insert(key, entry);
+                }
+                _ => return Err(err("expected `<language> [count | corrected] <word>`")),
+            }
+        }
+        Ok(lexicon)
+    }
+
+    /// Read a lexicon file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Write the lexicon to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin() {
+        let mut lex = UserLexicon::new();
+        assert!(lex.pin("Kubectl", Language::English));
+        assert!(lex.pin("gõ", Language::Vietnamese));
+        assert_eq!(lex.language("kubectl"), Some(Language::English));
+        assert_eq!(lex.language("GÕ"), Some(Language::Vietnamese));
+        assert_eq!(lex.language("other"), None);
+        assert!(!lex.pin("", Language::English));
+        assert!(!lex.pin("two words", Language::English));
+        assert!(!lex.pin(&"a".repeat(MAX_WORD_LEN + 1), Language::English));
+        assert!(lex.remove("kubectl"));
+        assert_eq!(lex.language("kubectl"), None);
+    }
+
+    #[test]
+    fn test_learn() {
+        let mut lex = UserLexicon::new();
+        for _ in 1..LEARN_THRESHOLD {
+            lex.learn("ngrok");
+        }
+        assert_eq!(lex.language("ngrok"), None);
+        lex.learn("ngrok");
+        assert_eq!(lex.language("ngrok"), Some(Language::English));
+
+        // Committing the other form starts over
+        lex.learn("ngrók");
+        lex.learn("việt");
+        assert_eq!(lex.language("việt"), None);
+
+        // Pinned words keep their language
+        lex.pin("text", Language::English);
+        lex.learn("text");
+        assert_eq!(lex.language("text"), Some(Language::English));
+    }
+
+    #[test]
+    fn test_exceptions() {
+        let mut lex = UserLexicon::new();
+        lex.learn("ngrok");
+        assert!(lex.correct("tẽt", Language::Vietnamese));
+        assert!(lex.correct("ngrok", Language::Vietnamese));
+        // One correction is enough, and commits don't overturn it
+        assert_eq!(lex.language("tẽt"), Some(Language::Vietnamese));
+        for _ in 0..LEARN_THRESHOLD {
+            lex.learn("ngrok");
+        }
+        assert_eq!(lex.language("ngrok"), Some(Language::Vietnamese));
+
+        // Pinned words win over corrections
+        lex.pin("kubectl", Language::English);
+        lex.correct("kubectl", Language::Vietnamese);
+        assert_eq!(lex.language("kubectl"), Some(Language::English));
+
+        let words: Vec<String> = lex.exceptions().into_iter().map(|w| w.word).collect();
+        assert_eq!(words, ["ngrok", "tẽt"]);
+        assert_eq!(lex.clear_exceptions(), 2);
+        assert!(lex.exceptions().is_empty());
+        assert_eq!(lex.language("kubectl"), Some(Language::English));
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut lex = UserLexicon::new();
+        lex.pin("pinned", Language::English);
+        lex.learn("frequent");
+        lex.learn("frequent");
+        for i in 0..MAX_ENTRIES + 10 {
+            lex.learn(&format!("w{}", i));
+        }
+        assert_eq!(lex.len(), MAX_ENTRIES);
+        assert_eq!(lex.language("pinned"), Some(Language::English));
+        assert!(lex.words().iter().any(|w| w.word == "frequent"));
+        assert!(!lex.words().iter().any(|w| w.word == "w0"));
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut lex = UserLexicon::new();
+        lex.pin("kubectl", Language::English);
+        lex.pin("gõ", Language::Vietnamese);
+        for _ in 0..4 {
+            lex.learn("ngrok");
+        }
+        lex.correct("tẽt", Language::Vietnamese);
+        let text = lex.to_text();
+        assert_eq!(
+            text,
+            "# Gõ Nhanh user lexicon\nvietnamese gõ\nenglish kubectl\n\
+             vietnamese corrected tẽt\nenglish 4 ngrok\n"
+        );
+        let parsed = UserLexicon::parse(&text).unwrap();
+        assert_eq!(parsed.words(), lex.words());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = UserLexicon::parse("english ok\nklingon word").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(UserLexicon::parse("english x word").is_err());
+        assert!(UserLexicon::parse("english a b c").is_err());
+        assert!(UserLexicon::parse("english").is_err());
+        assert!(UserLexicon::parse("\n# only comments\n")
+            .unwrap()
+            .is_empty());
+    }
+}
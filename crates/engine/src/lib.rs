@@ -4,10 +4,19 @@
 //! text input processing, including syllable parsing and validation.
 
 pub mod buffer;
+pub mod compose;
+pub mod config;
+pub mod convert;
 pub mod engine;
 pub mod input;
+pub mod lexicon;
+pub mod migrate;
+pub mod recording;
+pub mod reverse;
 pub mod shortcut;
+pub mod surrounding;
 pub mod syllable;
+pub mod trace;
 pub mod transform;
 pub mod utils;
 pub mod validation;
@@ -15,10 +24,19 @@ pub mod validation;
 // Re-export main types for convenience
 pub use buffer::MAX as BUFFER_MAX;
 pub use buffer::{Buffer, Char, MAX};
-pub use engine::{Action, Engine, Result, FLAG_KEY_CONSUMED};
-pub use input::{get, Method, Telex, ToneType, Vni};
+pub use compose::{Composition, PreeditHint};
+pub use config::EngineConfig;
+pub use convert::{convert, convert_reader, ConvertOptions, Converter};
+pub use engine::{Action, Candidate, CandidateKind, Engine, Result, FLAG_KEY_CONSUMED};
+pub use input::{get, CustomMethod, Method, Telex, ToneType, Viqr, Vni};
+pub use lexicon::{Language, LexiconWord, UserLexicon, LEARN_THRESHOLD};
+pub use migrate::MigrationReport;
+pub use recording::{Divergence, KeyEvent, KeyOutput, RecordedKey, Recording};
+pub use reverse::{to_keystrokes, KeystrokeError};
 pub use shortcut::{CaseMode, InputMethod, Shortcut, ShortcutTable, TriggerCondition};
+pub use surrounding::TextEdit;
 pub use syllable::{parse, Syllable};
+pub use trace::{FlagChange, KeyTrace, Lookup};
 pub use transform::{ModifierType, TransformResult};
 pub use utils::{
     char_to_key, collect_vowels, has_final_consonant, has_gi_initial, has_qu_initial, key_to_char,
@@ -0,0 +1,551 @@
+//! Settings Migration - bring settings from earlier storage into the settings file
+//!
+//! A settings file (see `config`) records the app version that wrote it:
+//!
+//! ```text
+//! # Gõ Nhanh settings
+//! version = 0.1.0
+//! method = telex
+//! ```
+//!
+//! Loading runs every step in `MIGRATIONS` newer than that version and not
+//! newer than the running app, in order, on the raw `key = value` lines,
+//! then parses the result. Each step reports what it changed. The file
+//! format has not changed since it shipped, so there are no steps yet;
+//! a file without a version is read as the current format, untouched.
+//!
+//! A file from a newer release loads too: settings this version doesn't
+//! know are dropped and reported.
+//!
+//! Before the settings file, the macOS app kept its settings in
+//! UserDefaults (`gonhanh.*` keys). `import_user_defaults` reads them.
+
+use crate::config::{EngineConfig, SETTINGS};
+use crate::input::custom::ParseError;
+use crate::shortcut::Shortcut;
+use std::io;
+use std::path::Path;
+use updater::Version;
+
+/// One schema change
+pub struct Migration {
+    /// First release writing the new schema
+    pub version: &'static str,
+    pub description: &'static str,
+    apply: fn(&mut Settings) -> Vec<(&'static str, String)>,
+}
+
+/// Schema changes, oldest first
+pub static MIGRATIONS: &[Migration] = &[];
+
+/// Version of this build, used when the app doesn't pass its own
+pub fn crate_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("crate version parses")
+}
+
+/// One setting changed by a migration step or an import
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Step that made the change
+    pub version: Version,
+    pub key: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}: {}", self.version, self.key, self.message)
+    }
+}
+
+/// What loading a settings file changed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Version that wrote the file, None for unversioned files and imports
+    pub from: Option<Version>,
+    /// Version the settings were migrated to
+    pub to: Version,
+    pub changes: Vec<Change>,
+}
+
+impl MigrationReport {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+enum Line {
+    Setting(String, String),
+    Other(String),
+}
+
+/// Top-level `key = value` lines of a settings file, shortcuts kept as text
+struct Settings {
+    lines: Vec<Line>,
+    /// Everything from the first section on
+    sections: String,
+}
+
+impl Settings {
+    fn parse(text: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut rest = text.lines();
+        let mut sections = String::new();
+        for line in rest.by_ref() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                sections.push_str(line);
+                sections.push('\n');
+                break;
+            }
+            match trimmed.split_once('=') {
+                Some((key, value)) if !trimmed.starts_with('#') => {
+                    lines.push(Line::Setting(key.trim().into(), value.trim().into()))
+                }
+                _ => lines.push(Line::Other(line.into())),
+            }
+        }
+        for line in rest {
+            sections.push_str(line);
+            sections.push('\n');
+        }
+        Self { lines, sections }
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            Line::Setting(k, v) if k == key => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Replace a value in place, or add it after the other settings
+    fn set(&mut self, key: &str, value: &str) {
+        for line in &mut self.lines {
+            if let Line::Setting(k, v) = line {
+                if k == key {
+                    *v = value.to_string();
+                    return;
+                }
+            }
+        }
+        let at = self
+            .lines
+            .iter()
+            .rposition(|line| matches!(line, Line::Setting(..)))
+            .map_or(self.lines.len(), |i| i + 1);
+        self.lines
+            .insert(at, Line::Setting(key.to_string(), value.to_string()));
+    }
+
+    fn remove(&mut self, key: &str) -> Option<String> {
+        let i = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, Line::Setting(k, _) if k == key))?;
+        match self.lines.remove(i) {
+            Line::Setting(_, value) => Some(value),
+            Line::Other(_) => None,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Setting(key, value) => {
+                    out.push_str(format!("{} = {}", key, value).trim_end());
+                    out.push('\n');
+                }
+                Line::Other(text) => {
+                    out.push_str(text);
+                    out.push('\n');
+                }
+            }
+        }
+        out.push_str(&self.sections);
+        out
+    }
+}
+
+/// Migrate settings text written by any version to `app_version`
+///
+/// Returns the migrated text, stamped with `app_version`, and the report.
+pub fn migrate(text: &str, app_version: &Version) -> Result<(String, MigrationReport), ParseError> {
+    let mut settings = Settings::parse(text);
+    let from = match settings.get("version") {
+        Some(v) => Some(Version::parse(v).ok_or_else(|| ParseError {
+            line: 0,
+            message: format!("invalid version {:?}", v),
+        })?),
+        None => None,
+    };
+
+    let mut changes = Vec::new();
+    // Unversioned files are the current format: nothing to run
+    if let Some(from) = &from {
+        for step in MIGRATIONS {
+            let version = Version::parse(step.version).expect("migration versions parse");
+            if from.has_update(&version) && version.compare(app_version) <= 0 {
+                for (key, message) in (step.apply)(&mut settings) {
+                    changes.push(Change {
+                        version: version.clone(),
+                        key: key.to_string(),
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    // Written by a newer release: keep what this version understands
+    if from
+        .as_ref()
+        .is_some_and(|from| app_version.has_update(from))
+    {
+        let unknown: Vec<String> = settings
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Setting(key, _) if !SETTINGS.contains(&key.as_str()) => Some(key.clone()),
+                _ => None,
+            })
+            .collect();
+        for key in unknown {
+            let value = settings.remove(&key).unwrap_or_default();
+            changes.push(Change {
+                version: app_version.clone(),
+                message: format!("unknown setting dropped ({} = {})", key, value),
+                key,
+            });
+        }
+    }
+
+    settings.set("version", &app_version.to_string());
+    let report = MigrationReport {
+        from,
+        to: app_version.clone(),
+        changes,
+    };
+    Ok((settings.to_text(), report))
+}
+
+/// Serialize `config` stamped with the version writing it
+pub fn to_text(config: &EngineConfig, app_version: &Version) -> String {
+    let mut settings = Settings::parse(&config.to_text());
+    settings.set("version", &app_version.to_string());
+    // Version first, right after the header comment
+    if let Some(i) = settings
+        .lines
+        .iter()
+        .position(|line| matches!(line, Line::Setting(k, _) if k == "version"))
+    {
+        let line = settings.lines.remove(i);
+        let at = settings
+            .lines
+            .iter()
+            .position(|line| matches!(line, Line::Setting(..)))
+            .unwrap_or(settings.lines.len());
+        settings.lines.insert(at, line);
+    }
+    settings.to_text()
+}
+
+/// Migrate and parse settings text
+pub fn parse(
+    text: &str,
+    app_version: &Version,
+) -> Result<(EngineConfig, MigrationReport), ParseError> {
+    let (migrated, report) = migrate(text, app_version)?;
+    Ok((EngineConfig::parse(&migrated)?, report))
+}
+
+/// Read a settings file written by any version
+pub fn load(
+    path: impl AsRef<Path>,
+    app_version: &Version,
+) -> io::Result<(EngineConfig, MigrationReport)> {
+    let text = std::fs::read_to_string(path)?;
+    parse(&text, app_version).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write a settings file stamped with `app_version`
+pub fn save(
+    config: &EngineConfig,
+    path: impl AsRef<Path>,
+    app_version: &Version,
+) -> io::Result<()> {
+    std::fs::write(path, to_text(config, app_version))
+}
+
+// macOS UserDefaults
+
+/// Shortcuts the macOS app shows when `gonhanh.shortcuts` was never saved
+const DEFAULT_SHORTCUTS: &[(&str, &str)] = &[
+    ("vn", "Việt Nam"),
+    ("hn", "Hà Nội"),
+    ("hcm", "Hồ Chí Minh"),
+    ("tphcm", "Thành phố Hồ Chí Minh"),
+];
+
+/// Convert the macOS app's UserDefaults to settings
+///
+/// `text` has one `gonhanh.<key> = <value>` line per stored key: booleans
+/// as `1`/`0` (or `true`/`false`, `YES`/`NO`), `gonhanh.method` as its
+/// number, `gonhanh.shortcuts` as the JSON the app encoded. Missing keys
+/// take the app's registered defaults; app-only keys (sounds, per-app
+/// mode, hotkeys) and other lines are ignored. The report lists every
+/// imported key.
+pub fn import_user_defaults(
+    text: &str,
+    app_version: &Version,
+) -> Result<(EngineConfig, MigrationReport), ParseError> {
+    let mut config = EngineConfig::default();
+    let mut changes = Vec::new();
+    let mut shortcuts = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let Some(name) = key.strip_prefix("gonhanh.") else {
+            continue;
+        };
+        let error = |message: String| ParseError {
+            line: i + 1,
+            message,
+        };
+        let flag =
+            || parse_bool(value).ok_or_else(|| error(format!("{}: expected a boolean", key)));
+        let setting = match name {
+            "enabled" => {
+                config.enabled = flag()?;
+                "enabled"
+            }
+            "method" => {
+                config.method = match value {
+                    "0" => 0,
+                    "1" => 1,
+                    _ => return Err(error(format!("{}: unknown input mode {}", key, value))),
+                };
+                "method"
+            }
+            "autoWShortcut" => {
+                config.skip_w_shortcut = !flag()?;
+                "skip_w_shortcut"
+            }
+            "bracketShortcut" => {
+                config.bracket_shortcut = flag()?;
+                "bracket_shortcut"
+            }
+            "escRestore" => {
+                config.esc_restore = flag()?;
+                "esc_restore"
+            }
+            "modernTone" => {
+                config.modern_tone = flag()?;
+                "modern_tone"
+            }
+            "englishAutoRestore" => {
+                config.english_auto_restore = flag()?;
+                "english_auto_restore"
+            }
+            "autoCapitalize" => {
+                config.auto_capitalize = flag()?;
+                "auto_capitalize"
+            }
+            "allowForeignConsonants" => {
+                config.allow_foreign_consonants = flag()?;
+                "allow_foreign_consonants"
+            }
+            "shortcuts" => {
+                let items =
+                    parse_shortcut_items(value).map_err(|e| error(format!("{}: {}", key, e)))?;
+                shortcuts = Some(items);
+                "shortcuts"
+            }
+            _ => continue,
+        };
+        changes.push(Change {
+            version: app_version.clone(),
+            key: setting.to_string(),
+            message: format!("imported from {} = {}", key, value),
+        });
+    }
+
+    config.shortcuts = match shortcuts {
+        Some(items) => items
+            .into_iter()
+            // The app never sends half-filled rows to the engine
+            .filter(|(trigger, replacement, _)| !trigger.is_empty() && !replacement.is_empty())
+            .map(|(trigger, replacement, enabled)| Shortcut {
+                enabled,
+                ..app_shortcut(&trigger, &replacement)
+            })
+            .collect(),
+        None => DEFAULT_SHORTCUTS
+            .iter()
+            .map(|(trigger, replacement)| Shortcut {
+                enabled: false,
+                ..Shortcut::new(trigger, replacement)
+            })
+            .collect(),
+    };
+
+    let report = MigrationReport {
+        from: None,
+        to: app_version.clone(),
+        changes,
+    };
+    Ok((config, report))
+}
+
+/// Shortcut as the app adds it: symbol-only triggers ("->") fire immediately
+fn app_shortcut(trigger: &str, replacement: &str) -> Shortcut {
+    if trigger.chars().all(|c| !c.is_alphabetic()) {
+        Shortcut::immediate(trigger, replacement)
+    } else {
+        Shortcut::new(trigger, replacement)
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "1" | "true" | "YES" => Some(true),
+        "0" | "false" | "NO" => Some(false),
+        _ => None,
+    }
+}
+
+/// `[ShortcutItem]` as JSON: `[{"id": …, "key": …, "value": …, "isEnabled": …}]`
+fn parse_shortcut_items(json: &str) -> Result<Vec<(String, String, bool)>, String> {
+    let mut p = Json {
+        chars: json.chars().peekable(),
+    };
+    let mut items = Vec::new();
+    p.expect('[')?;
+    if !p.eat(']') {
+        loop {
+            let (mut key, mut value, mut enabled) = (None, None, true);
+            p.expect('{')?;
+            if !p.eat('}') {
+                loop {
+                    let field = p.string()?;
+                    p.expect(':')?;
+                    match field.as_str() {
+                        "key" => key = Some(p.string()?),
+                        "value" => value = Some(p.string()?),
+                        "isEnabled" => enabled = p.boolean()?,
+                        _ => p.skip_scalar()?,
+                    }
+                    if p.eat('}') {
+                        break;
+                    }
+                    p.expect(',')?;
+                }
+            }
+            match (key, value) {
+                (Some(key), Some(value)) => items.push((key, value, enabled)),
+                _ => return Err("shortcut without key or value".into()),
+            }
+            if p.eat(']') {
+                break;
+            }
+            p.expect(',')?;
+        }
+    }
+    Ok(items)
+}
+
+/// Just enough JSON for `parse_shortcut_items`
+struct Json<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Json<'_> {
+    fn skip_space(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_space();
+        self.chars.next_if_eq(&c).is_some()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", c))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next().ok_or("unterminated string")? {
+                '"' => return Ok(out),
+                '\\' => match self.chars.next().ok_or("unterminated string")? {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'u' => {
+                        let mut code = self.hex4()?;
+                        if (0xD800..0xDC00).contains(&code) {
+                            // Surrogate pair
+                            if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                                return Err("unpaired surrogate".into());
+                            }
+                            let low = self.hex4()?;
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00));
+                        }
+                        out.push(char::from_u32(code).ok_or("invalid \\u escape")?);
+                    }
+                    c => out.push(c),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.by_ref().take(4).collect();
+        u32::from_str_radix(&digits, 16).map_err(|_| "invalid \\u escape".to_string())
+    }
+
+    fn boolean(&mut self) -> Result<bool, String> {
+        match self.word().as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err("expected true or false".into()),
+        }
+    }
+
+    /// Skip a field this importer doesn't use (`id`, or one added later)
+    fn skip_scalar(&mut self) -> Result<(), String> {
+        self.skip_space();
+        if self.chars.peek() == Some(&'"') {
+            self.string().map(|_| ())
+        } else if self.word().is_empty() {
+            Err("unsupported value".into())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn word(&mut self) -> String {
+        self.skip_space();
+        let mut out = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+        {
+            out.push(c);
+        }
+        out
+    }
+}
@@ -0,0 +1,351 @@
+//! Keystroke Recording - a typing session that can be replayed
+//!
+//! Frontends record the key events they pass to the engine together with
+//! the results they got back, and save them as text a user can attach to a
+//! bug report. The file is the settings file (see `config`) followed by a
+//! `[keys]` section with one key per line:
+//!
+//! ```text
+//! # Gõ Nhanh recording
+//! enabled = true
+//! method = telex
+//! ...
+//!
+//! [keys]
+//! 9 caps "V" => none 0 0 ""
+//! 34 - "i" => none 0 0 ""
+//! 14 - "e" => none 0 0 ""
+//! 14 - "e" => send 1 1 "ê"
+//! 38 - "j" => send 1 1 "ệ"
+//! ```
+//!
+//! A key line is `keycode modifiers char => action backspace flags text`.
+//! Modifiers are `caps`, `ctrl` and `shift` joined with `+`, `-` for none;
+//! char is the character from the platform (`on_key_with_char`) or `-`.
+//! Strings are quoted, with `\"`, `\\`, `\n`, `\r`, `\t` and `\u{..}`
+//! escapes. Text is the result chars as they were sent, so legacy charsets
+//! show their bytes.
+//!
+//! `Recording::replay` types the keys into a fresh engine with the recorded
+//! settings and reports the first key whose result differs. Recordings
+//! start from an empty engine; custom input methods and the user lexicon
+//! are not part of them.
+
+use crate::config::EngineConfig;
+use crate::engine::{Action, Engine, Result};
+use crate::input::custom::ParseError;
+use std::fmt::{self, Write};
+use std::io;
+use std::path::Path;
+
+/// Section header that ends the settings
+const KEYS_SECTION: &str = "[keys]";
+
+/// A key event as passed to `Engine::on_key_with_char`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: u16,
+    pub caps: bool,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub ch: Option<char>,
+}
+
+/// What the engine returned for a key, see `Result`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyOutput {
+    pub action: u8,
+    pub backspace: u8,
+    pub flags: u8,
+    /// Result chars, in the output charset
+    pub text: String,
+}
+
+impl From<&Result> for KeyOutput {
+    fn from(r: &Result) -> Self {
+        Self {
+            action: r.action,
+            backspace: r.backspace,
+            flags: r.flags,
+            text: r.chars[..r.count as usize]
+                .iter()
+                .filter_map(|&c| char::from_u32(c))
+                .collect(),
+        }
+    }
+}
+
+/// One recorded key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedKey {
+    pub event: KeyEvent,
+    pub output: KeyOutput,
+}
+
+/// Settings and keys of a typing session
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    pub config: EngineConfig,
+    pub keys: Vec<RecordedKey>,
+}
+
+/// First key whose replayed result differs from the recording
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index in `Recording::keys`
+    pub index: usize,
+    pub event: KeyEvent,
+    pub expected: KeyOutput,
+    pub actual: KeyOutput,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key {} ({}): expected {}, got {}",
+            self.index,
+            event_text(&self.event),
+            output_text(&self.expected),
+            output_text(&self.actual)
+        )
+    }
+}
+
+const ACTIONS: &[(u8, &str)] = &[
+    (Action::None as u8, "none"),
+    (Action::Send as u8, "send"),
+    (Action::Restore as u8, "restore"),
+];
+
+/// Quoted string with escapes
+fn quote(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{{{:x}}}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Split off a quoted string at the start of `s`: (content, rest)
+fn unquote(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut out = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &s[i + 2..])),
+            '\\' => match chars.next()?.1 {
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'u' => {
+                    let rest = chars.as_str().strip_prefix('{')?;
+                    let (hex, _) = rest.split_once('}')?;
+                    out.push(char::from_u32(u32::from_str_radix(hex, 16).ok()?)?);
+                    // Skip `{hex}`
+                    for _ in 0..hex.len() + 2 {
+                        chars.next();
+                    }
+                }
+                _ => return None,
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+/// `keycode modifiers char`
+fn event_text(e: &KeyEvent) -> String {
+    let mods: Vec<&str> = [(e.caps, "caps"), (e.ctrl, "ctrl"), (e.shift, "shift")]
+        .iter()
+        .filter(|&&(on, _)| on)
+        .map(|&(_, name)| name)
+        .collect();
+    let mut out = format!("{} ", e.key);
+    out.push_str(if mods.is_empty() { "-" } else { "" });
+    out.push_str(&mods.join("+"));
+    out.push(' ');
+    match e.ch {
+        Some(c) => quote(&mut out, c.encode_utf8(&mut [0; 4])),
+        None => out.push('-'),
+    }
+    out
+}
+
+/// `action backspace flags text`
+fn output_text(o: &KeyOutput) -> String {
+    let action = ACTIONS
+        .iter()
+        .find(|&&(id, _)| id == o.action)
+        .map_or_else(|| o.action.to_string(), |&(_, name)| name.to_string());
+    let mut out = format!("{} {} {} ", action, o.backspace, o.flags);
+    quote(&mut out, &o.text);
+    out
+}
+
+/// Parse a key line, None if malformed
+fn parse_key(line: &str) -> Option<RecordedKey> {
+    let (event, output) = line.split_once(" => ")?;
+
+    let mut fields = event.splitn(3, ' ');
+    let key = fields.next()?.parse().ok()?;
+    let mut event = KeyEvent {
+        key,
+        ..KeyEvent::default()
+    };
+    let mods = fields.next()?;
+    if mods != "-" {
+        for m in mods.split('+') {
+            match m {
+                "caps" => event.caps = true,
+                "ctrl" => event.ctrl = true,
+                "shift" => event.shift = true,
+                _ => return None,
+            }
+        }
+    }
+    let ch = fields.next()?.trim_end();
+    if ch != "-" {
+        let (s, rest) = unquote(ch)?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), rest.is_empty()) {
+            (Some(c), None, true) => event.ch = Some(c),
+            _ => return None,
+        }
+    }
+
+    let mut fields = output.trim_start().splitn(4, ' ');
+    let action = fields.next()?;
+    let action = ACTIONS
+        .iter()
+        .find(|&&(_, name)| name == action)
+        .map(|&(id, _)| id)
+        .or_else(|| action.parse().ok())?;
+    let backspace = fields.next()?.parse().ok()?;
+    let flags = fields.next()?.parse().ok()?;
+    let (text, rest) = unquote(fields.next()?)?;
+    if !rest.trim().is_empty() {
+        return None;
+    }
+    Some(RecordedKey {
+        event,
+        output: KeyOutput {
+            action,
+            backspace,
+            flags,
+            text,
+        },
+    })
+}
+
+impl Recording {
+    /// Empty recording with the settings in effect
+    pub fn new(config: EngineConfig) -> Self {
+        Self {
+            config,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Add a key and the result the engine returned for it
+    pub fn push(&mut self, event: KeyEvent, result: &Result) {
+        self.keys.push(RecordedKey {
+            event,
+            output: result.into(),
+        });
+    }
+
+    /// Serialize in the recording format
+    pub fn to_text(&self) -> String {
+        let settings = self.config.to_text();
+        let settings = settings
+            .strip_prefix("# Gõ Nhanh settings\n")
+            .unwrap_or(&settings);
+        let mut out = String::from("# Gõ Nhanh recording\n");
+        out.push_str(settings);
+        out.push('\n');
+        out.push_str(KEYS_SECTION);
+        out.push('\n');
+        for k in &self.keys {
+            out.push_str(&event_text(&k.event));
+            out.push_str(" => ");
+            out.push_str(&output_text(&k.output));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Parse the recording format
+    pub fn parse(text: &str) -> std::result::Result<Self, ParseError> {
+        let lines: Vec<&str> = text.lines().collect();
+        let keys_at = lines
+            .iter()
+            .position(|l| l.trim() == KEYS_SECTION)
+            .ok_or_else(|| ParseError {
+                line: 0,
+                message: format!("missing `{}` section", KEYS_SECTION),
+            })?;
+        let config = EngineConfig::parse(&lines[..keys_at].join("\n"))?;
+
+        let mut keys = Vec::new();
+        for (idx, line) in lines.iter().enumerate().skip(keys_at + 1) {
+            let line = line.trim_end_matches('\r');
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            keys.push(parse_key(trimmed).ok_or_else(|| ParseError {
+                line: idx + 1,
+                message: "expected `key modifiers char => action backspace flags text`".to_string(),
+            })?);
+        }
+        Ok(Self { config, keys })
+    }
+
+    /// Read a recording file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Write the recording to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    /// Type the keys into a fresh engine with the recorded settings
+    ///
+    /// Returns the first key whose result differs, None if all match.
+    /// Errors only if the settings are invalid.
+    pub fn replay(&self) -> std::result::Result<Option<Divergence>, ParseError> {
+        let mut engine = Engine::new();
+        engine.apply_config(&self.config)?;
+        for (index, k) in self.keys.iter().enumerate() {
+            let e = k.event;
+            let result = engine.on_key_with_char(e.key, e.caps, e.ctrl, e.shift, e.ch);
+            let actual = KeyOutput::from(&result);
+            if actual != k.output {
+                return Ok(Some(Divergence {
+                    index,
+                    event: e,
+                    expected: k.output.clone(),
+                    actual,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
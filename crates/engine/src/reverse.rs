@@ -0,0 +1,245 @@
+//! Reverse Conversion
+//!
+//! Generates the keystrokes that type a Vietnamese text with an input method:
+//! "Nguyễn Văn Tuấn" → "Nguyeenx Vawn Tuaans" (Telex), "Nguye6n4 Va8n Tua6n1" (VNI).
+//!
+//! Keys come from the method's own mappings (`Method::mark`, `tone`, `stroke`).
+//! Every word is typed back through `Engine` before it is accepted, so a
+//! returned sequence always converts to exactly the input text with
+//! `convert` and the same options. Tone position follows
+//! `ConvertOptions::modern_tone`: "hoà" needs modern, "hòa" traditional.
+
+use crate::convert::{convert, key_for_char, ConvertOptions};
+use crate::input::{self, Method};
+use crate::utils::key_to_char_ext;
+use data::chars::{self, ParsedChar};
+
+/// Keys tried when looking up a modifier, unshifted first
+const KEY_CHARS: &str = "abcdefghijklmnopqrstuvwxyz0123456789`-=[]\\;',./~!@#$%^&*()_+{}|:\"<>?";
+
+/// Text that cannot be typed with the requested method and options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeystrokeError {
+    /// Byte offset of the word in the input text
+    pub offset: usize,
+    pub word: String,
+}
+
+impl std::fmt::Display for KeystrokeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot type '{}' at byte {}", self.word, self.offset)
+    }
+}
+
+impl std::error::Error for KeystrokeError {}
+
+/// Keystrokes that type `text` with `options.method`
+///
+/// Guarantee: `convert(&to_keystrokes(text, options)?, options) == text`.
+/// Words whose tone position doesn't match `options.modern_tone`, and text
+/// the engine would rewrite (auto-capitalize, auto-restore), are errors.
+pub fn to_keystrokes(text: &str, options: &ConvertOptions) -> Result<String, KeystrokeError> {
+    let method = input::get(options.method);
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut start = None;
+
+    for (i, c) in text.char_indices() {
+        if chars::parse_char(c).is_some() {
+            start.get_or_insert(i);
+        } else if let Some(s) = start.take() {
+            out.push_str(&word_keystrokes(&text[s..i], Some(c), method, options, s)?);
+        } else {
+            out.push(c);
+        }
+    }
+    if let Some(s) = start {
+        out.push_str(&word_keystrokes(&text[s..], None, method, options, s)?);
+    }
+
+    // Words are checked one by one; check how they combine too
+    let typed = convert(&out, options);
+    if typed != text {
+        let offset = text
+            .char_indices()
+            .zip(typed.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(typed.len().min(text.len()), |((i, _), _)| i);
+        return Err(error_at(text, offset));
+    }
+    Ok(out)
+}
+
+/// Error for the word (or single character) at `offset`
+fn error_at(text: &str, offset: usize) -> KeystrokeError {
+    let is_letter = |c: char| chars::parse_char(c).is_some();
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_letter(c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = text[offset..]
+        .char_indices()
+        .find(|&(i, c)| !is_letter(c) && (i > 0 || start < offset))
+        .map_or(text.len(), |(i, c)| {
+            offset + if i == 0 { c.len_utf8() } else { i }
+        });
+    KeystrokeError {
+        offset: start,
+        word: text[start..end.max(start)].to_string(),
+    }
+}
+
+/// One letter of a word split into the keys that produce it
+struct Letter {
+    base: char,
+    /// Stroke and tone keys, typed after the base letter
+    modifiers: Vec<char>,
+    mark: Option<char>,
+}
+
+/// Where modifier and mark keys go relative to their letter
+#[derive(Clone, Copy)]
+enum Order {
+    /// vieetj: modifiers after each letter, marks at the end
+    MarkLast,
+    /// vieejt: mark right after its vowel
+    MarkInline,
+    /// vietej: plain letters first, all modifiers and marks at the end
+    AllLast,
+}
+
+/// First verified key sequence for one word and the character after it
+///
+/// Punctuation the method reads as a modifier (VIQR ? after a vowel) is
+/// escaped with a backslash.
+fn word_keystrokes(
+    word: &str,
+    follow: Option<char>,
+    method: &dyn Method,
+    options: &ConvertOptions,
+    offset: usize,
+) -> Result<String, KeystrokeError> {
+    let error = || KeystrokeError {
+        offset,
+        word: word.to_string(),
+    };
+    let letters = word
+        .chars()
+        .map(|c| split_letter(chars::parse_char(c)?, method))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(error)?;
+
+    let follows = match follow {
+        Some(c) if !c.is_whitespace() && key_for_char(c).is_some() => {
+            vec![c.to_string(), format!("\\{}", c)]
+        }
+        Some(c) => vec![c.to_string()],
+        None => vec![String::new()],
+    };
+    // End on a word boundary so auto-restore gets its say
+    let end = if follow.is_some_and(char::is_whitespace) {
+        ""
+    } else {
+        " "
+    };
+    let mut expected = word.to_string();
+    expected.extend(follow);
+    expected.push_str(end);
+
+    [Order::MarkLast, Order::MarkInline, Order::AllLast]
+        .into_iter()
+        .map(|order| arrange(&letters, order))
+        .chain(escaped(word, &letters, options))
+        .flat_map(|keys| follows.iter().map(move |f| format!("{}{}", keys, f)))
+        .find(|typed| convert(&format!("{}{}", typed, end), options) == expected)
+        .ok_or_else(error)
+}
+
+/// Letter by letter with marks inline, typing a letter twice when the
+/// engine would take it as a modifier: xooong → xoong, blowwing → blowing
+fn escaped(word: &str, letters: &[Letter], options: &ConvertOptions) -> Option<String> {
+    let mut keys = String::new();
+    for (i, (letter, c)) in letters.iter().zip(word.chars()).enumerate() {
+        let prefix: String = word.chars().take(i).chain(std::iter::once(c)).collect();
+        let extended = [1, 2].into_iter().find_map(|times| {
+            let mut next = keys.clone();
+            next.extend(std::iter::repeat(letter.base).take(times));
+            next.extend(&letter.modifiers);
+            next.extend(letter.mark);
+            (convert(&next, options) == prefix).then_some(next)
+        })?;
+        keys = extended;
+    }
+    Some(keys)
+}
+
+fn split_letter(parsed: ParsedChar, method: &dyn Method) -> Option<Letter> {
+    let caps = parsed.caps;
+    let base = key_to_char_ext(parsed.key, caps, false)?;
+    let mut modifiers = Vec::new();
+    if parsed.stroke {
+        modifiers.push(find_key(caps, |k, _| method.stroke(k))?);
+    }
+    if parsed.tone != 0 {
+        modifiers.push(find_key(caps, |k, shift| {
+            method
+                .tone_with_shift(k, shift)
+                .is_some_and(|t| t.value() == parsed.tone)
+                && method.tone_targets(k).contains(&parsed.key)
+        })?);
+    }
+    let mark = match parsed.mark {
+        0 => None,
+        m => Some(find_key(caps, |k, shift| {
+            method.mark_with_shift(k, shift) == Some(m)
+        })?),
+    };
+    Some(Letter {
+        base,
+        modifiers,
+        mark,
+    })
+}
+
+/// Character typing the first key that matches, in the letter's case
+fn find_key(caps: bool, matches: impl Fn(u16, bool) -> bool) -> Option<char> {
+    KEY_CHARS.chars().find_map(|c| {
+        let (key, shift) = key_for_char(c)?;
+        if !matches(key, shift) {
+            return None;
+        }
+        Some(if caps { c.to_ascii_uppercase() } else { c })
+    })
+}
+
+/// Lay out the keys of a word
+fn arrange(letters: &[Letter], order: Order) -> String {
+    let mut out = String::new();
+    let mut tail = String::new();
+
+    for letter in letters {
+        out.push(letter.base);
+        match order {
+            Order::MarkLast => {
+                out.extend(&letter.modifiers);
+                tail.extend(letter.mark);
+            }
+            Order::MarkInline => {
+                out.extend(&letter.modifiers);
+                out.extend(letter.mark);
+            }
+            Order::AllLast => {
+                for &m in &letter.modifiers {
+                    // One horn key covers ươ
+                    if !tail.ends_with(m) {
+                        tail.push(m);
+                    }
+                }
+                tail.extend(letter.mark);
+            }
+        }
+    }
+    out.push_str(&tail);
+    out
+}
@@ -1,7 +1,7 @@
 //! Shortcut Table - Abbreviation expansion
 //!
 //! Allows users to define shortcuts like "vn" → "Việt Nam"
-//! Shortcuts can be specific to input methods (Telex/VNI) or apply to all.
+//! Shortcuts can be specific to input methods (Telex/VNI/VIQR) or apply to all.
 
 use super::buffer::MAX;
 use std::collections::HashMap;
@@ -21,6 +21,8 @@ pub enum InputMethod {
     Telex,
     /// Apply only to VNI
     Vni,
+    /// Apply only to VIQR
+    Viqr,
 }
 
 /// Trigger condition for shortcut
@@ -42,7 +44,7 @@ pub enum CaseMode {
 }
 
 /// A single shortcut entry
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Shortcut {
     /// Trigger string (lowercase for matching)
     pub trigger: String,
@@ -127,6 +129,19 @@ impl Shortcut {
         }
     }
 
+    /// Create a VIQR-specific shortcut with immediate trigger.
+    /// Replacement is truncated to MAX_REPLACEMENT_LEN (255) codepoints if too long.
+    pub fn viqr(trigger: &str, replacement: &str) -> Self {
+        Self {
+            trigger: trigger.to_lowercase(), // Store lowercase for case-insensitive matching
+            replacement: Self::validate_replacement(replacement),
+            condition: TriggerCondition::Immediate,
+            case_mode: CaseMode::MatchCase, // Smart case transformation
+            enabled: true,
+            input_method: InputMethod::Viqr,
+        }
+    }
+
     /// Set the input method for this shortcut
     pub fn for_method(mut self, method: InputMethod) -> Self {
         self.input_method = method;
@@ -138,6 +153,7 @@ impl Shortcut {
     /// - If shortcut is for `All`: matches any method
     /// - If shortcut is for `Telex`: matches `Telex` or `All` query
     /// - If shortcut is for `Vni`: matches `Vni` or `All` query
+    /// - If shortcut is for `Viqr`: matches `Viqr` or `All` query
     pub fn applies_to(&self, query_method: InputMethod) -> bool {
         match self.input_method {
             // Shortcut for All → matches any query
@@ -149,6 +165,9 @@ impl Shortcut {
             InputMethod::Vni => {
                 query_method == InputMethod::Vni || query_method == InputMethod::All
             }
+            InputMethod::Viqr => {
+                query_method == InputMethod::Viqr || query_method == InputMethod::All
+            }
         }
     }
 }
@@ -345,27 +364,7 @@ impl ShortcutTable {
     fn apply_case(&self, trigger: &str, replacement: &str, mode: CaseMode) -> String {
         match mode {
             CaseMode::Exact => replacement.to_string(),
-            CaseMode::MatchCase => {
-                if trigger.chars().all(|c| c.is_uppercase()) {
-                    // All uppercase → replacement all uppercase
-                    replacement.to_uppercase()
-                } else if trigger
-                    .chars()
-                    .next()
-                    .map(|c| c.is_uppercase())
-                    .unwrap_or(false)
-                {
-                    // First char uppercase → capitalize replacement
-                    let mut chars = replacement.chars();
-                    match chars.next() {
-                        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
-                        None => String::new(),
-                    }
-                } else {
-                    // Lowercase → keep replacement as-is
-                    replacement.to_string()
-                }
-            }
+            CaseMode::MatchCase => data::case::match_case(trigger, replacement),
         }
     }
 
@@ -376,6 +375,11 @@ impl ShortcutTable {
             .sort_by_key(|s| std::cmp::Reverse(s.len()));
     }
 
+    /// All shortcuts, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &Shortcut> {
+        self.shortcuts.values()
+    }
+
     /// Check if shortcut table is empty
     pub fn is_empty(&self) -> bool {
         self.shortcuts.is_empty()
@@ -592,6 +596,19 @@ mod tests {
         assert_no_match(&table, "7", None, false, InputMethod::Telex);
     }
 
+    #[test]
+    fn test_viqr_specific_shortcut() {
+        let mut table = ShortcutTable::new();
+        table.add(Shortcut::viqr("dd", "đ"));
+
+        // Should match for VIQR
+        assert_shortcut_match(&table, "dd", None, false, "đ", 2, InputMethod::Viqr);
+
+        // Should NOT match for Telex or VNI
+        assert_no_match(&table, "dd", None, false, InputMethod::Telex);
+        assert_no_match(&table, "dd", None, false, InputMethod::Vni);
+    }
+
     #[test]
     fn test_all_input_method_shortcut() {
         let table = table_with_shortcut("vn", "Việt Nam");
@@ -0,0 +1,135 @@
+//! Surrounding Text - engine state from the text around the cursor
+//!
+//! Frontends that can read the document (Fcitx5, IBus, macOS AX) pass the
+//! text before and after the cursor to `Engine::set_surrounding_text`
+//! whenever the cursor moves. The engine rebuilds what typing that text
+//! would have left behind:
+//!
+//! ```text
+//! before          buffer   history      spaces   capitalize
+//! "Xin chào. Việ"  "Việ"    []           0        no
+//! "chào bạn  "     ""       [chào, bạn]  2        no
+//! "Xong. "         ""       []           0        yes
+//! ```
+//!
+//! `Engine::on_key_surrounding` then reports each key as a `TextEdit`: a
+//! range before the cursor and its replacement, trimmed to the chars that
+//! actually change ("tieng" + s replaces "e" with "ế", not "ieng").
+
+use data::chars;
+
+/// Output of one key as a replace-range edit
+///
+/// Replace the chars from `start` to `end` chars before the cursor with
+/// `text`; the cursor stays after the unchanged `end` chars. Then forward
+/// the key to the application if `pass_through`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Chars before the cursor where the replaced range starts
+    pub start: usize,
+    /// Chars before the cursor where the replaced range ends (`end <= start`)
+    pub end: usize,
+    pub text: String,
+    /// The key was not handled, or still types its own char after the edit
+    pub pass_through: bool,
+}
+
+impl TextEdit {
+    /// Nothing to replace
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end && self.text.is_empty()
+    }
+
+    /// Edit that deletes `backspace` chars before the cursor and inserts `new`
+    ///
+    /// `old` is the text known to end at the cursor (the word on screen);
+    /// chars both sides agree on are left out of the range.
+    pub(crate) fn replace(old: &[char], backspace: usize, new: &[char]) -> Self {
+        let known = backspace.min(old.len());
+        let old = &old[old.len() - known..];
+        let same_end = old
+            .iter()
+            .rev()
+            .zip(new.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        // Deleted chars beyond the word are unknown: the range starts there
+        let same_start = if known == backspace {
+            old[..known - same_end]
+                .iter()
+                .zip(&new[..new.len() - same_end])
+                .take_while(|(a, b)| a == b)
+                .count()
+        } else {
+            0
+        };
+        Self {
+            start: backspace - same_start,
+            end: same_end,
+            text: new[same_start..new.len() - same_end].iter().collect(),
+            pass_through: false,
+        }
+    }
+}
+
+/// What typing the text before the cursor leaves in the engine
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Context<'a> {
+    /// Word the cursor is at the end of
+    pub word: &'a str,
+    /// Words committed with space since the last break, oldest first
+    pub history: Vec<&'a str>,
+    /// Spaces before the word
+    pub spaces: usize,
+    /// Sentence-ending punctuation right before `spaces` ("ok. ", "ok.")
+    pub sentence_end: bool,
+    /// Line break right before `spaces`
+    pub line_start: bool,
+}
+
+fn is_letter(c: char) -> bool {
+    chars::parse_char(c).is_some()
+}
+
+/// Split `s` into the text before its trailing word and the word
+fn split_word(s: &str) -> (&str, &str) {
+    let start = s
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_letter(c))
+        .last()
+        .map_or(s.len(), |(i, _)| i);
+    s.split_at(start)
+}
+
+/// Context for a cursor between `before` and `after`
+///
+/// None when the cursor is inside a word: the engine only edits the word
+/// it is at the end of.
+pub(crate) fn context<'a>(before: &'a str, after: &str) -> Option<Context<'a>> {
+    let (mut rest, word) = split_word(before);
+    if after.chars().next().is_some_and(is_letter) {
+        return None;
+    }
+
+    let trimmed = rest.trim_end_matches(' ');
+    let mut ctx = Context {
+        word,
+        history: Vec::new(),
+        spaces: rest.len() - trimmed.len(),
+        sentence_end: trimmed.ends_with(['.', '!', '?']),
+        line_start: trimmed.ends_with(['\n', '\r']),
+    };
+    // Words separated by spaces only; any other break clears the history
+    loop {
+        let trimmed = rest.trim_end_matches(' ');
+        let (head, previous) = split_word(trimmed);
+        if trimmed.len() == rest.len() || previous.is_empty() {
+            break;
+        }
+        ctx.history.push(previous);
+        rest = head;
+    }
+    ctx.history.reverse();
+    Some(ctx)
+}
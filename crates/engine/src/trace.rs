@@ -0,0 +1,279 @@
+//! Decision Trace - why the engine did what it did, key by key
+//!
+//! Off by default. With `Engine::set_trace(true)` every key records the
+//! branches taken, the state flags it changed, the dictionary lookups made
+//! and how the buffer validates afterwards:
+//!
+//! ```text
+//! key  steps                           flags                           lookups
+//! t    letter                                                          english "t" hit
+//! e    letter                                                          english "te" hit
+//! x    mark                            had_any_transform false → true
+//! t    letter, auto_restore_mid_word   restored_mid_word None → "tẽt"  english "text" hit
+//! ```
+//!
+//! Read it with `Engine::trace` or as JSON with `Engine::trace_json`, the
+//! format attached to bug reports. Only the last `CAPACITY` keys are kept.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+/// Keys kept in the trace
+pub const CAPACITY: usize = 1000;
+
+/// Everything recorded for one key
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyTrace {
+    pub key: u16,
+    pub caps: bool,
+    pub ctrl: bool,
+    pub shift: bool,
+    /// Character reported by the platform (`on_key_with_char`)
+    pub ch: Option<char>,
+    /// Branches taken, in order ("space", "auto_restore")
+    pub steps: Vec<&'static str>,
+    pub flags: Vec<FlagChange>,
+    pub lookups: Vec<Lookup>,
+    /// Word being typed after the key
+    pub buffer: String,
+    /// Keystrokes of the word after the key
+    pub raw: String,
+    /// Validation of the buffer after the key, None when empty
+    pub validation: Option<String>,
+    pub action: u8,
+    pub backspace: u8,
+    pub output: String,
+}
+
+/// A state flag the key changed, values as `Debug` prints them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlagChange {
+    pub name: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+/// A word list consulted while handling the key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lookup {
+    /// "english" (`english_dict`), "telex_doubles" or "lexicon"
+    pub list: &'static str,
+    pub word: String,
+    pub hit: bool,
+}
+
+/// Flag values by name, see `Engine::trace_flags`
+pub(crate) type Flags = Vec<(&'static str, String)>;
+
+/// Key being recorded, with the flags it started from
+struct Current {
+    key: KeyTrace,
+    flags: Flags,
+}
+
+#[derive(Default)]
+struct Log {
+    keys: VecDeque<KeyTrace>,
+    current: Option<Current>,
+}
+
+/// Trace storage inside `Engine`
+///
+/// Interior mutability lets `&self` helpers (auto-restore checks, word
+/// lookups) record too. Costs one branch per call while tracing is off.
+#[derive(Default)]
+pub(crate) struct Recorder {
+    log: RefCell<Option<Log>>,
+}
+
+impl Recorder {
+    /// Turn recording on (with an empty log) or off
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        *self.log.get_mut() = enabled.then(Log::default);
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.log.borrow().is_some()
+    }
+
+    /// Start recording a key; false if off or a key is already being recorded
+    pub(crate) fn begin(&self, key: KeyTrace, flags: Flags) -> bool {
+        match self.log.borrow_mut().as_mut() {
+            Some(log) if log.current.is_none() => {
+                log.current = Some(Current { key, flags });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Finish the key started by `begin`, with the state it left behind
+    pub(crate) fn end(&self, flags: Flags, after: KeyTrace) {
+        let mut log = self.log.borrow_mut();
+        let Some(log) = log.as_mut() else {
+            return;
+        };
+        let Some(Current { key, flags: before }) = log.current.take() else {
+            return;
+        };
+        let changed = before
+            .into_iter()
+            .zip(flags)
+            .filter(|(b, a)| b.1 != a.1)
+            .map(|((name, before), (_, after))| FlagChange {
+                name,
+                before,
+                after,
+            })
+            .collect();
+        if log.keys.len() == CAPACITY {
+            log.keys.pop_front();
+        }
+        log.keys.push_back(KeyTrace {
+            steps: key.steps,
+            lookups: key.lookups,
+            flags: changed,
+            ..after
+        });
+    }
+
+    fn with_current(&self, f: impl FnOnce(&mut KeyTrace)) {
+        if let Some(current) = self
+            .log
+            .borrow_mut()
+            .as_mut()
+            .and_then(|log| log.current.as_mut())
+        {
+            f(&mut current.key);
+        }
+    }
+
+    /// Record a branch of the key being handled
+    pub(crate) fn step(&self, step: &'static str) {
+        self.with_current(|k| k.steps.push(step));
+    }
+
+    /// Record a word list lookup, returning `hit`
+    pub(crate) fn lookup(&self, list: &'static str, word: &str, hit: bool) -> bool {
+        self.with_current(|k| {
+            k.lookups.push(Lookup {
+                list,
+                word: word.to_string(),
+                hit,
+            })
+        });
+        hit
+    }
+
+    pub(crate) fn keys(&self) -> Vec<KeyTrace> {
+        self.log
+            .borrow()
+            .as_ref()
+            .map_or_else(Vec::new, |log| log.keys.iter().cloned().collect())
+    }
+
+    pub(crate) fn clear(&mut self) {
+        if let Some(log) = self.log.get_mut() {
+            log.keys.clear();
+        }
+    }
+}
+
+/// JSON string literal
+fn string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn strings<'a>(out: &mut String, items: impl IntoIterator<Item = &'a str>) {
+    out.push('[');
+    for (i, s) in items.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        string(out, s);
+    }
+    out.push(']');
+}
+
+impl KeyTrace {
+    /// One JSON object, keys in field order
+    pub fn to_json(&self) -> String {
+        let mut out = String::with_capacity(256);
+        let _ = write!(
+            out,
+            "{{\"key\":{},\"caps\":{},\"ctrl\":{},\"shift\":{},\"ch\":",
+            self.key, self.caps, self.ctrl, self.shift
+        );
+        match self.ch {
+            Some(c) => string(&mut out, c.encode_utf8(&mut [0; 4])),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"steps\":");
+        strings(&mut out, self.steps.iter().copied());
+        out.push_str(",\"flags\":[");
+        for (i, f) in self.flags.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            string(&mut out, f.name);
+            out.push_str(",\"before\":");
+            string(&mut out, &f.before);
+            out.push_str(",\"after\":");
+            string(&mut out, &f.after);
+            out.push('}');
+        }
+        out.push_str("],\"lookups\":[");
+        for (i, l) in self.lookups.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"list\":");
+            string(&mut out, l.list);
+            out.push_str(",\"word\":");
+            string(&mut out, &l.word);
+            let _ = write!(out, ",\"hit\":{}}}", l.hit);
+        }
+        out.push_str("],\"buffer\":");
+        string(&mut out, &self.buffer);
+        out.push_str(",\"raw\":");
+        string(&mut out, &self.raw);
+        out.push_str(",\"validation\":");
+        match &self.validation {
+            Some(v) => string(&mut out, v),
+            None => out.push_str("null"),
+        }
+        let _ = write!(
+            out,
+            ",\"action\":{},\"backspace\":{},\"output\":",
+            self.action, self.backspace
+        );
+        string(&mut out, &self.output);
+        out.push('}');
+        out
+    }
+}
+
+/// JSON array of `keys`, one object per line
+pub fn to_json<'a>(keys: impl IntoIterator<Item = &'a KeyTrace>) -> String {
+    let lines: Vec<String> = keys.into_iter().map(KeyTrace::to_json).collect();
+    if lines.is_empty() {
+        return "[]".to_string();
+    }
+    format!("[\n{}\n]", lines.join(",\n"))
+}
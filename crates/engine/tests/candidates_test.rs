@@ -0,0 +1,116 @@
+//! Candidate List Tests - alternatives for the word being typed and commit
+
+mod common;
+use common::{engine_telex, engine_vni, type_word};
+use data::charset::Charset;
+use engine::{Action, CandidateKind, Engine, Result};
+
+fn texts(e: &Engine) -> Vec<String> {
+    e.candidates().into_iter().map(|c| c.text).collect()
+}
+
+/// Apply a result to the screen
+fn apply(screen: &mut String, r: &Result) {
+    assert_eq!(r.action, Action::Send as u8);
+    for _ in 0..r.backspace {
+        screen.pop();
+    }
+    screen.extend(
+        r.chars[..r.count as usize]
+            .iter()
+            .filter_map(|&c| char::from_u32(c)),
+    );
+}
+
+#[test]
+fn english_word() {
+    let mut e = engine_telex();
+    assert_eq!(type_word(&mut e, "text"), "tẽt");
+    let candidates = e.candidates();
+    assert_eq!(candidates[0].text, "tẽt");
+    assert_eq!(candidates[0].kind, CandidateKind::Vietnamese);
+    assert_eq!(candidates[1].text, "text");
+    assert_eq!(candidates[1].kind, CandidateKind::English);
+    // Raw keystrokes are the English word: listed once
+    assert!(candidates[2..]
+        .iter()
+        .all(|c| c.kind == CandidateKind::Dictionary));
+}
+
+#[test]
+fn stroke_and_raw() {
+    let mut e = engine_telex();
+    type_word(&mut e, "dd");
+    assert_eq!(texts(&e)[..2], ["đ", "dd"]);
+
+    let mut e = engine_vni();
+    type_word(&mut e, "vie6t5");
+    assert_eq!(texts(&e), ["việt", "vie6t5"]);
+    assert_eq!(e.candidates()[1].kind, CandidateKind::Raw);
+}
+
+#[test]
+fn dictionary_nearest() {
+    let mut e = engine_telex();
+    type_word(&mut e, "khoanhf");
+    let candidates = e.candidates();
+    assert_eq!(candidates[0].text, "khoành");
+    assert_eq!(candidates[1].kind, CandidateKind::Dictionary);
+    assert_eq!(candidates[1].text, "khoanh");
+    assert_eq!(candidates[2].text, "khoanhf");
+}
+
+#[test]
+fn valid_word_has_no_dictionary_candidate() {
+    let mut e = engine_telex();
+    type_word(&mut e, "vieejt");
+    assert!(e
+        .candidates()
+        .iter()
+        .all(|c| c.kind != CandidateKind::Dictionary));
+}
+
+#[test]
+fn empty_buffer() {
+    let mut e = engine_telex();
+    assert!(e.candidates().is_empty());
+    type_word(&mut e, "abc ");
+    assert!(e.candidates().is_empty());
+    assert_eq!(e.commit_candidate(0).action, Action::None as u8);
+}
+
+#[test]
+fn commit_replaces_screen() {
+    let mut e = engine_telex();
+    let mut screen = type_word(&mut e, "text");
+    apply(&mut screen, &e.commit_candidate(1));
+    assert_eq!(screen, "text");
+    // The word is finished
+    assert!(e.candidates().is_empty());
+    screen.push_str(&type_word(&mut e, "as"));
+    assert_eq!(screen, "textá");
+}
+
+#[test]
+fn commit_out_of_range() {
+    let mut e = engine_telex();
+    type_word(&mut e, "vieejt");
+    assert_eq!(e.commit_candidate(5).action, Action::None as u8);
+    assert_eq!(texts(&e), ["việt", "vieejt"]);
+}
+
+#[test]
+fn commit_legacy_charset() {
+    // NFD: "việt" is 6 units on screen (ệ = e + circumflex + dot below)
+    let mut e = engine_telex();
+    e.set_charset(Charset::UnicodeNfd);
+    type_word(&mut e, "vieejt");
+    let raw = e.candidates().len() - 1;
+    let r = e.commit_candidate(raw);
+    assert_eq!(r.backspace, 6);
+    let out: String = r.chars[..r.count as usize]
+        .iter()
+        .filter_map(|&c| char::from_u32(c))
+        .collect();
+    assert_eq!(out, "vieejt");
+}
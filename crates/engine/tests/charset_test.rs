@@ -0,0 +1,272 @@
+//! Output Charset Tests - legacy encodings (TCVN3, VNI-Windows, VISCII, CP1258)
+//! and decomposed Unicode (NFD)
+//!
+//! Results carry one entry per encoded unit (byte or codepoint) and backspace
+//! counts in the same units. The screen is simulated and decoded back.
+
+mod common;
+use common::char_to_key;
+use data::charset::{self, Charset};
+use data::keys;
+use engine::{Action, Engine, Shortcut};
+
+/// Type input with a legacy charset, return the decoded screen text
+/// '<' is DELETE, same as type_word
+fn type_encoded(charset: Charset, method: u8, input: &str) -> String {
+    let mut e = Engine::new();
+    e.set_method(method);
+    e.set_charset(charset);
+    let mut screen: Vec<u8> = Vec::new();
+    for c in input.chars() {
+        let key = if c == '<' {
+            keys::DELETE
+        } else {
+            char_to_key(c)
+        };
+        let r = e.on_key(key, c.is_uppercase(), false);
+        if r.action == Action::Send as u8 {
+            for _ in 0..r.backspace {
+                screen.pop();
+            }
+            for &ch in &r.chars[..r.count as usize] {
+                assert!(
+                    ch < 0x100,
+                    "{:?} output U+{:04X} is not a byte",
+                    charset,
+                    ch
+                );
+                screen.push(ch as u8);
+            }
+            if r.key_consumed() {
+                continue;
+            }
+        }
+        // Key passed through: app types it (or deletes one byte)
+        if key == keys::DELETE {
+            screen.pop();
+        } else {
+            screen.push(c as u8);
+        }
+    }
+    charset::decode(&screen, charset)
+}
+
+const CASES: &[(&str, &str)] = &[
+    ("vieetj", "việt"),
+    ("tieengs vieetj", "tiếng việt"),
+    ("dduwowngf", "đường"),
+    ("nguyeenx", "nguyễn"),
+    ("hoaf", "hoà"),
+    ("asf", "à"),
+    ("thuowngr", "thưởng"),
+    ("vieetj<<", "vi"),
+    ("ddas<", "đ"),
+];
+
+#[test]
+fn legacy_charsets_telex() {
+    for charset in [
+        Charset::Tcvn3,
+        Charset::VniWindows,
+        Charset::Viscii,
+        Charset::Cp1258,
+    ] {
+        for (input, expected) in CASES {
+            let result = type_encoded(charset, 0, input);
+            assert_eq!(
+                result, *expected,
+                "[{:?}] '{}' → '{}'",
+                charset, input, result
+            );
+        }
+    }
+}
+
+#[test]
+fn legacy_charsets_vni() {
+    for charset in [Charset::VniWindows, Charset::Cp1258] {
+        assert_eq!(type_encoded(charset, 1, "vie65t"), "việt");
+        assert_eq!(type_encoded(charset, 1, "d9uo7ng2"), "đường");
+    }
+}
+
+#[test]
+fn vni_windows_backspace_counts_bytes() {
+    let mut e = Engine::new();
+    e.set_charset(Charset::VniWindows);
+    e.on_key(keys::V, false, false);
+    e.on_key(keys::I, false, false);
+    e.on_key(keys::E, false, false);
+    // ee → ê = "e" + 0xE2
+    let r = e.on_key(keys::E, false, false);
+    assert_eq!(r.backspace, 1);
+    assert_eq!(&r.chars[..r.count as usize], &['e' as u32, 0xE2]);
+    // ê + j → ệ: replaces both bytes of ê
+    let r = e.on_key(keys::J, false, false);
+    assert_eq!(r.backspace, 2);
+    assert_eq!(&r.chars[..r.count as usize], &['e' as u32, 0xE4]);
+    // DELETE on a two-byte letter removes both bytes
+    let r = e.on_key(keys::DELETE, false, false);
+    assert!(r.key_consumed());
+    assert_eq!(r.backspace, 2);
+    assert_eq!(r.count, 0);
+}
+
+#[test]
+fn vni_windows_long_shortcut_passes_through() {
+    // 35 characters, 42 bytes in VNI-Windows (ộ ò ã ộ ủ ĩ ệ take two)
+    let motto = "Cộng hòa xã hội chủ nghĩa Việt Nam ";
+    let mut e = Engine::new();
+    e.set_charset(Charset::VniWindows);
+    e.shortcuts_mut().add(Shortcut::new("ch", motto.trim_end()));
+    e.shortcuts_mut()
+        .add(Shortcut::new("ch7", &motto.repeat(7)));
+    e.on_key(keys::C, false, false);
+    e.on_key(keys::H, false, false);
+    let r = e.on_key(keys::SPACE, false, false);
+    assert_eq!(r.action, Action::Send as u8);
+    assert_eq!(r.backspace, 2);
+    let bytes: Vec<u8> = r.chars[..r.count as usize]
+        .iter()
+        .map(|&c| c as u8)
+        .collect();
+    assert_eq!(charset::decode(&bytes, Charset::VniWindows), motto);
+
+    // 245 characters fit a shortcut, 294 bytes don't fit a Result: the key
+    // is typed as is
+    for key in [keys::C, keys::H, keys::N7] {
+        e.on_key(key, false, false);
+    }
+    let r = e.on_key(keys::SPACE, false, false);
+    assert_eq!(r.action, Action::None as u8);
+    assert_eq!(r.count, 0);
+    // Engine starts over: the next word types normally
+    e.on_key(keys::A, false, false);
+    let r = e.on_key(keys::S, false, false);
+    assert_eq!(r.backspace, 1);
+    assert_eq!(&r.chars[..r.count as usize], &['a' as u32, 0xF9]);
+}
+
+#[test]
+fn tcvn3_single_byte() {
+    let mut e = Engine::new();
+    e.set_charset(Charset::Tcvn3);
+    e.on_key(keys::A, false, false);
+    let r = e.on_key(keys::S, false, false);
+    assert_eq!(r.backspace, 1);
+    assert_eq!(&r.chars[..r.count as usize], &[0xB8]);
+    // Single-byte letters pass DELETE through
+    let r = e.on_key(keys::DELETE, false, false);
+    assert_eq!(r.action, Action::None as u8);
+}
+
+#[test]
+fn unicode_default() {
+    let mut e = Engine::new();
+    assert_eq!(e.charset(), Charset::Unicode);
+    e.on_key(keys::A, false, false);
+    let r = e.on_key(keys::S, false, false);
+    assert_eq!(&r.chars[..r.count as usize], &['á' as u32]);
+}
+
+// UNICODE NFD
+
+/// Type input with NFD output, return the screen text (codepoints)
+fn type_nfd(input: &str) -> String {
+    let mut e = Engine::new();
+    e.set_charset(Charset::UnicodeNfd);
+    let mut screen: Vec<char> = Vec::new();
+    for c in input.chars() {
+        let key = if c == '<' {
+            keys::DELETE
+        } else {
+            char_to_key(c)
+        };
+        let r = e.on_key(key, c.is_uppercase(), false);
+        if r.action == Action::Send as u8 {
+            for _ in 0..r.backspace {
+                screen.pop();
+            }
+            screen.extend(
+                r.chars[..r.count as usize]
+                    .iter()
+                    .filter_map(|&c| char::from_u32(c)),
+            );
+            if r.key_consumed() {
+                continue;
+            }
+        }
+        if key == keys::DELETE {
+            screen.pop();
+        } else {
+            screen.push(c);
+        }
+    }
+    screen.into_iter().collect()
+}
+
+#[test]
+fn nfd_output() {
+    for (input, expected) in CASES {
+        let result = type_nfd(input);
+        assert!(
+            result.chars().all(|c| data::chars::decompose(c).len() == 1),
+            "'{}' output not decomposed: {:?}",
+            input,
+            result
+        );
+        assert_eq!(
+            data::chars::compose(&result),
+            *expected,
+            "[NFD] '{}'",
+            input
+        );
+    }
+}
+
+#[test]
+fn nfd_backspace_counts_codepoints() {
+    let mut e = Engine::new();
+    e.set_charset(Charset::UnicodeNfd);
+    e.on_key(keys::A, false, false);
+    e.on_key(keys::A, false, false); // â = a + U+0302
+    let r = e.on_key(keys::S, false, false); // ấ = a + U+0302 + U+0301
+    assert_eq!(r.backspace, 2);
+    assert_eq!(&r.chars[..r.count as usize], &['a' as u32, 0x0302, 0x0301]);
+    let r = e.on_key(keys::DELETE, false, false);
+    assert_eq!(r.backspace, 3);
+}
+
+#[test]
+fn restore_word_accepts_nfd() {
+    let mut e = Engine::new();
+    // "việt" decomposed: i, e + U+0323 + U+0302
+    e.restore_word("vie\u{0323}\u{0302}t");
+    let r = e.on_key(keys::S, false, false);
+    let out: String = r.chars[..r.count as usize]
+        .iter()
+        .filter_map(|&c| char::from_u32(c))
+        .collect();
+    assert_eq!(r.backspace, 2);
+    assert_eq!(out, "ết");
+}
+
+#[test]
+fn on_key_with_char_accepts_combining_marks() {
+    let mut e = Engine::new();
+    e.on_key_with_char(keys::V, false, false, false, Some('v'));
+    e.on_key_with_char(keys::I, false, false, false, Some('i'));
+    e.on_key_with_char(keys::E, false, false, false, Some('e'));
+    // Circumflex then dot below, as a dead-key keyboard would send them
+    let r = e.on_key_with_char(0, false, false, false, Some('\u{0302}'));
+    assert!(r.key_consumed());
+    assert_eq!(&r.chars[..r.count as usize], &['ê' as u32]);
+    let r = e.on_key_with_char(0, false, false, false, Some('\u{0323}'));
+    assert_eq!(r.backspace, 1);
+    assert_eq!(&r.chars[..r.count as usize], &['ệ' as u32]);
+    // Marks that don't fit pass through
+    e.clear();
+    e.on_key_with_char(keys::N, false, false, false, Some('n'));
+    let r = e.on_key_with_char(0, false, false, false, Some('\u{0303}'));
+    assert!(!r.key_consumed());
+}
@@ -88,33 +88,38 @@ pub fn keys_from_str(s: &str) -> Vec<u16> {
     s.chars().map(char_to_key).filter(|&k| k != 255).collect()
 }
 
+/// Key code and shift state that type a character
+pub fn key_for(c: char) -> (u16, bool) {
+    match c {
+        '@' => (keys::N2, true),
+        '!' => (keys::N1, true),
+        '#' => (keys::N3, true),
+        '$' => (keys::N4, true),
+        '%' => (keys::N5, true),
+        '^' => (keys::N6, true),
+        '&' => (keys::N7, true),
+        '*' => (keys::N8, true),
+        '(' => (keys::N9, true),
+        ')' => (keys::N0, true),
+        '_' => (keys::MINUS, true),
+        '+' => (keys::EQUAL, true),
+        ':' => (keys::SEMICOLON, true),
+        '"' => (keys::QUOTE, true),
+        '>' => (keys::DOT, true),
+        '?' => (keys::SLASH, true),
+        '|' => (keys::BACKSLASH, true),
+        '{' => (keys::LBRACKET, true),
+        '}' => (keys::RBRACKET, true),
+        '~' => (keys::BACKQUOTE, true),
+        _ => (char_to_key(c), false),
+    }
+}
+
 /// Simulate typing, returns screen output
 pub fn type_word(e: &mut Engine, input: &str) -> String {
     let mut screen = String::new();
     for c in input.chars() {
-        let (key, shift) = match c {
-            '@' => (keys::N2, true),
-            '!' => (keys::N1, true),
-            '#' => (keys::N3, true),
-            '$' => (keys::N4, true),
-            '%' => (keys::N5, true),
-            '^' => (keys::N6, true),
-            '&' => (keys::N7, true),
-            '*' => (keys::N8, true),
-            '(' => (keys::N9, true),
-            ')' => (keys::N0, true),
-            '_' => (keys::MINUS, true),
-            '+' => (keys::EQUAL, true),
-            ':' => (keys::SEMICOLON, true),
-            '"' => (keys::QUOTE, true),
-            '>' => (keys::DOT, true),
-            '?' => (keys::SLASH, true),
-            '|' => (keys::BACKSLASH, true),
-            '{' => (keys::LBRACKET, true),
-            '}' => (keys::RBRACKET, true),
-            '~' => (keys::BACKQUOTE, true),
-            _ => (char_to_key(c), false),
-        };
+        let (key, shift) = key_for(c);
         let is_caps = c.is_uppercase();
 
         if key == keys::DELETE {
@@ -186,6 +191,30 @@ pub fn type_word(e: &mut Engine, input: &str) -> String {
     screen
 }
 
+/// Simulate typing in composition mode, returns the committed text
+/// followed by the preedit still being composed
+pub fn type_compose(e: &mut Engine, input: &str) -> String {
+    let mut doc = String::new();
+    for c in input.chars() {
+        let (key, shift) = key_for(c);
+        let r = e.on_key_compose(key, c.is_uppercase(), false, shift, None);
+        for _ in 0..r.delete {
+            doc.pop();
+        }
+        doc.push_str(&r.commit);
+        if r.pass_through {
+            match key {
+                keys::DELETE => {
+                    doc.pop();
+                }
+                keys::ESC => {}
+                _ => doc.push(c),
+            }
+        }
+    }
+    doc + &e.preedit()
+}
+
 /// Run Telex test cases
 pub fn telex(cases: &[(&str, &str)]) {
     for (input, expected) in cases {
@@ -233,6 +262,16 @@ pub fn vni(cases: &[(&str, &str)]) {
     }
 }
 
+/// Run VIQR test cases
+pub fn viqr(cases: &[(&str, &str)]) {
+    for (input, expected) in cases {
+        let mut e = Engine::new();
+        e.set_method(2);
+        let result = type_word(&mut e, input);
+        assert_eq!(result, *expected, "[VIQR] '{}' → '{}'", input, result);
+    }
+}
+
 /// Run Telex test cases with traditional tone placement (hòa, thúy style)
 pub fn telex_traditional(cases: &[(&str, &str)]) {
     for (input, expected) in cases {
@@ -269,6 +308,7 @@ pub fn vni_traditional(cases: &[(&str, &str)]) {
 pub enum Method {
     Telex,
     Vni,
+    Viqr,
 }
 
 /// Run test cases with method
@@ -276,6 +316,7 @@ pub fn run(method: Method, cases: &[(&str, &str)]) {
     match method {
         Method::Telex => telex(cases),
         Method::Vni => vni(cases),
+        Method::Viqr => viqr(cases),
     }
 }
 
@@ -297,6 +338,12 @@ pub fn engine_vni() -> Engine {
     e
 }
 
+pub fn engine_viqr() -> Engine {
+    let mut e = Engine::new();
+    e.set_method(2);
+    e
+}
+
 // ASSERTION HELPERS
 
 /// Assert engine action
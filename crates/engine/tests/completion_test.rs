@@ -0,0 +1,117 @@
+//! Completion Tests - prefix completion, next-syllable prediction, accept
+
+mod common;
+use common::{engine_telex, type_word};
+use data::keys;
+use engine::{Action, Engine, Result};
+
+fn engine_completion() -> Engine {
+    let mut e = engine_telex();
+    e.set_completion(true);
+    e
+}
+
+/// Apply a result to the screen
+fn apply(screen: &mut String, r: &Result) {
+    assert_eq!(r.action, Action::Send as u8);
+    for _ in 0..r.backspace {
+        screen.pop();
+    }
+    screen.extend(
+        r.chars[..r.count as usize]
+            .iter()
+            .filter_map(|&c| char::from_u32(c)),
+    );
+}
+
+#[test]
+fn disabled_by_default() {
+    let mut e = engine_telex();
+    assert!(!e.completion());
+    type_word(&mut e, "ngh");
+    assert!(e.completions(5).is_empty());
+    assert_eq!(e.accept_completion(0).action, Action::None as u8);
+}
+
+#[test]
+fn completes_prefix() {
+    let mut e = engine_completion();
+    type_word(&mut e, "nghi");
+    let completions = e.completions(5);
+    assert_eq!(completions.len(), 5);
+    assert!(completions.iter().all(|w| w.starts_with("ngh")));
+}
+
+#[test]
+fn predicts_next_syllable() {
+    let mut e = engine_completion();
+    type_word(&mut e, "Vieejt ");
+    assert_eq!(e.completions(1), ["nam"]);
+    type_word(&mut e, "N");
+    assert_eq!(e.completions(1), ["Nam"]);
+}
+
+#[test]
+fn predicts_from_learned_phrases() {
+    let mut e = engine_completion();
+    type_word(&mut e, "Vieejt Nam ");
+    assert_eq!(e.phrases().count("việt nam"), 1);
+
+    e.clear_all();
+    type_word(&mut e, "Vieejt ");
+    assert_eq!(e.completions(1), ["Nam"]);
+    type_word(&mut e, "n");
+    assert_eq!(e.completions(1), ["nam"]);
+}
+
+#[test]
+fn completion_off_learns_nothing() {
+    let mut e = engine_telex();
+    type_word(&mut e, "Vieejt Nam ");
+    assert!(e.phrases().is_empty());
+}
+
+#[test]
+fn accept_replaces_word() {
+    let mut e = engine_completion();
+    type_word(&mut e, "hoom nay ");
+    e.clear_all();
+    let mut screen = type_word(&mut e, "hoom ");
+    screen.push_str(&type_word(&mut e, "n"));
+    let expected = e.completions(1)[0].clone();
+    assert_eq!(expected, "nay");
+    apply(&mut screen, &e.accept_completion(0));
+    assert_eq!(screen, "hôm nay");
+
+    // The accepted word is editable, then space commits it
+    apply(&mut screen, &e.on_key(keys::F, false, false));
+    assert_eq!(screen, "hôm này");
+    screen.push_str(&type_word(&mut e, " "));
+    assert_eq!(screen, "hôm này ");
+    assert_eq!(e.phrases().count("hôm này"), 1);
+}
+
+#[test]
+fn accept_multi_syllable() {
+    let mut e = engine_completion();
+    type_word(&mut e, "coojng hoaf xax ");
+    e.clear_all();
+    let mut screen = type_word(&mut e, "coojng h");
+    assert_eq!(e.completions(2), ["hoà xã", "hoà"]);
+    apply(&mut screen, &e.accept_completion(0));
+    assert_eq!(screen, "cộng hoà xã");
+
+    // The last word stays editable, the one before it is committed
+    apply(&mut screen, &e.on_key(keys::S, false, false));
+    assert_eq!(screen, "cộng hoà xá");
+    screen.push_str(&type_word(&mut e, " "));
+    assert_eq!(e.phrases().count("cộng hoà xá"), 1);
+}
+
+#[test]
+fn accept_out_of_range() {
+    let mut e = engine_completion();
+    type_word(&mut e, "xyz");
+    assert!(e.completions(5).is_empty());
+    assert_eq!(e.accept_completion(0).action, Action::None as u8);
+}
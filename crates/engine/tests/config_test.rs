@@ -0,0 +1,79 @@
+//! Engine Config Tests - apply, read back, and load settings files
+
+mod common;
+use common::type_word;
+use data::charset::Charset;
+use engine::{Engine, EngineConfig, Shortcut};
+
+#[test]
+fn new_engine_has_default_config() {
+    assert_eq!(Engine::new().config(), EngineConfig::default());
+}
+
+#[test]
+fn apply_and_read_back() {
+    let config = EngineConfig {
+        method: 1,
+        modern_tone: false,
+        esc_restore: true,
+        auto_capitalize: true,
+        quick_consonants: vec!['f', 'k'],
+        charset: Charset::Tcvn3,
+        shortcuts: vec![
+            Shortcut::new("hn", "Hà Nội"),
+            Shortcut::new("vn", "Việt Nam"),
+        ],
+        ..EngineConfig::default()
+    };
+    let mut e = Engine::new();
+    e.apply_config(&config).unwrap();
+    assert_eq!(e.config(), config);
+    assert_eq!(e.charset(), Charset::Tcvn3);
+
+    // Applying replaces everything, shortcuts included
+    e.apply_config(&EngineConfig::default()).unwrap();
+    assert_eq!(e.config(), EngineConfig::default());
+    assert!(e.shortcuts().is_empty());
+}
+
+#[test]
+fn applied_settings_take_effect() {
+    let text = "method = vni\nmodern_tone = false\n\n[shortcuts]\nvn = Việt Nam\n";
+    let mut e = Engine::new();
+    e.apply_config(&EngineConfig::parse(text).unwrap()).unwrap();
+    assert_eq!(type_word(&mut e, "hoa2 "), "hòa ");
+    assert_eq!(type_word(&mut e, "vn "), "Việt Nam ");
+}
+
+#[test]
+fn invalid_config_changes_nothing() {
+    let mut e = Engine::new();
+    e.set_free_tone(true);
+    let config = EngineConfig {
+        method: 9,
+        modern_tone: false,
+        ..EngineConfig::default()
+    };
+    assert!(e.apply_config(&config).is_err());
+    assert!(e.config().free_tone);
+    assert!(e.config().modern_tone);
+}
+
+#[test]
+fn save_and_load() {
+    let mut e = Engine::new();
+    e.set_method(2);
+    e.set_quick_telex(true);
+    e.shortcuts_mut().add(Shortcut::immediate("->", "→"));
+
+    let path = std::env::temp_dir().join(format!("gonhanh-config-{}.txt", std::process::id()));
+    e.config().save(&path).unwrap();
+    let loaded = EngineConfig::load(&path).unwrap();
+    assert_eq!(loaded, e.config());
+
+    std::fs::write(&path, "method = telex\ncharset = ebcdic\n").unwrap();
+    let err = EngineConfig::load(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("line 2"));
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,131 @@
+//! Offline Conversion Tests
+//!
+//! Whole strings and streamed chunks converted with `engine::convert`.
+
+use engine::{convert, convert_reader, ConvertOptions, Converter, Engine, Shortcut};
+
+fn telex() -> ConvertOptions {
+    ConvertOptions::default()
+}
+
+fn vni() -> ConvertOptions {
+    ConvertOptions {
+        method: 1,
+        ..ConvertOptions::default()
+    }
+}
+
+#[test]
+fn converts_sentence() {
+    assert_eq!(convert("Tieesng Vieejt", &telex()), "Tiếng Việt");
+    assert_eq!(convert("Tie61ng Vie65t", &vni()), "Tiếng Việt");
+}
+
+#[test]
+fn keeps_punctuation() {
+    assert_eq!(
+        convert("Xin chaof, banj khoer khoong? Toos!", &telex()),
+        "Xin chào, bạn khoẻ không? Tố!"
+    );
+    assert_eq!(
+        convert("\"aaa\" (vieejt) nhatkha1407@gmail.com", &telex()),
+        "\"aa\" (việt) nhatkha1407@gmail.com"
+    );
+}
+
+#[test]
+fn keeps_line_breaks() {
+    assert_eq!(
+        convert("dongf moojt\ndongf hai\r\ndongf ba\n", &telex()),
+        "dòng một\ndòng hai\r\ndòng ba\n"
+    );
+    assert_eq!(convert("a\tbs", &telex()), "a\tbs");
+    assert_eq!(convert("a\tas", &telex()), "a\tá");
+}
+
+#[test]
+fn copies_unmapped_characters() {
+    assert_eq!(
+        convert("Việt Nam vaf Lào 🇻🇳 tooi", &telex()),
+        "Việt Nam và Lào 🇻🇳 tôi"
+    );
+}
+
+#[test]
+fn auto_capitalize() {
+    let options = ConvertOptions {
+        auto_capitalize: true,
+        ..telex()
+    };
+    assert_eq!(
+        convert("Tooi ddi hocj. tooi veef nhaf.\nbaau", &options),
+        "Tôi đi học. Tôi về nhà.\nBâu"
+    );
+}
+
+#[test]
+fn english_auto_restore() {
+    let options = ConvertOptions {
+        english_auto_restore: true,
+        ..telex()
+    };
+    assert_eq!(
+        convert("Google Docss expect vieetj", &options),
+        "Google Docs expect việt"
+    );
+}
+
+#[test]
+fn traditional_tone() {
+    let options = ConvertOptions {
+        modern_tone: false,
+        ..telex()
+    };
+    assert_eq!(convert("hoaf thuys", &options), "hòa thúy");
+    assert_eq!(convert("hoaf thuys", &telex()), "hoà thuý");
+}
+
+#[test]
+fn streaming_matches_whole_string() {
+    let input = "Tooi ddax thuwr raats nhieeuf booj gox tieengs Vieetj.\nGox treen Chrome.";
+    let expected = convert(input, &telex());
+    for size in 1..8 {
+        let mut converter = Converter::new(&telex());
+        let mut out = String::new();
+        let chars: Vec<char> = input.chars().collect();
+        for chunk in chars.chunks(size) {
+            out.push_str(&converter.push_str(&chunk.iter().collect::<String>()));
+        }
+        out.push_str(&converter.finish());
+        assert_eq!(out, expected, "chunk size {}", size);
+    }
+}
+
+#[test]
+fn streaming_emits_finished_words() {
+    let mut converter = Converter::new(&telex());
+    assert_eq!(converter.push_str("vieej"), "");
+    assert_eq!(converter.push_str("t nam"), "việt ");
+    assert_eq!(converter.finish(), "nam");
+}
+
+#[test]
+fn reader_to_writer() {
+    let input = "Tieesng Vieejt\nlaf tieengs mej dder\n";
+    let mut out = Vec::new();
+    convert_reader(input.as_bytes(), &mut out, &telex()).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Tiếng Việt\nlà tiếng mẹ đẻ\n"
+    );
+}
+
+#[test]
+fn preconfigured_engine_shortcuts() {
+    let mut e = Engine::new();
+    e.shortcuts_mut().add(Shortcut::new("vn", "Việt Nam"));
+    let mut converter = Converter::with_engine(e);
+    let mut out = converter.push_str("tooi yeeu vn ");
+    out.push_str(&converter.finish());
+    assert_eq!(out, "tôi yêu Việt Nam ");
+}
@@ -0,0 +1,194 @@
+//! Custom Input Method Tests
+//!
+//! Methods defined at runtime from a key-mapping table and registered on
+//! the engine with `register_method`.
+
+mod common;
+use common::type_word;
+use engine::{CustomMethod, Engine};
+
+/// Telex with [ ] as horn keys and q instead of z to remove diacritics
+const TELEX_BRACKETS: &str = r#"
+name = "Telex brackets"
+base = "telex"
+
+[marks]
+sac = "s"
+huyen = "f"
+hoi = "r"
+nga = "x"
+nang = "j"
+
+[tones]
+a = "circumflex:a"
+e = "circumflex:e"
+o = "circumflex:o"
+w = "horn:aou"
+"[" = "horn:o"
+"]" = "horn:u"
+
+[keys]
+stroke = "d"
+remove = "q"
+"#;
+
+/// VNI-style layout with marks moved to the right-hand digits
+const VNI_RIGHT: &str = r#"
+name = "VNI right"
+base = "vni"
+
+[marks]
+sac = "6"
+huyen = "7"
+hoi = "8"
+nga = "9"
+nang = "0"
+
+[tones]
+"1" = "circumflex:aeo"
+"2" = "horn:ou"
+"3" = "breve:a"
+
+[keys]
+stroke = "4"
+remove = "5"
+"#;
+
+/// Telex without w: horn on =, q as remove key
+const TELEX_EQUALS: &str = r#"
+name = "Telex equals"
+
+[marks]
+sac = "s"
+huyen = "f"
+hoi = "r"
+nga = "x"
+nang = "j"
+
+[tones]
+a = "circumflex:a"
+e = "circumflex:e"
+o = "circumflex:o"
+"=" = "horn:aou"
+
+[keys]
+stroke = "d"
+remove = "q"
+"#;
+
+fn engine_with(definition: &str) -> Engine {
+    let mut e = Engine::new();
+    let id = e
+        .register_method(CustomMethod::parse(definition).unwrap())
+        .unwrap();
+    e.set_method(id);
+    e
+}
+
+fn run(definition: &str, cases: &[(&str, &str)]) {
+    for (input, expected) in cases {
+        let mut e = engine_with(definition);
+        let result = type_word(&mut e, input);
+        assert_eq!(result, *expected, "[custom] '{}' → '{}'", input, result);
+    }
+}
+
+#[test]
+fn telex_brackets() {
+    run(
+        TELEX_BRACKETS,
+        &[
+            ("o[", "ơ"),
+            ("u]", "ư"),
+            ("tu]o[i", "tươi"),
+            ("tu]o[is", "tưới"),
+            ("vieetj", "việt"),
+            ("dduwowcj", "được"),
+            ("ddaaus", "đấu"),
+            // q removes diacritics, z is an ordinary letter
+            ("asq", "a"),
+            ("az", "az"),
+        ],
+    );
+}
+
+#[test]
+fn telex_brackets_punctuation_without_vowel() {
+    // [ with nothing to modify stays punctuation
+    run(TELEX_BRACKETS, &[("[", "["), ("x[", "x[")]);
+}
+
+#[test]
+fn telex_extras_follow_mapping() {
+    run(
+        TELEX_EQUALS,
+        &[
+            ("u=", "ư"),
+            ("tu=o=i", "tươi"),
+            // w is not the horn key: no w → ư
+            ("w", "w"),
+            ("nhw", "nhw"),
+        ],
+    );
+
+    // Brackets stand in for w, so they stay punctuation
+    let mut e = engine_with(TELEX_EQUALS);
+    e.set_bracket_shortcut(true);
+    assert_eq!(type_word(&mut e, "tu]"), "tu]");
+
+    // q is the remove key, not Quick Telex qq → qu
+    let mut e = engine_with(TELEX_EQUALS);
+    e.set_quick_telex(true);
+    assert_eq!(type_word(&mut e, "qq nn"), "qq ng");
+}
+
+#[test]
+fn vni_right() {
+    run(
+        VNI_RIGHT,
+        &[
+            ("a6", "á"),
+            ("a7", "à"),
+            ("a1", "â"),
+            ("o2", "ơ"),
+            ("a3", "ă"),
+            ("d4", "đ"),
+            ("vie1t0", "việt"),
+            ("a65", "a"),
+            // Built-in VNI keys are plain digits here
+            ("a9", "ã"),
+        ],
+    );
+}
+
+#[test]
+fn reregister_replaces_method_and_keeps_id() {
+    let mut e = Engine::new();
+    let id = e
+        .register_method(CustomMethod::parse(TELEX_BRACKETS).unwrap())
+        .unwrap();
+    let other = e
+        .register_method(CustomMethod::parse(VNI_RIGHT).unwrap())
+        .unwrap();
+    assert_ne!(id, other);
+    e.set_method(id);
+
+    // Same name, q now means sắc
+    let edited = TELEX_BRACKETS
+        .replace("sac = \"s\"", "sac = \"q\"")
+        .replace("remove = \"q\"", "");
+    let again = e
+        .register_method(CustomMethod::parse(&edited).unwrap())
+        .unwrap();
+    assert_eq!(again, id);
+    assert_eq!(type_word(&mut e, "aq"), "á");
+}
+
+#[test]
+fn builtin_methods_unaffected() {
+    let mut e = engine_with(TELEX_BRACKETS);
+    e.set_method(0);
+    assert_eq!(type_word(&mut e, "o["), "o[");
+    e.set_method(1);
+    assert_eq!(type_word(&mut e, "a1"), "á");
+}
@@ -0,0 +1,104 @@
+//! User Lexicon Tests - pinned and learned words override auto-restore
+
+mod common;
+use common::type_word;
+use engine::{Engine, Language, UserLexicon, LEARN_THRESHOLD};
+
+fn engine_auto_restore() -> Engine {
+    let mut e = Engine::new();
+    e.set_method(0);
+    e.set_english_auto_restore(true);
+    e
+}
+
+#[test]
+fn defaults_without_lexicon() {
+    let mut e = engine_auto_restore();
+    assert_eq!(type_word(&mut e, "text "), "text ");
+    assert_eq!(type_word(&mut e, "dax "), "dã ");
+}
+
+#[test]
+fn pinned_english_is_restored() {
+    let mut e = engine_auto_restore();
+    e.lexicon_mut().pin("dax", Language::English);
+    assert_eq!(type_word(&mut e, "dax "), "dax ");
+    assert_eq!(type_word(&mut e, "Dax "), "Dax ");
+}
+
+#[test]
+fn pinned_vietnamese_is_kept() {
+    let mut e = engine_auto_restore();
+    e.lexicon_mut().pin("tẽt", Language::Vietnamese);
+    assert_eq!(type_word(&mut e, "text "), "tẽt ");
+}
+
+#[test]
+fn lexicon_needs_auto_restore() {
+    let mut e = Engine::new();
+    e.lexicon_mut().pin("dax", Language::English);
+    assert_eq!(type_word(&mut e, "dax "), "dã ");
+}
+
+#[test]
+fn learning_is_opt_in() {
+    let mut e = engine_auto_restore();
+    assert!(!e.lexicon_learning());
+    for _ in 0..LEARN_THRESHOLD {
+        type_word(&mut e, "dax ");
+    }
+    assert!(e.lexicon().is_empty());
+}
+
+#[test]
+fn learns_committed_words() {
+    let mut e = engine_auto_restore();
+    e.set_lexicon_learning(true);
+    for _ in 0..LEARN_THRESHOLD {
+        type_word(&mut e, "dax ");
+    }
+    assert_eq!(e.lexicon().language("dã"), Some(Language::Vietnamese));
+    // Words without transforms never reach the restore decision
+    type_word(&mut e, "hello ");
+    assert_eq!(e.lexicon().language("hello"), None);
+    assert!(e.lexicon().words().iter().all(|w| w.word != "hello"));
+}
+
+#[test]
+fn learns_restored_form() {
+    let mut e = engine_auto_restore();
+    e.set_lexicon_learning(true);
+    for _ in 0..LEARN_THRESHOLD {
+        assert_eq!(type_word(&mut e, "text "), "text ");
+    }
+    assert_eq!(e.lexicon().language("text"), Some(Language::English));
+}
+
+#[test]
+fn mark_revert_is_not_learned() {
+    let mut e = engine_auto_restore();
+    e.set_lexicon_learning(true);
+    for _ in 0..LEARN_THRESHOLD {
+        assert_eq!(type_word(&mut e, "ass "), "as ");
+    }
+    assert_eq!(e.lexicon().language("as"), None);
+    assert_eq!(type_word(&mut e, "as "), "á ");
+}
+
+#[test]
+fn save_and_load() {
+    let path = std::env::temp_dir().join(format!("gonhanh-lexicon-{}.txt", std::process::id()));
+    let mut lex = UserLexicon::new();
+    lex.pin("dax", Language::English);
+    lex.save(&path).unwrap();
+
+    let mut e = engine_auto_restore();
+    *e.lexicon_mut() = UserLexicon::load(&path).unwrap();
+    assert_eq!(type_word(&mut e, "dax "), "dax ");
+
+    std::fs::write(&path, "klingon dax\n").unwrap();
+    let err = UserLexicon::load(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    std::fs::remove_file(&path).unwrap();
+    assert!(UserLexicon::load(&path).is_err());
+}
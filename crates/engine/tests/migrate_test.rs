@@ -0,0 +1,158 @@
+//! Settings Migration Tests - settings files and macOS UserDefaults
+
+use engine::migrate::{self, MIGRATIONS};
+use engine::{EngineConfig, MigrationReport, TriggerCondition};
+use updater::Version;
+
+fn v(s: &str) -> Version {
+    Version::parse(s).unwrap()
+}
+
+fn load(text: &str) -> (EngineConfig, MigrationReport) {
+    migrate::parse(text, &v("0.1.0")).unwrap()
+}
+
+#[test]
+fn steps_are_ordered() {
+    let versions: Vec<Version> = MIGRATIONS.iter().map(|m| v(m.version)).collect();
+    assert!(versions.windows(2).all(|w| w[0].has_update(&w[1])));
+}
+
+#[test]
+fn unversioned_file_is_current() {
+    // What `EngineConfig::save` writes: no version line
+    let config = EngineConfig {
+        modern_tone: false,
+        esc_restore: true,
+        skip_w_shortcut: true,
+        ..EngineConfig::default()
+    };
+    let (loaded, report) = load(&config.to_text());
+    assert_eq!(report.from, None);
+    assert!(report.is_empty());
+    assert_eq!(loaded, config);
+}
+
+#[test]
+fn versioned_file_keeps_user_choices() {
+    let text = "version = 0.1.0\nmethod = vni\nmodern_tone = false\n\n[shortcuts]\nvn = Việt Nam\n";
+    let (config, report) = load(text);
+    assert_eq!(report.from, Some(v("0.1.0")));
+    assert!(report.is_empty());
+    assert_eq!(config.method, 1);
+    assert!(!config.modern_tone);
+    assert_eq!(config.shortcuts[0].replacement, "Việt Nam");
+
+    let (migrated, _) = migrate::migrate(text, &v("0.1.0")).unwrap();
+    assert_eq!(migrated, text);
+}
+
+#[test]
+fn newer_file_drops_unknown_settings() {
+    let (config, report) = load("version = 0.2.0\nmodern_tone = false\nemoji_shortcuts = true\n");
+    assert_eq!(report.from, Some(v("0.2.0")));
+    assert_eq!(report.changes.len(), 1);
+    assert_eq!(report.changes[0].key, "emoji_shortcuts");
+    assert!(!config.modern_tone);
+}
+
+#[test]
+fn save_stamps_version() {
+    let path = std::env::temp_dir().join(format!("gonhanh-migrate-{}.txt", std::process::id()));
+    let config = EngineConfig {
+        free_tone: true,
+        ..EngineConfig::default()
+    };
+    migrate::save(&config, &path, &v("1.4.2")).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.starts_with("# Gõ Nhanh settings\nversion = 1.4.2\nenabled = true\n"));
+
+    let (loaded, report) = migrate::load(&path, &v("1.4.2")).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, config);
+    assert!(report.is_empty());
+
+    assert!(migrate::migrate("version = one\n", &v("1.4.0")).is_err());
+}
+
+// macOS UserDefaults
+
+fn import(text: &str) -> (EngineConfig, MigrationReport) {
+    migrate::import_user_defaults(text, &v("0.1.0")).unwrap()
+}
+
+#[test]
+fn user_defaults_import() {
+    let text = r#"gonhanh.method = 1
+gonhanh.autoWShortcut = 0
+gonhanh.escRestore = 1
+gonhanh.modernTone = 0
+gonhanh.soundEnabled = 1
+gonhanh.shortcuts = [{"id":"6F1C1E64-2B0A-4C55-9C1B-0E5E4B8E2A11","key":"ko","value":"không","isEnabled":true},{"id":"0B7E2A53-8D4F-4E0E-A3B1-7A2E9C5D6F10","key":"-\/-","value":"÷","isEnabled":false},{"id":"1A2B3C4D-0000-4000-8000-000000000000","key":"","value":"","isEnabled":true}]
+"#;
+    let (config, report) = import(text);
+    assert_eq!(config.method, 1);
+    assert!(config.skip_w_shortcut);
+    assert!(config.esc_restore);
+    assert!(!config.modern_tone);
+    // Not stored: the app's registered default
+    assert!(!config.bracket_shortcut);
+
+    assert_eq!(config.shortcuts.len(), 2);
+    assert_eq!(config.shortcuts[0].trigger, "ko");
+    assert_eq!(config.shortcuts[0].replacement, "không");
+    assert!(config.shortcuts[0].enabled);
+    assert_eq!(config.shortcuts[1].trigger, "-/-");
+    assert_eq!(config.shortcuts[1].condition, TriggerCondition::Immediate);
+    assert!(!config.shortcuts[1].enabled);
+
+    // App-only keys are not reported
+    let keys: Vec<&str> = report.changes.iter().map(|c| c.key.as_str()).collect();
+    assert_eq!(
+        keys,
+        [
+            "method",
+            "skip_w_shortcut",
+            "esc_restore",
+            "modern_tone",
+            "shortcuts"
+        ]
+    );
+    assert_eq!(
+        report.changes[1].message,
+        "imported from gonhanh.autoWShortcut = 0"
+    );
+}
+
+#[test]
+fn user_defaults_never_changed() {
+    let (config, report) = import("");
+    assert!(report.is_empty());
+    assert_eq!(
+        EngineConfig {
+            shortcuts: Vec::new(),
+            ..config.clone()
+        },
+        EngineConfig::default()
+    );
+    // The four sample shortcuts the app lists, all off
+    assert_eq!(config.shortcuts.len(), 4);
+    assert!(config.shortcuts.iter().all(|s| !s.enabled));
+}
+
+#[test]
+fn user_defaults_invalid() {
+    let bad = [
+        "gonhanh.modernTone = maybe",
+        "gonhanh.method = 2",
+        "gonhanh.shortcuts = [{\"key\":\"vn\"}]",
+        "gonhanh.shortcuts = [{\"key\":\"vn\",\"value\":\"Việt Nam\"",
+    ];
+    for text in bad {
+        assert!(
+            migrate::import_user_defaults(text, &v("0.1.0")).is_err(),
+            "{}",
+            text
+        );
+    }
+}
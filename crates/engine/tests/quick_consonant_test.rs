@@ -0,0 +1,140 @@
+//! Quick Consonant Tests - Testing quick start/end consonant options
+//!
+//! Shorthand consonants expand when the syllable is valid Vietnamese:
+//! f → ph, j → gi, w → qu at the start; g → ng, h → nh, k → ch at the end.
+
+mod common;
+use common::{telex, type_word, vni};
+use data::keys;
+use engine::Engine;
+
+/// Helper to run tests with all quick consonants enabled
+fn quick(method: u8, cases: &[(&str, &str)]) {
+    for (input, expected) in cases {
+        let mut e = Engine::new();
+        e.set_method(method);
+        e.set_quick_start_consonants(true);
+        e.set_quick_end_consonants(true);
+        let result = type_word(&mut e, input);
+        assert_eq!(
+            result, *expected,
+            "[QuickConsonant] '{}' → '{}'",
+            input, result
+        );
+    }
+}
+
+#[test]
+fn quick_start_telex() {
+    quick(
+        0,
+        &[
+            ("fa", "pha"),
+            ("fos", "phó"),
+            ("Fa", "Pha"),
+            ("FA", "PHA"),
+            ("ja", "gia"),
+            ("jaf", "già"),
+            ("jif", "gì"),
+            ("wa", "qua"),
+            ("wees", "quế"),
+            ("wa ", "qua "),
+        ],
+    );
+}
+
+#[test]
+fn quick_start_vni() {
+    quick(
+        1,
+        &[("fa", "pha"), ("fo1", "phó"), ("ja2", "già"), ("wa", "qua")],
+    );
+}
+
+#[test]
+fn quick_end() {
+    quick(
+        0,
+        &[
+            ("ag", "ang"),
+            ("ah", "anh"),
+            ("ak", "ach"),
+            ("tieeg", "tiêng"),
+            ("tieegs", "tiếng"),
+            ("ans", "án"),
+            ("bags", "báng"),
+            ("fag", "phang"),
+        ],
+    );
+    quick(1, &[("ag1", "áng"), ("tie6g1", "tiếng")]);
+}
+
+#[test]
+fn quick_end_only_after_vowel() {
+    // Real digraphs typed in full are unchanged
+    quick(
+        0,
+        &[
+            ("ang", "ang"),
+            ("anh", "anh"),
+            ("ach", "ach"),
+            ("gas", "gá"),
+        ],
+    );
+}
+
+#[test]
+fn quick_consonant_requires_valid_syllable() {
+    // "ea" and "ou" are not Vietnamese vowel patterns
+    quick(0, &[("eag", "eag"), ("youh", "youh")]);
+}
+
+#[test]
+fn quick_consonant_per_key() {
+    let mut e = Engine::new();
+    assert!(e.set_quick_consonant(keys::F, true));
+    assert!(!e.set_quick_consonant(keys::B, true));
+    assert!(e.quick_consonant(keys::F));
+    assert!(!e.quick_consonant(keys::W));
+    assert_eq!(type_word(&mut e, "fa"), "pha");
+
+    // w stays ư in Telex when its shorthand is off
+    let mut e = Engine::new();
+    e.set_quick_consonant(keys::F, true);
+    assert_eq!(type_word(&mut e, "wa"), "ưa");
+
+    let mut e = Engine::new();
+    e.set_quick_end_consonants(true);
+    e.set_quick_consonant(keys::H, false);
+    assert_eq!(type_word(&mut e, "ag"), "ang");
+    assert_eq!(type_word(&mut e, " ah"), " ah");
+}
+
+#[test]
+fn quick_consonant_with_foreign_consonants() {
+    let mut e = Engine::new();
+    e.set_allow_foreign_consonants(true);
+    e.set_quick_start_consonants(true);
+    // Shorthand wins when it forms a valid syllable
+    assert_eq!(type_word(&mut e, "fas"), "phá");
+
+    // Foreign initial kept with shorthand off
+    let mut e = Engine::new();
+    e.set_allow_foreign_consonants(true);
+    assert_eq!(type_word(&mut e, "fas"), "fá");
+}
+
+#[test]
+fn quick_consonant_esc_restores_raw() {
+    let mut e = Engine::new();
+    e.set_quick_start_consonants(true);
+    e.set_quick_end_consonants(true);
+    e.set_esc_restore(true);
+    assert_eq!(type_word(&mut e, "fag\x1b"), "fag");
+}
+
+#[test]
+fn quick_consonant_off_by_default() {
+    telex(&[("ag", "ag"), ("wa", "ưa")]);
+    vni(&[("fa", "fa"), ("ja", "ja")]);
+}
@@ -0,0 +1,115 @@
+//! Quick Telex Tests - Testing quick_telex option
+//!
+//! Doubled consonants expand to digraphs (cc → ch, nn → ng, ...) when the
+//! option is enabled. A third press reverts, like ddd → dd.
+
+mod common;
+use common::{telex, type_word};
+use engine::Engine;
+
+/// Helper to run telex tests with Quick Telex enabled
+fn telex_quick(cases: &[(&str, &str)]) {
+    for (input, expected) in cases {
+        let mut e = Engine::new();
+        e.set_quick_telex(true);
+        let result = type_word(&mut e, input);
+        assert_eq!(
+            result, *expected,
+            "[Telex QuickTelex] '{}' → '{}'",
+            input, result
+        );
+    }
+}
+
+/// Helper to run telex tests with Quick Telex and English auto-restore enabled
+fn telex_quick_auto_restore(cases: &[(&str, &str)]) {
+    for (input, expected) in cases {
+        let mut e = Engine::new();
+        e.set_quick_telex(true);
+        e.set_english_auto_restore(true);
+        let result = type_word(&mut e, input);
+        assert_eq!(
+            result, *expected,
+            "[Telex QuickTelex AutoRestore] '{}' → '{}'",
+            input, result
+        );
+    }
+}
+
+#[test]
+fn quick_telex_initials() {
+    telex_quick(&[
+        ("cc", "ch"),
+        ("gg", "gi"),
+        ("kk", "kh"),
+        ("nn", "ng"),
+        ("pp", "ph"),
+        ("qq", "qu"),
+        ("tt", "th"),
+        ("ccaof", "chào"),
+        ("ggaf", "già"),
+        ("kkoong", "không"),
+        ("nnuwowif", "người"),
+        ("ppos", "phó"),
+        ("qqaan", "quân"),
+        ("ttees", "thế"),
+    ]);
+}
+
+#[test]
+fn quick_telex_finals() {
+    telex_quick(&[
+        ("cacc", "cach"),
+        ("caccs", "cách"),
+        ("tieenn", "tiêng"),
+        ("tieenns", "tiếng"),
+        ("ttuongg", "thuongg"),
+    ]);
+}
+
+#[test]
+fn quick_telex_uppercase() {
+    telex_quick(&[("CC", "CH"), ("Cc", "Ch"), ("TTAAN", "THÂN")]);
+}
+
+#[test]
+fn quick_telex_triple_press_reverts() {
+    telex_quick(&[
+        ("ccc", "cc"),
+        ("nnn", "nn"),
+        ("cccc", "ccc"),
+        ("cacc", "cach"),
+        ("caccc", "cacc"),
+    ]);
+}
+
+#[test]
+fn quick_telex_no_final_ph_th() {
+    // pp/tt are initials only
+    telex_quick(&[("app", "app"), ("att", "att")]);
+}
+
+#[test]
+fn quick_telex_esc_restores_raw() {
+    let mut e = Engine::new();
+    e.set_quick_telex(true);
+    e.set_esc_restore(true);
+    assert_eq!(type_word(&mut e, "ccaof\x1b"), "ccaof");
+}
+
+#[test]
+fn quick_telex_auto_restore_english() {
+    telex_quick_auto_restore(&[
+        ("coffee ", "coffee "),
+        ("apple ", "apple "),
+        ("little ", "little "),
+        ("occur ", "occur "),
+        ("running ", "running "),
+        ("ccaof ", "chào "),
+    ]);
+}
+
+#[test]
+fn quick_telex_off_by_default() {
+    telex(&[("cc", "cc"), ("nn", "nn"), ("tt", "tt")]);
+}
@@ -0,0 +1,133 @@
+//! Recording Tests - file format round trip, and replay reporting the first
+//! key that types differently
+
+mod common;
+use common::key_for;
+use data::keys;
+use engine::{Engine, KeyEvent, KeyOutput, Recording, Shortcut};
+
+/// Record typing `input` with the engine's settings, as a frontend would
+fn record(e: &mut Engine, input: &str) -> Recording {
+    let mut rec = Recording::new(e.config());
+    for c in input.chars() {
+        let (key, shift) = key_for(c);
+        let event = KeyEvent {
+            key,
+            caps: c.is_uppercase(),
+            ctrl: false,
+            shift,
+            ch: (key != keys::DELETE).then_some(c),
+        };
+        let r = e.on_key_with_char(event.key, event.caps, event.ctrl, event.shift, event.ch);
+        rec.push(event, &r);
+    }
+    rec
+}
+
+#[test]
+fn text_format() {
+    let mut e = Engine::new();
+    e.set_english_auto_restore(true);
+    let rec = record(&mut e, "Vieej");
+    let text = rec.to_text();
+    assert!(text.starts_with("# Gõ Nhanh recording\nenabled = true\n"));
+    assert!(text.contains("\nenglish_auto_restore = true\n"));
+    let keys: Vec<&str> = text.lines().skip_while(|l| *l != "[keys]").collect();
+    assert_eq!(
+        keys,
+        [
+            "[keys]",
+            "9 caps \"V\" => none 0 0 \"\"",
+            "34 - \"i\" => none 0 0 \"\"",
+            "14 - \"e\" => none 0 0 \"\"",
+            "14 - \"e\" => send 1 1 \"ê\"",
+            "38 - \"j\" => send 1 1 \"ệ\"",
+        ]
+    );
+}
+
+#[test]
+fn round_trip() {
+    let mut e = Engine::new();
+    e.set_method(1);
+    e.shortcuts_mut().add(Shortcut::new("vn", "Việt\nNam"));
+    let mut rec = record(&mut e, "Vie65t vn <");
+    rec.push(
+        KeyEvent {
+            key: keys::SPACE,
+            ctrl: true,
+            ch: Some('"'),
+            ..KeyEvent::default()
+        },
+        &engine::Result::send(0, &['\\', '\u{1}', '"']),
+    );
+    let parsed = Recording::parse(&rec.to_text()).unwrap();
+    assert_eq!(parsed, rec);
+}
+
+#[test]
+fn parse_errors() {
+    let text = Recording::new(Engine::new().config()).to_text();
+    let err = Recording::parse(&text.replace("[keys]", "")).unwrap_err();
+    assert_eq!(
+        (err.line, err.message.as_str()),
+        (0, "missing `[keys]` section")
+    );
+
+    let err = Recording::parse(&text.replace("method = telex", "method = qwerty")).unwrap_err();
+    assert_eq!(err.message, "unknown method");
+
+    let lines = text.lines().count();
+    for bad in [
+        "0 - \"a\" => none 0 0",
+        "0 alt \"a\" => none 0 0 \"\"",
+        "0 - \"ab\" => none 0 0 \"\"",
+        "0 - \"a\" => jump 0 0 \"\"",
+        "0 - \"a\" => send 0 0 \"\\q\"",
+    ] {
+        let err = Recording::parse(&format!("{}{}\n", text, bad)).unwrap_err();
+        assert_eq!(err.line, lines + 1, "{}", bad);
+    }
+}
+
+#[test]
+fn replay_matches_recording() {
+    let mut e = Engine::new();
+    e.set_english_auto_restore(true);
+    e.set_auto_capitalize(true);
+    let rec = record(&mut e, "xin chaof. text ddi <<s");
+    assert_eq!(rec.replay().unwrap(), None);
+
+    let mut e = Engine::new();
+    e.set_charset(data::charset::Charset::Tcvn3);
+    let rec = record(&mut e, "vieejt nam ");
+    assert_eq!(rec.replay().unwrap(), None);
+}
+
+#[test]
+fn replay_reports_first_divergence() {
+    let mut e = Engine::new();
+    let mut rec = record(&mut e, "text ");
+    // As if recorded with auto-restore on: space restores "text"
+    rec.keys[4].output = KeyOutput {
+        action: 1,
+        backspace: 3,
+        flags: 0,
+        text: "text ".into(),
+    };
+    let d = rec.replay().unwrap().unwrap();
+    assert_eq!(d.index, 4);
+    assert_eq!(d.event.key, keys::SPACE);
+    assert_eq!(d.actual.action, 0);
+    assert_eq!(
+        d.to_string(),
+        "key 4 (49 - \" \"): expected send 3 0 \"text \", got none 0 0 \"\""
+    );
+
+    // Different settings: auto-restore already rewrites "tẽt" at the last t
+    rec.keys[4].output = KeyOutput::default();
+    rec.config.english_auto_restore = true;
+    let d = rec.replay().unwrap().unwrap();
+    assert_eq!((d.index, d.event.ch), (3, Some('t')));
+    assert_eq!(d.actual.text, "text");
+}
@@ -0,0 +1,103 @@
+//! Reverse Conversion Tests
+//!
+//! Vietnamese text → keystrokes, checked by converting back.
+
+use engine::{convert, to_keystrokes, ConvertOptions};
+
+fn options(method: u8, modern_tone: bool) -> ConvertOptions {
+    ConvertOptions {
+        method,
+        modern_tone,
+        ..ConvertOptions::default()
+    }
+}
+
+fn check(method: u8, cases: &[(&str, &str)]) {
+    let opts = options(method, true);
+    for (text, expected) in cases {
+        let keys = to_keystrokes(text, &opts).unwrap();
+        assert_eq!(keys, *expected, "[{}] '{}'", method, text);
+        assert_eq!(convert(&keys, &opts), *text);
+    }
+}
+
+#[test]
+fn telex_keystrokes() {
+    check(
+        0,
+        &[
+            ("Nguyễn Văn Tuấn", "Nguyeenx Vawn Tuaans"),
+            ("người", "nguwowif"),
+            ("ĐƯỢC", "DDUWOWCJ"),
+            ("khuỷu tay, quốc gia!", "khuyur tay, quoocs gia!"),
+            ("xoong", "xooong"),
+            ("Blowing dust", "Blowing dusst"),
+        ],
+    );
+}
+
+#[test]
+fn vni_keystrokes() {
+    check(
+        1,
+        &[
+            ("Nguyễn Văn Tuấn", "Nguye6n4 Va8n Tua6n1"),
+            ("người", "ngu7o7i2"),
+            ("Được", "D9u7o7c5"),
+        ],
+    );
+}
+
+#[test]
+fn viqr_keystrokes() {
+    check(
+        2,
+        &[
+            ("Nguyễn Văn Tuấn", "Nguye^n~ Va(n Tua^n'"),
+            // Punctuation that would mark the vowel is escaped
+            ("kho?", "kho\\?"),
+            ("Đi ra.", "DDi ra\\."),
+        ],
+    );
+}
+
+#[test]
+fn tone_position_follows_style() {
+    assert_eq!(to_keystrokes("hoà", &options(0, true)).unwrap(), "hoaf");
+    assert_eq!(to_keystrokes("hòa", &options(0, false)).unwrap(), "hoaf");
+
+    let err = to_keystrokes("cây hòa", &options(0, true)).unwrap_err();
+    assert_eq!(err.offset, 5);
+    assert_eq!(err.word, "hòa");
+}
+
+#[test]
+fn multiline_text() {
+    let text = "Dòng một.\nDòng hai: \"khoẻ\" (100%)\n";
+    let opts = options(0, true);
+    let keys = to_keystrokes(text, &opts).unwrap();
+    assert_eq!(keys, "Dongf mootj.\nDongf hai: \"khoer\" (100%)\n");
+}
+
+/// Every dictionary word round-trips with the tone style it is written in
+/// (entries like "loã xõa" mix styles, so words are checked one by one)
+#[test]
+fn dictionary_round_trip() {
+    let content = include_str!("data/vietnamese_22k.txt");
+    for method in [0, 1, 2] {
+        let failures: Vec<&str> = content
+            .split_whitespace()
+            .filter(|word| {
+                to_keystrokes(word, &options(method, true)).is_err()
+                    && to_keystrokes(word, &options(method, false)).is_err()
+            })
+            .collect();
+        assert!(
+            failures.is_empty(),
+            "[{}] {} entries failed: {:?}",
+            method,
+            failures.len(),
+            &failures[..failures.len().min(20)]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Surrounding Text Tests - state rebuilt from the text before the cursor,
+//! and keys reported as replace-range edits
+
+mod common;
+use common::key_for;
+use data::keys;
+use engine::{Engine, TextEdit};
+
+/// Type `input` at the end of `doc` with surrounding-text edits
+fn type_at(e: &mut Engine, doc: &str, input: &str) -> String {
+    let mut doc: Vec<char> = doc.chars().collect();
+    for c in input.chars() {
+        let (key, shift) = key_for(c);
+        let edit = e.on_key_surrounding(key, c.is_uppercase(), false, shift, None);
+        apply(&mut doc, &edit);
+        if edit.pass_through {
+            match key {
+                keys::DELETE => {
+                    doc.pop();
+                }
+                keys::ESC => {}
+                _ => doc.push(c),
+            }
+        }
+    }
+    doc.into_iter().collect()
+}
+
+fn apply(doc: &mut Vec<char>, edit: &TextEdit) {
+    let n = doc.len();
+    doc.splice(n - edit.start..n - edit.end, edit.text.chars());
+}
+
+/// Load `before` as surrounding text, then type `input` after it
+fn continue_typing(setup: impl Fn(&mut Engine), before: &str, input: &str) -> String {
+    let mut e = Engine::new();
+    setup(&mut e);
+    e.set_surrounding_text(before, "");
+    type_at(&mut e, before, input)
+}
+
+#[test]
+fn edit_covers_only_changed_chars() {
+    let mut e = Engine::new();
+    e.set_surrounding_text("tiêng", "");
+    let edit = e.on_key_surrounding(keys::S, false, false, false, None);
+    assert_eq!(
+        edit,
+        TextEdit {
+            start: 3,
+            end: 2,
+            text: "ế".into(),
+            pass_through: false,
+        }
+    );
+
+    // Plain letters are left to the application
+    let edit = e.on_key_surrounding(keys::A, false, false, false, None);
+    assert!(edit.is_empty() && edit.pass_through);
+}
+
+#[test]
+fn word_before_cursor_keeps_editing() {
+    // Rebuilt from keystrokes: a consonant continues the word
+    assert_eq!(
+        continue_typing(|_| {}, "Xin chào tiê", "ngs"),
+        "Xin chào tiếng"
+    );
+    assert_eq!(continue_typing(|_| {}, "Việt", "z"), "Viêt");
+    assert_eq!(continue_typing(|_| {}, "vie", "ej"), "việ");
+    assert_eq!(continue_typing(|e| e.set_method(1), "Vie", "65t"), "Việt");
+}
+
+#[test]
+fn cursor_inside_word_starts_fresh() {
+    let mut e = Engine::new();
+    e.set_surrounding_text("vi", "et nam");
+    assert_eq!(e.get_buffer_string(), "");
+    let edit = e.on_key_surrounding(keys::S, false, false, false, None);
+    assert!(edit.is_empty() && edit.pass_through);
+}
+
+#[test]
+fn backspace_after_space_restores_previous_word() {
+    assert_eq!(continue_typing(|_| {}, "chào ban ", "<f"), "chào bàn");
+    assert_eq!(continue_typing(|_| {}, "chào ban  ", "<<f"), "chào bàn");
+    // Any break but space ends the history
+    assert_eq!(continue_typing(|_| {}, "chào ban, ", "<<f"), "chào banf");
+
+    let mut e = Engine::new();
+    e.set_surrounding_text("chào ban ", "");
+    let edit = e.on_key_surrounding(keys::DELETE, false, false, false, None);
+    assert_eq!((edit.start, edit.end, edit.text.as_str()), (1, 0, ""));
+    assert_eq!(e.get_buffer_string(), "ban");
+}
+
+#[test]
+fn sentence_context_capitalizes() {
+    let capitalize = |e: &mut Engine| e.set_auto_capitalize(true);
+    assert_eq!(continue_typing(capitalize, "Xong. ", "ddi"), "Xong. Đi");
+    assert_eq!(continue_typing(capitalize, "Xong.", " ddi"), "Xong. Đi");
+    assert_eq!(continue_typing(capitalize, "Xong\n", "ddi"), "Xong\nĐi");
+    assert_eq!(continue_typing(capitalize, "Xong, ", "ddi"), "Xong, đi");
+    assert_eq!(continue_typing(|_| {}, "Xong. ", "ddi"), "Xong. đi");
+}
+
+#[test]
+fn same_word_keeps_typed_keystrokes() {
+    let mut e = Engine::new();
+    e.set_esc_restore(true);
+    let doc = type_at(&mut e, "", "text");
+    assert_eq!(doc, "tẽt");
+    // Cursor reported where it already is: ESC still knows the keys
+    e.set_surrounding_text("tẽt", "");
+    assert_eq!(type_at(&mut e, &doc, "\x1b"), "text");
+}
+
+/// Every dictionary entry reloaded from the screen stays as it is on space,
+/// with and without auto-restore
+#[test]
+fn dictionary_words_survive_reload() {
+    let corpus = include_str!("data/vietnamese_22k.txt");
+    for auto_restore in [false, true] {
+        let mut e = Engine::new();
+        e.set_english_auto_restore(auto_restore);
+        let words = corpus
+            .lines()
+            .filter(|line| line.chars().all(|c| c == ' ' || c.is_alphabetic()));
+        for line in words {
+            e.set_surrounding_text(line, "");
+            let word = line.rsplit(' ').next().unwrap();
+            assert_eq!(e.get_buffer_string(), word, "{:?}", line);
+            assert_eq!(type_at(&mut e, line, " "), format!("{} ", line));
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Decision Trace Tests - what each key records, and that tracing never
+//! changes the output
+
+mod common;
+use common::type_word;
+use data::keys;
+use engine::{trace, Engine, FlagChange, KeyTrace, Lookup};
+
+fn traced(setup: impl Fn(&mut Engine), input: &str) -> Vec<KeyTrace> {
+    let mut e = Engine::new();
+    setup(&mut e);
+    e.set_trace(true);
+    type_word(&mut e, input);
+    e.trace().collect()
+}
+
+fn flag<'a>(k: &'a KeyTrace, name: &str) -> Option<&'a FlagChange> {
+    k.flags.iter().find(|f| f.name == name)
+}
+
+#[test]
+fn off_by_default() {
+    let mut e = Engine::new();
+    type_word(&mut e, "vieejt ");
+    assert_eq!(e.trace().count(), 0);
+    assert_eq!(e.trace_json(), "[]");
+}
+
+#[test]
+fn records_steps_flags_and_buffer() {
+    let t = traced(|_| {}, "vieejt");
+    assert_eq!(t.len(), 6);
+    let steps: Vec<&[&str]> = t.iter().map(|k| k.steps.as_slice()).collect();
+    assert_eq!(
+        steps,
+        [
+            &["letter"][..],
+            &["letter"],
+            &["letter"],
+            &["tone"],
+            &["mark"],
+            &["letter"]
+        ]
+    );
+
+    let mark = &t[4];
+    assert_eq!(mark.key, keys::J);
+    assert_eq!((mark.buffer.as_str(), mark.raw.as_str()), ("việ", "vieej"));
+    assert_eq!((mark.backspace, mark.output.as_str()), (1, "ệ"));
+    assert_eq!(mark.validation.as_deref(), Some("Valid"));
+    assert_eq!(
+        flag(&t[3], "had_any_transform").map(|f| (f.before.as_str(), f.after.as_str())),
+        Some(("false", "true"))
+    );
+    // Only changes are listed
+    assert!(flag(mark, "had_any_transform").is_none());
+}
+
+#[test]
+fn records_reverts() {
+    let t = traced(|_| {}, "ass");
+    assert!(t[2].steps.contains(&"revert_mark"));
+    assert_eq!(
+        flag(&t[2], "had_mark_revert").map(|f| f.after.as_str()),
+        Some("true")
+    );
+    assert_eq!(t[2].buffer, "as");
+}
+
+#[test]
+fn records_auto_restore_and_lookups() {
+    let t = traced(|e| e.set_english_auto_restore(true), "text ");
+    let restore = &t[3];
+    assert_eq!(restore.steps, ["letter", "auto_restore_mid_word"]);
+    assert!(restore.lookups.contains(&Lookup {
+        list: "english",
+        word: "text".into(),
+        hit: true,
+    }));
+    assert_eq!(restore.buffer, "text");
+
+    let t = traced(|e| e.set_english_auto_restore(true), "nurses ");
+    let space = t.last().unwrap();
+    assert_eq!(space.steps, ["space", "auto_restore"]);
+    assert_eq!(space.output, "nurses ");
+    assert!(space.lookups.iter().any(|l| l.list == "telex_doubles"));
+}
+
+#[test]
+fn nested_keys_record_once() {
+    // on_key_with_char goes through the key path again for the mapped key
+    let mut e = Engine::new();
+    e.set_trace(true);
+    e.on_key_with_char(keys::A, false, false, false, Some('a'));
+    e.on_key_with_char(keys::S, false, false, false, Some('s'));
+    let t: Vec<KeyTrace> = e.trace().collect();
+    assert_eq!(t.len(), 2);
+    assert_eq!(t[1].ch, Some('s'));
+    assert_eq!(t[1].steps, ["mark"]);
+}
+
+#[test]
+fn keeps_last_keys_only() {
+    let mut e = Engine::new();
+    e.set_trace(true);
+    let input = "vieejt nam ".repeat(trace::CAPACITY / 10);
+    type_word(&mut e, &input);
+    assert_eq!(e.trace().count(), trace::CAPACITY);
+    assert_eq!(e.trace().last().unwrap().key, keys::SPACE);
+
+    e.clear_trace();
+    assert_eq!(e.trace().count(), 0);
+    type_word(&mut e, "a");
+    assert_eq!(e.trace().count(), 1);
+    e.set_trace(false);
+    type_word(&mut e, "a");
+    assert_eq!(e.trace().count(), 0);
+}
+
+#[test]
+fn json_dump() {
+    let mut e = Engine::new();
+    e.set_trace(true);
+    type_word(&mut e, "as");
+    let json = e.trace_json();
+    assert!(json.starts_with("[\n{\"key\":0,\"caps\":false"));
+    assert!(json.ends_with("}\n]"));
+    assert_eq!(json.lines().count(), 4);
+    assert!(json.contains("\"steps\":[\"mark\"]"));
+
+    let key = KeyTrace {
+        ch: Some('"'),
+        output: "a\\b\n\u{1}".into(),
+        ..KeyTrace::default()
+    };
+    let json = key.to_json();
+    assert!(json.contains("\"ch\":\"\\\"\""));
+    assert!(json.contains("\"output\":\"a\\\\b\\n\\u0001\""));
+    assert!(json.contains("\"validation\":null"));
+}
+
+/// Tracing only observes: same text for the whole telex corpus
+#[test]
+fn tracing_keeps_output() {
+    let corpus = include_str!("data/vietnamese_telex_pairs.txt");
+    let inputs: Vec<&str> = corpus
+        .lines()
+        .filter_map(|line| line.split('\t').next())
+        .collect();
+    for chunk in inputs.chunks(500) {
+        let text = chunk.join(" ") + " ";
+        let mut plain = Engine::new();
+        let mut traced = Engine::new();
+        plain.set_english_auto_restore(true);
+        traced.set_english_auto_restore(true);
+        traced.set_trace(true);
+        assert_eq!(type_word(&mut traced, &text), type_word(&mut plain, &text));
+    }
+}
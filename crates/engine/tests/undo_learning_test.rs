@@ -0,0 +1,92 @@
+//! Undo Learning Tests - backspace into a committed word and going back to
+//! the form auto-restore didn't pick teaches a per-word exception
+
+mod common;
+use common::type_word;
+use engine::{Engine, Language, UserLexicon};
+
+fn engine_auto_restore() -> Engine {
+    let mut e = Engine::new();
+    e.set_method(0);
+    e.set_english_auto_restore(true);
+    e
+}
+
+fn exceptions(e: &Engine) -> Vec<(String, Language)> {
+    e.lexicon()
+        .exceptions()
+        .into_iter()
+        .map(|w| (w.word, w.language))
+        .collect()
+}
+
+#[test]
+fn undo_restore_keeps_vietnamese() {
+    let mut e = engine_auto_restore();
+    // Backspace into the restored word, retype its end as Vietnamese
+    assert_eq!(type_word(&mut e, "text <<<xt "), "tẽt ");
+    assert_eq!(exceptions(&e), [("tẽt".to_string(), Language::Vietnamese)]);
+
+    assert_eq!(type_word(&mut e, " text "), " tẽt ");
+    assert_eq!(type_word(&mut e, "Text "), "Tẽt ");
+}
+
+#[test]
+fn undo_transform_restores_english() {
+    let mut e = engine_auto_restore();
+    // Backspace brings "dã" back, x reverts the mark
+    assert_eq!(type_word(&mut e, "dax <x "), "dax ");
+    assert_eq!(exceptions(&e), [("dax".to_string(), Language::English)]);
+    assert_eq!(type_word(&mut e, "dax "), "dax ");
+}
+
+#[test]
+fn esc_keeps_restoring_raw_keys() {
+    let mut e = engine_auto_restore();
+    e.set_esc_restore(true);
+    assert_eq!(type_word(&mut e, "text <\x1b"), "text");
+    assert_eq!(type_word(&mut e, " vieejt\x1b"), " vieejt");
+    assert!(exceptions(&e).is_empty());
+}
+
+#[test]
+fn plain_backspace_learns_nothing() {
+    let mut e = engine_auto_restore();
+    // Same form committed again, or a different word typed instead
+    assert_eq!(type_word(&mut e, "text < "), "text ");
+    assert_eq!(type_word(&mut e, "dax <<<<vieejt "), "việt ");
+    assert_eq!(type_word(&mut e, "dax < tex"), "dã tẽ");
+    assert!(exceptions(&e).is_empty());
+    // Only the newest word's decision can be undone
+    let mut e = engine_auto_restore();
+    type_word(&mut e, "text dax  <<");
+    assert!(exceptions(&e).is_empty());
+}
+
+#[test]
+fn list_and_clear_exceptions() {
+    let mut e = engine_auto_restore();
+    e.lexicon_mut().pin("kubectl", Language::English);
+    type_word(&mut e, "dax <x ");
+    type_word(&mut e, "text <<<xt ");
+    assert_eq!(e.lexicon().exceptions().len(), 2);
+
+    assert_eq!(e.lexicon_mut().clear_exceptions(), 2);
+    assert!(e.lexicon().exceptions().is_empty());
+    assert_eq!(e.lexicon().language("kubectl"), Some(Language::English));
+    assert_eq!(type_word(&mut e, " dax "), " dã ");
+}
+
+#[test]
+fn exceptions_persist() {
+    let mut e = engine_auto_restore();
+    type_word(&mut e, "text <<<xt ");
+    let path = std::env::temp_dir().join(format!("gonhanh-undo-{}.txt", std::process::id()));
+    e.lexicon().save(&path).unwrap();
+
+    let mut e = engine_auto_restore();
+    *e.lexicon_mut() = UserLexicon::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(type_word(&mut e, "text "), "tẽt ");
+    assert_eq!(e.lexicon().exceptions().len(), 1);
+}
@@ -0,0 +1,161 @@
+//! VIQR Input Method Tests
+//!
+//! VIQR uses punctuation symbols as diacritics:
+//! ' ` ? ~ . for marks, ^ + ( for tones, dd for đ, \ to escape.
+
+mod common;
+use common::{telex, type_word, viqr, vni};
+use engine::{Engine, InputMethod, Shortcut};
+
+// MARKS & TONES
+
+const VIQR_MARKS: &[(&str, &str)] = &[
+    ("a'", "á"),
+    ("a`", "à"),
+    ("a?", "ả"),
+    ("a~", "ã"),
+    ("a.", "ạ"),
+    // Changing mark replaces the previous one
+    ("a'`", "à"),
+];
+
+const VIQR_TONES: &[(&str, &str)] = &[
+    ("a^", "â"),
+    ("e^", "ê"),
+    ("o^", "ô"),
+    ("o+", "ơ"),
+    ("u+", "ư"),
+    ("a(", "ă"),
+    ("dd", "đ"),
+    ("ddi", "đi"),
+];
+
+#[test]
+fn viqr_marks() {
+    viqr(VIQR_MARKS);
+}
+
+#[test]
+fn viqr_tones() {
+    viqr(VIQR_TONES);
+}
+
+// WORDS & SENTENCES
+
+const VIQR_WORDS: &[(&str, &str)] = &[
+    ("Vie^.t Nam", "Việt Nam"),
+    ("tie^'ng", "tiếng"),
+    ("mo^.t", "một"),
+    ("Nguye^~n Va(n Tua^'n", "Nguyễn Văn Tuấn"),
+    ("ddu+o+`ng", "đường"),
+    ("ddu+o+ng`", "đường"),
+    ("thu+o+ng", "thương"),
+    ("na(m", "năm"),
+    ("cu+'", "cứ"),
+    ("ca'c ba.n", "các bạn"),
+    // Modern tone placement by default
+    ("hoa`", "hoà"),
+    ("khoe?", "khoẻ"),
+];
+
+#[test]
+fn viqr_words() {
+    viqr(VIQR_WORDS);
+}
+
+// REVERT & ESCAPE
+
+const VIQR_LITERAL: &[(&str, &str)] = &[
+    // Same symbol twice: revert and type symbol
+    ("a''", "a'"),
+    ("a^^", "a^"),
+    ("o++", "o+"),
+    ("ddd", "dd"),
+    // Only right after the mark: later the symbol is punctuation
+    ("vie^.t.", "việt."),
+    ("ca'n'", "cán'"),
+    // Backslash escapes the next symbol
+    ("a\\'", "a'"),
+    ("ca'i\\?", "cái?"),
+    // Nothing to modify: symbol is plain punctuation
+    ("hello.", "hello."),
+    ("'a", "'a"),
+    // Unshifted digits are not modifiers
+    ("a6", "a6"),
+    ("a9", "a9"),
+];
+
+#[test]
+fn viqr_literal_symbols() {
+    viqr(VIQR_LITERAL);
+}
+
+#[test]
+fn viqr_symbols_are_punctuation_in_telex() {
+    telex(&[("a'", "a'"), ("o+", "o+"), ("a^", "a^")]);
+}
+
+// ESC RESTORE / AUTO-RESTORE / SHORTCUTS
+
+#[test]
+fn viqr_esc_restore() {
+    let mut e = Engine::new();
+    e.set_method(2);
+    e.set_esc_restore(true);
+    assert_eq!(type_word(&mut e, "vie^.t\x1b"), "vie^.t");
+}
+
+#[test]
+fn viqr_english_auto_restore() {
+    let mut e = Engine::new();
+    e.set_method(2);
+    e.set_english_auto_restore(true);
+    assert_eq!(type_word(&mut e, "add vie^.t "), "add việt ");
+    // Apostrophe placed a mark: the invalid syllable goes back to English
+    assert_eq!(type_word(&mut e, "don't it's "), "don't it's ");
+
+    let mut e = Engine::new();
+    e.set_method(2);
+    assert_eq!(type_word(&mut e, "don't "), "dónt ");
+}
+
+#[test]
+fn viqr_scoped_shortcut() {
+    let mut e = Engine::new();
+    e.set_method(2);
+    e.shortcuts_mut()
+        .add(Shortcut::new("vn", "Việt Nam").for_method(InputMethod::Viqr));
+    assert_eq!(type_word(&mut e, "vn "), "Việt Nam ");
+
+    // Not applied in Telex
+    let mut e = Engine::new();
+    e.shortcuts_mut()
+        .add(Shortcut::new("vn", "Việt Nam").for_method(InputMethod::Viqr));
+    assert_eq!(type_word(&mut e, "vn "), "vn ");
+}
+
+// SYMBOL KEYS IN OTHER METHODS
+
+// ' ` . are plain punctuation outside VIQR: they end the word, and ESC or
+// auto-restore never brings them back as part of it
+#[test]
+fn telex_vni_symbol_keys() {
+    telex(&[
+        ("vieetj. ", "việt. "),
+        ("as'", "á'"),
+        ("tieengs`", "tiếng`"),
+        ("a.b ", "a.b "),
+    ]);
+    vni(&[("vie65t. ", "việt. "), ("a1'", "á'"), ("a2`", "à`")]);
+
+    for method in [0, 1] {
+        let mut e = Engine::new();
+        e.set_method(method);
+        e.set_esc_restore(true);
+        e.set_english_auto_restore(true);
+        assert_eq!(type_word(&mut e, "don't it's "), "don't it's ");
+        assert_eq!(type_word(&mut e, "hello.world "), "hello.world ");
+        assert_eq!(type_word(&mut e, "vie'\x1b"), "vie'");
+        assert_eq!(type_word(&mut e, "ca'f\x1b"), "ca'f");
+    }
+}
@@ -0,0 +1,87 @@
+//! Per-keystroke cost of the key result APIs
+//!
+//! Run with `cargo bench -p ffi`. Types the same Telex sentence through
+//! `ime_key_ext` (boxed `Result`, freed with `ime_free`), `ime_key_into`
+//! (caller-owned `Result`) and `ime_key_utf8` (caller-owned UTF-8 text),
+//! and prints the average time per key. Engine work dominates, so the
+//! three come out about the same; the variants exist for ownership and
+//! encoding, not speed.
+
+use data::keys;
+use engine::Result;
+use gonhanh_core::*;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// "Tieesng Vieejt cos dduwowcj gox nhanh khoong"
+const SENTENCE: &str = "tieesng vieejt cos dduwowcj gox nhanh khoong ";
+
+const ROUNDS: u32 = 20_000;
+
+fn key(c: char) -> u16 {
+    match c {
+        'a' => keys::A,
+        'c' => keys::C,
+        'd' => keys::D,
+        'e' => keys::E,
+        'g' => keys::G,
+        'h' => keys::H,
+        'i' => keys::I,
+        'j' => keys::J,
+        'k' => keys::K,
+        'n' => keys::N,
+        'o' => keys::O,
+        's' => keys::S,
+        't' => keys::T,
+        'u' => keys::U,
+        'v' => keys::V,
+        'w' => keys::W,
+        'x' => keys::X,
+        ' ' => keys::SPACE,
+        _ => unreachable!("no key for {:?}", c),
+    }
+}
+
+fn run(name: &str, keys: &[u16], mut press: impl FnMut(u16)) {
+    ime_init();
+    ime_method(0);
+    // Warm up
+    for &k in keys {
+        press(k);
+    }
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for &k in keys {
+            press(k);
+        }
+    }
+    let elapsed = start.elapsed();
+    let per_key = elapsed / (ROUNDS * keys.len() as u32);
+    println!("{:<28} {:>8.1} ns/key", name, as_ns(per_key));
+}
+
+fn as_ns(d: Duration) -> f64 {
+    d.as_secs_f64() * 1e9
+}
+
+fn main() {
+    let keys: Vec<u16> = SENTENCE.chars().map(key).collect();
+
+    run("ime_key_ext + ime_free", &keys, |k| {
+        let r = ime_key_ext(k, false, false, false);
+        black_box(unsafe { &*r }.count);
+        unsafe { ime_free(r) };
+    });
+
+    let mut out = Result::none();
+    run("ime_key_into", &keys, |k| {
+        unsafe { ime_key_into(k, false, false, false, 0, &mut out) };
+        black_box(out.count);
+    });
+
+    let mut out = std::mem::MaybeUninit::<Utf8Result>::uninit();
+    run("ime_key_utf8", &keys, |k| {
+        unsafe { ime_key_utf8(k, false, false, false, 0, out.as_mut_ptr()) };
+        black_box(unsafe { out.assume_init_ref() }.len);
+    });
+}
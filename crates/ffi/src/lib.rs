@@ -8,7 +8,7 @@
 //! ```c
 //! // Initialize once at app start
 //! ime_init();
-//! ime_method(0);  // 0=Telex, 1=VNI
+//! ime_method(0);  // 0=Telex, 1=VNI, 2=VIQR
 //!
 //! // Process each keystroke
 //! ImeResult* r = ime_key(keycode, is_shift, is_ctrl);
@@ -19,9 +19,17 @@
 //!
 //! // Clean up on word boundary
 //! ime_clear();
+//!
+//! // Or into caller-owned memory, nothing to free
+//! ImeResult out;
+//! ime_key_into(keycode, is_shift, is_ctrl, false, 0, &out);
 //! ```
+//!
+//! Frontends that keep state per input context create one engine each with
+//! `ime_engine_new` and call the `ime_engine_*` variants, which take the
+//! handle as first argument. The `ime_*` functions use a global engine.
 
-use engine::{Engine, Result};
+use engine::{Engine, KeyEvent, Recording, Result};
 use std::sync::Mutex;
 
 // Re-export updater FFI functions so they're included in the static library.
@@ -31,11 +39,92 @@ pub use updater::{version_compare, version_has_update};
 // Global engine instance (thread-safe via Mutex)
 static ENGINE: Mutex<Option<Engine>> = Mutex::new(None);
 
+// App version stamped into settings files, see `ime_set_app_version`
+static APP_VERSION: Mutex<Option<updater::Version>> = Mutex::new(None);
+
 /// Lock the engine mutex, recovering from poisoned state if needed (for tests)
 fn lock_engine() -> std::sync::MutexGuard<'static, Option<Engine>> {
     ENGINE.lock().unwrap_or_else(|e| e.into_inner())
 }
 
+// Keys typed on the global engine, see `ime_record`
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+/// Engine instance owned by the caller, see `ime_engine_new`.
+///
+/// Opaque to C. Each handle has its own buffer, word history, settings and
+/// shortcuts. Calls on one handle are serialized by its own mutex, so
+/// different handles never block each other.
+pub struct ImeEngine {
+    engine: Mutex<Engine>,
+    /// Keys typed on this handle, see `ime_engine_record`
+    recording: Mutex<Option<Recording>>,
+}
+
+/// Run `f` on the global engine, None if not initialized
+fn with_global<T>(f: impl FnOnce(&mut Engine) -> T) -> Option<T> {
+    lock_engine().as_mut().map(f)
+}
+
+/// Run `f` on the engine behind `handle`, None for a null handle
+///
+/// # Safety
+/// `handle` must be null or a live pointer from `ime_engine_new`.
+unsafe fn with_handle<T>(handle: *mut ImeEngine, f: impl FnOnce(&mut Engine) -> T) -> Option<T> {
+    let handle = handle.as_ref()?;
+    let mut engine = handle.engine.lock().unwrap_or_else(|e| e.into_inner());
+    Some(f(&mut engine))
+}
+
+/// Run a key through `on_key_with_char`, adding it to `recording` if one
+/// is running. Lock the engine first: `ime_record` takes both in that order.
+fn recorded_key(e: &mut Engine, recording: &Mutex<Option<Recording>>, event: KeyEvent) -> Result {
+    let r = e.on_key_with_char(event.key, event.caps, event.ctrl, event.shift, event.ch);
+    if let Some(rec) = recording.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        rec.push(event, &r);
+    }
+    r
+}
+
+/// Key event passed to an `ime_key*` function
+fn key_event(key: u16, caps: bool, ctrl: bool, shift: bool, ch: Option<char>) -> KeyEvent {
+    KeyEvent {
+        key,
+        caps,
+        ctrl,
+        shift,
+        ch,
+    }
+}
+
+/// Run a key on the global engine, recorded while `ime_record` is on
+fn global_key(event: KeyEvent) -> Option<Result> {
+    with_global(|e| recorded_key(e, &RECORDING, event))
+}
+
+/// Run a key on the engine behind `handle`, recorded while
+/// `ime_engine_record` is on
+///
+/// # Safety
+/// `handle` must be null or a live pointer from `ime_engine_new`.
+unsafe fn handle_key(handle: *mut ImeEngine, event: KeyEvent) -> Option<Result> {
+    let recording = &handle.as_ref()?.recording;
+    with_handle(handle, |e| recorded_key(e, recording, event))
+}
+
+/// Hand a key result to the caller, null if there is none
+fn into_result(r: Option<Result>) -> *mut Result {
+    r.map_or(std::ptr::null_mut(), |r| Box::into_raw(Box::new(r)))
+}
+
+/// Read a C string argument, None for null or invalid UTF-8
+unsafe fn c_str<'a>(s: *const std::os::raw::c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    std::ffi::CStr::from_ptr(s).to_str().ok()
+}
+
 // FFI Interface
 
 /// Initialize the IME engine.
@@ -73,13 +162,7 @@ pub extern "C" fn ime_init() {
 /// use `ime_key_ext` with the shift parameter.
 #[no_mangle]
 pub extern "C" fn ime_key(key: u16, caps: bool, ctrl: bool) -> *mut Result {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        let r = e.on_key(key, caps, ctrl);
-        Box::into_raw(Box::new(r))
-    } else {
-        std::ptr::null_mut()
-    }
+    into_result(global_key(key_event(key, caps, ctrl, false, None)))
 }
 
 /// Process a key event with extended parameters.
@@ -102,13 +185,7 @@ pub extern "C" fn ime_key(key: u16, caps: bool, ctrl: bool) -> *mut Result {
 /// - etc.
 #[no_mangle]
 pub extern "C" fn ime_key_ext(key: u16, caps: bool, ctrl: bool, shift: bool) -> *mut Result {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        let r = e.on_key_ext(key, caps, ctrl, shift);
-        Box::into_raw(Box::new(r))
-    } else {
-        std::ptr::null_mut()
-    }
+    into_result(global_key(key_event(key, caps, ctrl, shift, None)))
 }
 
 /// Process a key event with the actual Unicode character.
@@ -141,32 +218,150 @@ pub extern "C" fn ime_key_with_char(
     shift: bool,
     char_code: u32,
 ) -> *mut Result {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        let ch = if char_code > 0 {
-            char::from_u32(char_code)
-        } else {
-            None
-        };
-        let r = e.on_key_with_char(key, caps, ctrl, shift, ch);
-        Box::into_raw(Box::new(r))
+    let ch = key_char(char_code);
+    into_result(global_key(key_event(key, caps, ctrl, shift, ch)))
+}
+
+/// Character passed to `ime_key_with_char`, None for 0 or invalid codes
+fn key_char(char_code: u32) -> Option<char> {
+    if char_code > 0 {
+        char::from_u32(char_code)
     } else {
-        std::ptr::null_mut()
+        None
+    }
+}
+
+/// Process a key event into a caller-owned `Result`.
+///
+/// Same as `ime_key_with_char` without allocating: `out` can be reused for
+/// every keystroke and there is nothing to free.
+///
+/// # Arguments
+/// * `key`, `caps`, `ctrl`, `shift` - as for `ime_key_ext`
+/// * `char_code` - as for `ime_key_with_char`, 0 to derive it from `key`
+/// * `out` - Result to overwrite
+///
+/// # Returns
+/// false if `out` is null or engine not initialized (`out` then holds action=0).
+///
+/// # Safety
+/// `out` must be null or point to writable memory for one `Result`.
+#[no_mangle]
+pub unsafe extern "C" fn ime_key_into(
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+    out: *mut Result,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let ch = key_char(char_code);
+    write_result(global_key(key_event(key, caps, ctrl, shift, ch)), out)
+}
+
+/// Process a key event into a caller-owned `Utf8Result`.
+///
+/// Like `ime_key_into` with the text already encoded, so frontends working
+/// with byte strings need no codepoint conversion. With a legacy charset
+/// (see `ime_charset`) `text` holds the charset bytes instead of UTF-8.
+///
+/// # Returns
+/// false if `out` is null or engine not initialized (`out` then holds action=0).
+///
+/// # Safety
+/// `out` must be null or point to writable memory for one `Utf8Result`.
+#[no_mangle]
+pub unsafe extern "C" fn ime_key_utf8(
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+    out: *mut Utf8Result,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let ch = key_char(char_code);
+    write_utf8(
+        with_global(|e| {
+            let r = recorded_key(e, &RECORDING, key_event(key, caps, ctrl, shift, ch));
+            (r, e.charset())
+        }),
+        out,
+    )
+}
+
+/// Bytes of text a `Utf8Result` holds: 4 per `Result` char
+pub const UTF8_MAX: usize = engine::MAX * 4;
+
+/// Key result with its text encoded, see `ime_key_utf8`
+#[repr(C)]
+pub struct Utf8Result {
+    /// Text to insert, NUL-terminated
+    pub text: [u8; UTF8_MAX + 1],
+    /// Same as `Result::action`
+    pub action: u8,
+    /// Characters to delete, same as `Result::backspace`
+    pub backspace: u8,
+    /// Same as `Result::flags`
+    pub flags: u8,
+    /// Bytes in `text` before the NUL
+    pub len: u16,
+}
+
+impl Utf8Result {
+    fn new(r: &Result, charset: data::charset::Charset) -> Self {
+        use data::charset::Charset;
+        let unicode = matches!(charset, Charset::Unicode | Charset::UnicodeNfd);
+        let mut text = [0; UTF8_MAX + 1];
+        let mut len = 0;
+        for &c in &r.chars[..r.count as usize] {
+            if unicode {
+                let c = char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER);
+                len += c.encode_utf8(&mut text[len..]).len();
+            } else {
+                text[len] = c as u8;
+                len += 1;
+            }
+        }
+        Self {
+            text,
+            action: r.action,
+            backspace: r.backspace,
+            flags: r.flags,
+            len: len as u16,
+        }
     }
 }
 
+/// Write a key result to caller memory, action=0 if there is none
+unsafe fn write_result(r: Option<Result>, out: *mut Result) -> bool {
+    let found = r.is_some();
+    out.write(r.unwrap_or_else(Result::none));
+    found
+}
+
+/// Encode a key result into caller memory, action=0 if there is none
+unsafe fn write_utf8(r: Option<(Result, data::charset::Charset)>, out: *mut Utf8Result) -> bool {
+    let found = r.is_some();
+    let (r, charset) = r.unwrap_or_else(|| (Result::none(), data::charset::Charset::Unicode));
+    out.write(Utf8Result::new(&r, charset));
+    found
+}
+
 /// Set the input method.
 ///
 /// # Arguments
-/// * `method` - 0 for Telex, 1 for VNI
+/// * `method` - 0 for Telex, 1 for VNI, 2 for VIQR
 ///
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_method(method: u8) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_method(method);
-    }
+    with_global(|e| e.set_method(method));
 }
 
 /// Enable or disable the engine.
@@ -175,10 +370,7 @@ pub extern "C" fn ime_method(method: u8) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_enabled(enabled: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_enabled(enabled);
-    }
+    with_global(|e| e.set_enabled(enabled));
 }
 
 /// Set whether to skip w→ư shortcut in Telex mode.
@@ -188,10 +380,7 @@ pub extern "C" fn ime_enabled(enabled: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_skip_w_shortcut(skip: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_skip_w_shortcut(skip);
-    }
+    with_global(|e| e.set_skip_w_shortcut(skip));
 }
 
 /// Set whether bracket shortcuts are enabled: ] → ư, [ → ơ (Issue #159)
@@ -200,10 +389,7 @@ pub extern "C" fn ime_skip_w_shortcut(skip: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_bracket_shortcut(enabled: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_bracket_shortcut(enabled);
-    }
+    with_global(|e| e.set_bracket_shortcut(enabled));
 }
 
 /// Set whether ESC key restores raw ASCII input.
@@ -213,10 +399,7 @@ pub extern "C" fn ime_bracket_shortcut(enabled: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_esc_restore(enabled: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_esc_restore(enabled);
-    }
+    with_global(|e| e.set_esc_restore(enabled));
 }
 
 /// Set whether to enable free tone placement (skip validation).
@@ -227,10 +410,7 @@ pub extern "C" fn ime_esc_restore(enabled: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_free_tone(enabled: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_free_tone(enabled);
-    }
+    with_global(|e| e.set_free_tone(enabled));
 }
 
 /// Set whether to use modern orthography for tone placement.
@@ -240,10 +420,7 @@ pub extern "C" fn ime_free_tone(enabled: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_modern(modern: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_modern_tone(modern);
-    }
+    with_global(|e| e.set_modern_tone(modern));
 }
 
 /// Enable/disable English auto-restore (experimental feature).
@@ -254,10 +431,7 @@ pub extern "C" fn ime_modern(modern: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_english_auto_restore(enabled: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_english_auto_restore(enabled);
-    }
+    with_global(|e| e.set_english_auto_restore(enabled));
 }
 
 /// Enable/disable auto-capitalize after sentence-ending punctuation.
@@ -268,10 +442,7 @@ pub extern "C" fn ime_english_auto_restore(enabled: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_auto_capitalize(enabled: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_auto_capitalize(enabled);
-    }
+    with_global(|e| e.set_auto_capitalize(enabled));
 }
 
 /// Enable/disable foreign consonants (z, w, j, f) as valid initial consonants.
@@ -282,10 +453,68 @@ pub extern "C" fn ime_auto_capitalize(enabled: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_allow_foreign_consonants(enabled: bool) {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.set_allow_foreign_consonants(enabled);
-    }
+    with_global(|e| e.set_allow_foreign_consonants(enabled));
+}
+
+/// Enable/disable Quick Telex (Telex only).
+///
+/// When `enabled` is true, doubled consonants expand to digraphs:
+/// cc → ch, gg → gi, kk → kh, nn → ng, pp → ph, qq → qu, tt → th.
+/// A third press reverts (ccc → cc). Default: false.
+/// No-op if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_quick_telex(enabled: bool) {
+    with_global(|e| e.set_quick_telex(enabled));
+}
+
+/// Enable/disable quick initial consonants: f → ph, j → gi, w → qu.
+///
+/// Expands only when the resulting syllable is valid Vietnamese ("fa" → "pha").
+/// In Telex, a leading w stays a letter instead of becoming ư. Default: false.
+/// No-op if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_quick_start_consonant(enabled: bool) {
+    with_global(|e| e.set_quick_start_consonants(enabled));
+}
+
+/// Enable/disable quick final consonants: g → ng, h → nh, k → ch.
+///
+/// Expands only after a vowel and when the resulting syllable is valid
+/// Vietnamese ("ag" → "ang"). Default: false.
+/// No-op if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_quick_end_consonant(enabled: bool) {
+    with_global(|e| e.set_quick_end_consonants(enabled));
+}
+
+/// Enable/disable a single quick consonant shorthand.
+///
+/// # Arguments
+/// * `key` - macOS keycode of the shorthand (f, j, w, g, h or k)
+/// * `enabled` - true to expand it
+///
+/// # Returns
+/// true if `key` is a shorthand, false otherwise or if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_quick_consonant(key: u16, enabled: bool) -> bool {
+    with_global(|e| e.set_quick_consonant(key, enabled)).unwrap_or(false)
+}
+
+/// Set the output character set.
+///
+/// # Arguments
+/// * `charset` - 0=Unicode, 1=TCVN3 (ABC), 2=VNI-Windows, 3=VISCII, 4=CP1258,
+///   5=Unicode NFD (base letter + combining marks)
+///
+/// Legacy charsets return one `chars` entry per byte and count `backspace`
+/// in bytes; NFD counts codepoints. Unknown ids are ignored; VPS is not
+/// supported. No-op if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_charset(charset: u8) {
+    let Some(charset) = data::charset::Charset::from_id(charset) else {
+        return;
+    };
+    with_global(|e| e.set_charset(charset));
 }
 
 /// Clear the input buffer.
@@ -295,10 +524,7 @@ pub extern "C" fn ime_allow_foreign_consonants(enabled: bool) {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_clear() {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.clear();
-    }
+    with_global(|e| e.clear());
 }
 
 /// Clear everything including word history.
@@ -308,10 +534,7 @@ pub extern "C" fn ime_clear() {
 /// No-op if engine not initialized.
 #[no_mangle]
 pub extern "C" fn ime_clear_all() {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.clear_all();
-    }
+    with_global(|e| e.clear_all());
 }
 
 /// Get the full composed buffer as UTF-32 codepoints.
@@ -330,20 +553,24 @@ pub extern "C" fn ime_clear_all() {
 /// `out` must point to valid memory of at least `max_len * sizeof(u32)` bytes.
 #[no_mangle]
 pub unsafe extern "C" fn ime_get_buffer(out: *mut u32, max_len: i64) -> i64 {
+    copy_buffer(with_global(|e| e.get_buffer_string()), out, max_len)
+}
+
+/// Copy a buffer string to a caller-provided UTF-32 array
+///
+/// # Safety
+/// See `ime_get_buffer`.
+unsafe fn copy_buffer(buffer: Option<String>, out: *mut u32, max_len: i64) -> i64 {
+    let Some(full) = buffer else {
+        return 0;
+    };
     if out.is_null() || max_len <= 0 {
         return 0;
     }
-
-    let guard = lock_engine();
-    if let Some(ref e) = *guard {
-        let full = e.get_buffer_string();
-        let utf32: Vec<u32> = full.chars().map(|c| c as u32).collect();
-        let len = utf32.len().min(max_len as usize);
-        std::ptr::copy_nonoverlapping(utf32.as_ptr(), out, len);
-        len as i64
-    } else {
-        0
-    }
+    let utf32: Vec<u32> = full.chars().map(|c| c as u32).collect();
+    let len = utf32.len().min(max_len as usize);
+    std::ptr::copy_nonoverlapping(utf32.as_ptr(), out, len);
+    len as i64
 }
 
 /// Free a result pointer returned by `ime_key`.
@@ -361,6 +588,20 @@ pub unsafe extern "C" fn ime_free(r: *mut Result) {
 
 // Shortcut FFI
 
+/// Add a shortcut, picking its trigger type from the trigger text
+fn add_shortcut(e: &mut Engine, trigger: &str, replacement: &str) {
+    // Auto-detect shortcut type:
+    // - If trigger contains only non-letter chars (like "->", "=>"), use immediate trigger
+    // - Otherwise use word boundary trigger (traditional abbreviations like "vn" → "Việt Nam")
+    let is_symbol_trigger = trigger.chars().all(|c| !c.is_alphabetic());
+    let shortcut = if is_symbol_trigger {
+        engine::Shortcut::immediate(trigger, replacement)
+    } else {
+        engine::Shortcut::new(trigger, replacement)
+    };
+    e.shortcuts_mut().add(shortcut);
+}
+
 /// Add a shortcut to the engine.
 ///
 /// # Arguments
@@ -374,32 +615,10 @@ pub unsafe extern "C" fn ime_add_shortcut(
     trigger: *const std::os::raw::c_char,
     replacement: *const std::os::raw::c_char,
 ) {
-    if trigger.is_null() || replacement.is_null() {
+    let (Some(trigger_str), Some(replacement_str)) = (c_str(trigger), c_str(replacement)) else {
         return;
-    }
-
-    let trigger_str = match std::ffi::CStr::from_ptr(trigger).to_str() {
-        Ok(s) => s,
-        Err(_) => return,
-    };
-    let replacement_str = match std::ffi::CStr::from_ptr(replacement).to_str() {
-        Ok(s) => s,
-        Err(_) => return,
     };
-
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        // Auto-detect shortcut type:
-        // - If trigger contains only non-letter chars (like "->", "=>"), use immediate trigger
-        // - Otherwise use word boundary trigger (traditional abbreviations like "vn" → "Việt Nam")
-        let is_symbol_trigger = trigger_str.chars().all(|c| !c.is_alphabetic());
-        let shortcut = if is_symbol_trigger {
-            engine::Shortcut::immediate(trigger_str, replacement_str)
-        } else {
-            engine::Shortcut::new(trigger_str, replacement_str)
-        };
-        e.shortcuts_mut().add(shortcut);
-    }
+    with_global(|e| add_shortcut(e, trigger_str, replacement_str));
 }
 
 /// Remove a shortcut from the engine.
@@ -411,55 +630,1638 @@ pub unsafe extern "C" fn ime_add_shortcut(
 /// Pointer must be a valid null-terminated UTF-8 string.
 #[no_mangle]
 pub unsafe extern "C" fn ime_remove_shortcut(trigger: *const std::os::raw::c_char) {
-    if trigger.is_null() {
+    let Some(trigger_str) = c_str(trigger) else {
         return;
-    }
-
-    let trigger_str = match std::ffi::CStr::from_ptr(trigger).to_str() {
-        Ok(s) => s,
-        Err(_) => return,
     };
-
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.shortcuts_mut().remove(trigger_str);
-    }
+    with_global(|e| e.shortcuts_mut().remove(trigger_str));
 }
 
 /// Clear all shortcuts from the engine.
 #[no_mangle]
 pub extern "C" fn ime_clear_shortcuts() {
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.shortcuts_mut().clear();
+    with_global(|e| e.shortcuts_mut().clear());
+}
+
+// Engine Handle FFI
+//
+// One engine per handle instead of the global one, for frontends that keep
+// separate state per input context. Each `ime_engine_*` function behaves
+// like the `ime_*` function of the same name; a null handle acts like an
+// uninitialized global engine.
+
+/// Create an engine with default settings.
+///
+/// Unlike `ime_init`, may be called any number of times; the global engine
+/// and every other handle are unaffected.
+///
+/// # Returns
+/// New handle to release with `ime_engine_free`.
+#[no_mangle]
+pub extern "C" fn ime_engine_new() -> *mut ImeEngine {
+    Box::into_raw(Box::new(ImeEngine {
+        engine: Mutex::new(Engine::new()),
+        recording: Mutex::new(None),
+    }))
+}
+
+/// Destroy an engine created by `ime_engine_new`.
+///
+/// # Safety
+/// * `engine` must be a handle returned by `ime_engine_new`, or null
+/// * Must be called exactly once per handle, with no call on it in progress
+/// * Do not use `engine` after calling this function
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_free(engine: *mut ImeEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
     }
 }
 
-// Word Restore FFI
+/// Process a key event on `engine`, see `ime_key`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_key(
+    engine: *mut ImeEngine,
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+) -> *mut Result {
+    into_result(handle_key(engine, key_event(key, caps, ctrl, false, None)))
+}
 
-/// Restore buffer from a Vietnamese word string.
+/// Process a key event on `engine`, see `ime_key_ext`.
 ///
-/// Used when native app detects cursor at word boundary and user
-/// wants to continue editing (e.g., backspace into previous word).
-/// Parses Vietnamese characters back to buffer components.
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_key_ext(
+    engine: *mut ImeEngine,
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+) -> *mut Result {
+    into_result(handle_key(engine, key_event(key, caps, ctrl, shift, None)))
+}
+
+/// Process a key event with its character on `engine`, see `ime_key_with_char`.
 ///
-/// # Arguments
-/// * `word` - C string containing the Vietnamese word to restore
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_key_with_char(
+    engine: *mut ImeEngine,
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+) -> *mut Result {
+    let ch = key_char(char_code);
+    into_result(handle_key(engine, key_event(key, caps, ctrl, shift, ch)))
+}
+
+/// Process a key event on `engine` into a caller-owned `Result`, see `ime_key_into`.
 ///
 /// # Safety
-/// Pointer must be a valid null-terminated UTF-8 string.
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `out` must be null or point to writable memory for one `Result`
 #[no_mangle]
-pub unsafe extern "C" fn ime_restore_word(word: *const std::os::raw::c_char) {
-    if word.is_null() {
-        return;
+pub unsafe extern "C" fn ime_engine_key_into(
+    engine: *mut ImeEngine,
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+    out: *mut Result,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let ch = key_char(char_code);
+    write_result(
+        handle_key(engine, key_event(key, caps, ctrl, shift, ch)),
+        out,
+    )
+}
+
+/// Process a key event on `engine` into a caller-owned `Utf8Result`, see `ime_key_utf8`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `out` must be null or point to writable memory for one `Utf8Result`
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_key_utf8(
+    engine: *mut ImeEngine,
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+    out: *mut Utf8Result,
+) -> bool {
+    if out.is_null() {
+        return false;
     }
-    let word_str = match std::ffi::CStr::from_ptr(word).to_str() {
-        Ok(s) => s,
-        Err(_) => return,
+    let ch = key_char(char_code);
+    let Some(handle) = engine.as_ref() else {
+        return write_utf8(None, out);
     };
-    let mut guard = lock_engine();
-    if let Some(ref mut e) = *guard {
-        e.restore_word(word_str);
+    write_utf8(
+        with_handle(engine, |e| {
+            let event = key_event(key, caps, ctrl, shift, ch);
+            let r = recorded_key(e, &handle.recording, event);
+            (r, e.charset())
+        }),
+        out,
+    )
+}
+
+/// Set the input method of `engine`, see `ime_method`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_method(engine: *mut ImeEngine, method: u8) {
+    with_handle(engine, |e| e.set_method(method));
+}
+
+/// Enable or disable `engine`, see `ime_enabled`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_enabled(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_enabled(enabled));
+}
+
+/// See `ime_skip_w_shortcut`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_skip_w_shortcut(engine: *mut ImeEngine, skip: bool) {
+    with_handle(engine, |e| e.set_skip_w_shortcut(skip));
+}
+
+/// See `ime_bracket_shortcut`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_bracket_shortcut(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_bracket_shortcut(enabled));
+}
+
+/// See `ime_esc_restore`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_esc_restore(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_esc_restore(enabled));
+}
+
+/// See `ime_free_tone`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_free_tone(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_free_tone(enabled));
+}
+
+/// See `ime_modern`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_modern(engine: *mut ImeEngine, modern: bool) {
+    with_handle(engine, |e| e.set_modern_tone(modern));
+}
+
+/// See `ime_english_auto_restore`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_english_auto_restore(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_english_auto_restore(enabled));
+}
+
+/// See `ime_auto_capitalize`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_auto_capitalize(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_auto_capitalize(enabled));
+}
+
+/// See `ime_allow_foreign_consonants`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_allow_foreign_consonants(
+    engine: *mut ImeEngine,
+    enabled: bool,
+) {
+    with_handle(engine, |e| e.set_allow_foreign_consonants(enabled));
+}
+
+/// See `ime_quick_telex`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_quick_telex(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_quick_telex(enabled));
+}
+
+/// See `ime_quick_start_consonant`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_quick_start_consonant(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_quick_start_consonants(enabled));
+}
+
+/// See `ime_quick_end_consonant`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_quick_end_consonant(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_quick_end_consonants(enabled));
+}
+
+/// See `ime_quick_consonant`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_quick_consonant(
+    engine: *mut ImeEngine,
+    key: u16,
+    enabled: bool,
+) -> bool {
+    with_handle(engine, |e| e.set_quick_consonant(key, enabled)).unwrap_or(false)
+}
+
+/// See `ime_charset`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_charset(engine: *mut ImeEngine, charset: u8) {
+    let Some(charset) = data::charset::Charset::from_id(charset) else {
+        return;
+    };
+    with_handle(engine, |e| e.set_charset(charset));
+}
+
+/// See `ime_completion`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_completion(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_completion(enabled));
+}
+
+/// See `ime_lexicon_learning`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_lexicon_learning(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_lexicon_learning(enabled));
+}
+
+/// Clear the input buffer of `engine`, see `ime_clear`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_clear(engine: *mut ImeEngine) {
+    with_handle(engine, |e| e.clear());
+}
+
+/// Clear everything including word history of `engine`, see `ime_clear_all`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_clear_all(engine: *mut ImeEngine) {
+    with_handle(engine, |e| e.clear_all());
+}
+
+/// Get the composed buffer of `engine`, see `ime_get_buffer`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `out` must point to valid memory of at least `max_len * sizeof(u32)` bytes
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_get_buffer(
+    engine: *mut ImeEngine,
+    out: *mut u32,
+    max_len: i64,
+) -> i64 {
+    copy_buffer(with_handle(engine, |e| e.get_buffer_string()), out, max_len)
+}
+
+/// Add a shortcut to `engine`, see `ime_add_shortcut`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * Both strings must be valid null-terminated UTF-8 strings
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_add_shortcut(
+    engine: *mut ImeEngine,
+    trigger: *const std::os::raw::c_char,
+    replacement: *const std::os::raw::c_char,
+) {
+    let (Some(trigger_str), Some(replacement_str)) = (c_str(trigger), c_str(replacement)) else {
+        return;
+    };
+    with_handle(engine, |e| add_shortcut(e, trigger_str, replacement_str));
+}
+
+/// Remove a shortcut from `engine`, see `ime_remove_shortcut`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `trigger` must be a valid null-terminated UTF-8 string
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_remove_shortcut(
+    engine: *mut ImeEngine,
+    trigger: *const std::os::raw::c_char,
+) {
+    let Some(trigger_str) = c_str(trigger) else {
+        return;
+    };
+    with_handle(engine, |e| e.shortcuts_mut().remove(trigger_str));
+}
+
+/// Clear all shortcuts of `engine`, see `ime_clear_shortcuts`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_clear_shortcuts(engine: *mut ImeEngine) {
+    with_handle(engine, |e| e.shortcuts_mut().clear());
+}
+
+// Composition FFI
+//
+// Preedit mode for input frameworks (IBus, Fcitx5, Wayland text-input),
+// see `engine::compose`. Same keys as `ime_key_into`; each result says what
+// to delete and commit in the document and what to show as preedit.
+
+/// Bytes of preedit a `ComposeResult` holds: the preedit never exceeds `MAX` chars
+pub const PREEDIT_MAX: usize = UTF8_MAX;
+
+/// Bytes of committed text a `ComposeResult` holds: a full preedit plus a key result
+pub const COMMIT_MAX: usize = 2 * UTF8_MAX;
+
+/// Key result in composition mode, see `ime_key_compose`
+///
+/// Apply in order: delete `delete` chars before the cursor, insert
+/// `commit`, show `preedit`, then forward the key if `pass_through`.
+#[repr(C)]
+pub struct ComposeResult {
+    /// Text to insert into the document, NUL-terminated UTF-8
+    pub commit: [u8; COMMIT_MAX + 1],
+    /// Text being composed, NUL-terminated UTF-8
+    pub preedit: [u8; PREEDIT_MAX + 1],
+    /// Bytes in `commit` before the NUL
+    pub commit_len: u16,
+    /// Bytes in `preedit` before the NUL
+    pub preedit_len: u16,
+    /// Cursor position in `preedit`, in bytes
+    pub cursor: u16,
+    /// Chars before the cursor to delete from the document first
+    pub delete: u16,
+    /// 0 = underline, 1 = word would be restored to its keystrokes
+    pub hint: u8,
+    /// The key was not handled: forward it to the application
+    pub pass_through: bool,
+}
+
+/// Copy `s` into a NUL-terminated buffer, cut at a char boundary if too long
+fn copy_text(dst: &mut [u8], s: &str) -> u16 {
+    let mut len = s.len().min(dst.len() - 1);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    dst[..len].copy_from_slice(&s.as_bytes()[..len]);
+    dst[len] = 0;
+    len as u16
+}
+
+impl ComposeResult {
+    fn new(c: &engine::Composition) -> Self {
+        let mut r = Self {
+            commit: [0; COMMIT_MAX + 1],
+            preedit: [0; PREEDIT_MAX + 1],
+            commit_len: 0,
+            preedit_len: 0,
+            cursor: 0,
+            delete: c.delete as u16,
+            hint: c.hint as u8,
+            pass_through: c.pass_through,
+        };
+        r.commit_len = copy_text(&mut r.commit, &c.commit);
+        r.preedit_len = copy_text(&mut r.preedit, &c.preedit);
+        let cursor = c
+            .preedit
+            .char_indices()
+            .nth(c.cursor)
+            .map_or(c.preedit.len(), |(i, _)| i);
+        r.cursor = cursor.min(r.preedit_len as usize) as u16;
+        r
+    }
+}
+
+/// Write a composition to caller memory, nothing to do if there is none
+unsafe fn write_compose(c: Option<engine::Composition>, out: *mut ComposeResult) -> bool {
+    let found = c.is_some();
+    out.write(ComposeResult::new(&c.unwrap_or_default()));
+    found
+}
+
+/// Process a key event in composition mode.
+///
+/// # Arguments
+/// * `key`, `caps`, `ctrl`, `shift`, `char_code` - as for `ime_key_into`
+/// * `out` - ComposeResult to overwrite
+///
+/// # Returns
+/// false if `out` is null or engine not initialized (`out` then asks for nothing).
+///
+/// # Safety
+/// `out` must be null or point to writable memory for one `ComposeResult`.
+#[no_mangle]
+pub unsafe extern "C" fn ime_key_compose(
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+    out: *mut ComposeResult,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let ch = key_char(char_code);
+    write_compose(
+        with_global(|e| e.on_key_compose(key, caps, ctrl, shift, ch)),
+        out,
+    )
+}
+
+/// End composition: commit the preedit and clear the current word.
+///
+/// Call on focus out, reset or a click elsewhere. Only `commit` is set.
+///
+/// # Returns
+/// false if `out` is null or engine not initialized.
+///
+/// # Safety
+/// `out` must be null or point to writable memory for one `ComposeResult`.
+#[no_mangle]
+pub unsafe extern "C" fn ime_finish_composition(out: *mut ComposeResult) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    write_compose(
+        with_global(|e| engine::Composition {
+            commit: e.finish_composition(),
+            ..Default::default()
+        }),
+        out,
+    )
+}
+
+/// Process a key event on `engine` in composition mode, see `ime_key_compose`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `out` must be null or point to writable memory for one `ComposeResult`
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_key_compose(
+    engine: *mut ImeEngine,
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+    out: *mut ComposeResult,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let ch = key_char(char_code);
+    write_compose(
+        with_handle(engine, |e| e.on_key_compose(key, caps, ctrl, shift, ch)),
+        out,
+    )
+}
+
+/// End composition on `engine`, see `ime_finish_composition`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `out` must be null or point to writable memory for one `ComposeResult`
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_finish_composition(
+    engine: *mut ImeEngine,
+    out: *mut ComposeResult,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    write_compose(
+        with_handle(engine, |e| engine::Composition {
+            commit: e.finish_composition(),
+            ..Default::default()
+        }),
+        out,
+    )
+}
+
+// Surrounding Text FFI
+//
+// For frontends that can read the document around the cursor, see
+// `engine::surrounding`. Edits are replace-ranges instead of backspaces.
+
+/// Key result as a replace-range edit, see `ime_key_surrounding`
+///
+/// Replace the chars from `start` to `end` chars before the cursor with
+/// `text`, then forward the key if `pass_through`.
+#[repr(C)]
+pub struct EditResult {
+    /// Replacement text, NUL-terminated UTF-8
+    pub text: [u8; UTF8_MAX + 1],
+    /// Chars before the cursor where the replaced range starts
+    pub start: u16,
+    /// Chars before the cursor where the replaced range ends
+    pub end: u16,
+    /// Bytes in `text` before the NUL
+    pub len: u16,
+    /// The key was not handled, or still types its own char after the edit
+    pub pass_through: bool,
+}
+
+/// Write an edit to caller memory, passing the key through if there is none
+unsafe fn write_edit(edit: Option<engine::TextEdit>, out: *mut EditResult) -> bool {
+    let found = edit.is_some();
+    let edit = edit.unwrap_or(engine::TextEdit {
+        pass_through: true,
+        ..Default::default()
+    });
+    let mut r = EditResult {
+        text: [0; UTF8_MAX + 1],
+        start: edit.start as u16,
+        end: edit.end as u16,
+        len: 0,
+        pass_through: edit.pass_through,
+    };
+    r.len = copy_text(&mut r.text, &edit.text);
+    out.write(r);
+    found
+}
+
+/// Rebuild the current word, word history and auto-capitalize state from
+/// the text around the cursor.
+///
+/// Call when the cursor moves or the document changes outside the IME,
+/// instead of `ime_clear_all` / `ime_restore_word`.
+///
+/// # Arguments
+/// * `before` - text before the cursor (a paragraph is enough), UTF-8
+/// * `after` - text after the cursor, UTF-8 (only its first char is read)
+///
+/// # Safety
+/// Both pointers must be null or valid null-terminated UTF-8 strings.
+/// Null is read as empty text.
+#[no_mangle]
+pub unsafe extern "C" fn ime_set_surrounding_text(
+    before: *const std::os::raw::c_char,
+    after: *const std::os::raw::c_char,
+) {
+    let (before, after) = (c_str(before).unwrap_or(""), c_str(after).unwrap_or(""));
+    with_global(|e| e.set_surrounding_text(before, after));
+}
+
+/// Process a key event into a replace-range edit.
+///
+/// # Arguments
+/// * `key`, `caps`, `ctrl`, `shift`, `char_code` - as for `ime_key_into`
+/// * `out` - EditResult to overwrite
+///
+/// # Returns
+/// false if `out` is null or engine not initialized (`out` then passes the key through).
+///
+/// # Safety
+/// `out` must be null or point to writable memory for one `EditResult`.
+#[no_mangle]
+pub unsafe extern "C" fn ime_key_surrounding(
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+    out: *mut EditResult,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let ch = key_char(char_code);
+    write_edit(
+        with_global(|e| e.on_key_surrounding(key, caps, ctrl, shift, ch)),
+        out,
+    )
+}
+
+/// Load the text around the cursor into `engine`, see `ime_set_surrounding_text`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `before` and `after` must be null or valid null-terminated UTF-8 strings
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_set_surrounding_text(
+    engine: *mut ImeEngine,
+    before: *const std::os::raw::c_char,
+    after: *const std::os::raw::c_char,
+) {
+    let (before, after) = (c_str(before).unwrap_or(""), c_str(after).unwrap_or(""));
+    with_handle(engine, |e| e.set_surrounding_text(before, after));
+}
+
+/// Process a key event on `engine` into a replace-range edit, see `ime_key_surrounding`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `out` must be null or point to writable memory for one `EditResult`
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_key_surrounding(
+    engine: *mut ImeEngine,
+    key: u16,
+    caps: bool,
+    ctrl: bool,
+    shift: bool,
+    char_code: u32,
+    out: *mut EditResult,
+) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let ch = key_char(char_code);
+    write_edit(
+        with_handle(engine, |e| e.on_key_surrounding(key, caps, ctrl, shift, ch)),
+        out,
+    )
+}
+
+// Decision Trace FFI
+//
+// Opt-in record of why each key was handled the way it was, see
+// `engine::trace`. Attach the JSON dump to bug reports.
+
+/// Start (with an empty trace) or stop recording key decisions.
+///
+/// Default: off. No-op if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_trace(enabled: bool) {
+    with_global(|e| e.set_trace(enabled));
+}
+
+/// Recorded keys as a JSON array, oldest first, one key per line.
+///
+/// "[]" when tracing is off or nothing was typed yet.
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_trace_json() -> *mut std::os::raw::c_char {
+    with_global(|e| e.trace_json()).map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Forget recorded keys and keep tracing.
+#[no_mangle]
+pub extern "C" fn ime_clear_trace() {
+    with_global(|e| e.clear_trace());
+}
+
+/// Start or stop recording key decisions on `engine`, see `ime_trace`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_trace(engine: *mut ImeEngine, enabled: bool) {
+    with_handle(engine, |e| e.set_trace(enabled));
+}
+
+/// Keys recorded on `engine` as JSON, see `ime_trace_json`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_trace_json(
+    engine: *mut ImeEngine,
+) -> *mut std::os::raw::c_char {
+    with_handle(engine, |e| e.trace_json()).map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Forget keys recorded on `engine`, see `ime_clear_trace`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_clear_trace(engine: *mut ImeEngine) {
+    with_handle(engine, |e| e.clear_trace());
+}
+
+// Keystroke Recording FFI
+//
+// Opt-in record of the keys passed to the `ime_key*` functions and their
+// results, see `engine::recording`. Users save the text and send it with a
+// bug report; `Recording::replay` shows where the engine now differs.
+// Only the settings in effect when recording starts are kept, so change
+// settings before starting. Composition and surrounding-text keys are not
+// recorded.
+
+/// Start a new recording, or stop and discard the current one.
+///
+/// Starting clears the word being typed and its history, so a replay
+/// begins from the same state. No-op if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_record(enabled: bool) {
+    with_global(|e| start_recording(e, &RECORDING, enabled));
+}
+
+/// Keys recorded since `ime_record(true)`, in the recording format.
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null if not recording
+/// or engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_recording_text() -> *mut std::os::raw::c_char {
+    with_global(|_| recording_text(&RECORDING))
+        .flatten()
+        .map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Start or stop recording on the engine locked as `e`
+fn start_recording(e: &mut Engine, recording: &Mutex<Option<Recording>>, enabled: bool) {
+    let mut recording = recording.lock().unwrap_or_else(|e| e.into_inner());
+    *recording = enabled.then(|| {
+        e.clear_all();
+        Recording::new(e.config())
+    });
+}
+
+fn recording_text(recording: &Mutex<Option<Recording>>) -> Option<String> {
+    recording
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(Recording::to_text)
+}
+
+/// Start or stop recording keys on `engine`, see `ime_record`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_record(engine: *mut ImeEngine, enabled: bool) {
+    let Some(handle) = engine.as_ref() else {
+        return;
+    };
+    with_handle(engine, |e| start_recording(e, &handle.recording, enabled));
+}
+
+/// Keys recorded on `engine`, see `ime_recording_text`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_recording_text(
+    engine: *mut ImeEngine,
+) -> *mut std::os::raw::c_char {
+    engine
+        .as_ref()
+        .and_then(|handle| recording_text(&handle.recording))
+        .map_or(std::ptr::null_mut(), into_c_string)
+}
+
+// Config FFI
+
+/// Version settings files are migrated to and stamped with
+fn app_version() -> updater::Version {
+    APP_VERSION
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(engine::migrate::crate_version)
+}
+
+/// Set the app version used by `ime_load_config` and `ime_save_config`.
+///
+/// Call once at startup with the marketing version ("1.4.2"). Until set,
+/// the engine's own version is used.
+///
+/// # Returns
+/// false if the version can't be parsed.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_set_app_version(version: *const std::os::raw::c_char) -> bool {
+    let Some(version) = c_str(version).and_then(updater::Version::parse) else {
+        return false;
+    };
+    *APP_VERSION.lock().unwrap_or_else(|e| e.into_inner()) = Some(version);
+    true
+}
+
+/// Settings read by `engine::migrate` and what migration changed
+type LoadedConfig = (engine::EngineConfig, engine::MigrationReport);
+
+/// Read a settings file, see `ime_load_config`
+fn load_config(path: Option<&str>) -> Option<LoadedConfig> {
+    engine::migrate::load(path?, &app_version()).ok()
+}
+
+/// Read UserDefaults text, see `ime_import_user_defaults`
+fn import_user_defaults(text: Option<&str>) -> Option<LoadedConfig> {
+    engine::migrate::import_user_defaults(text?, &app_version()).ok()
+}
+
+/// Replace every setting with `config`: number of migrated settings, or -1
+fn apply_config(e: &mut Engine, (config, report): LoadedConfig) -> i32 {
+    e.apply_config(&config)
+        .map_or(-1, |_| report.changes.len() as i32)
+}
+
+/// Write the settings of `e` to `path`: 0 on success, -1 on error
+fn save_config(e: &Engine, path: &str) -> i32 {
+    engine::migrate::save(&e.config(), path, &app_version()).map_or(-1, |_| 0)
+}
+
+/// Load all settings and shortcuts from a settings file.
+///
+/// See `engine::config` for the file format. Files written by other
+/// versions are migrated first (see `engine::migrate`). Replaces every
+/// setting and the whole shortcut table; on error nothing changes.
+///
+/// # Returns
+/// Number of settings changed by migration (0 for a current file),
+/// or -1 if the file can't be read or is invalid, or engine not initialized.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 path.
+#[no_mangle]
+pub unsafe extern "C" fn ime_load_config(path: *const std::os::raw::c_char) -> i32 {
+    let Some(loaded) = load_config(c_str(path)) else {
+        return -1;
+    };
+    with_global(|e| apply_config(e, loaded)).unwrap_or(-1)
+}
+
+/// Load settings the macOS app kept in UserDefaults.
+///
+/// One `gonhanh.<key> = <value>` line per stored key, shortcuts as the
+/// JSON the app encoded (see `engine::migrate::import_user_defaults`).
+/// Replaces every setting and the whole shortcut table; on error nothing
+/// changes. Save with `ime_save_config` afterwards.
+///
+/// # Returns
+/// Number of settings imported, or -1 if the text is invalid or engine
+/// not initialized.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_import_user_defaults(text: *const std::os::raw::c_char) -> i32 {
+    let Some(loaded) = import_user_defaults(c_str(text)) else {
+        return -1;
+    };
+    with_global(|e| apply_config(e, loaded)).unwrap_or(-1)
+}
+
+/// Save all settings and shortcuts to a settings file.
+///
+/// # Returns
+/// 0 on success, -1 on write error or engine not initialized.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 path.
+#[no_mangle]
+pub unsafe extern "C" fn ime_save_config(path: *const std::os::raw::c_char) -> i32 {
+    let Some(path_str) = c_str(path) else {
+        return -1;
+    };
+    with_global(|e| save_config(e, path_str)).unwrap_or(-1)
+}
+
+/// Load settings of `engine` from a file, see `ime_load_config`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `path` must be a valid null-terminated UTF-8 path
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_load_config(
+    engine: *mut ImeEngine,
+    path: *const std::os::raw::c_char,
+) -> i32 {
+    let Some(loaded) = load_config(c_str(path)) else {
+        return -1;
+    };
+    with_handle(engine, |e| apply_config(e, loaded)).unwrap_or(-1)
+}
+
+/// Load UserDefaults into `engine`, see `ime_import_user_defaults`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `text` must be a valid null-terminated UTF-8 string
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_import_user_defaults(
+    engine: *mut ImeEngine,
+    text: *const std::os::raw::c_char,
+) -> i32 {
+    let Some(loaded) = import_user_defaults(c_str(text)) else {
+        return -1;
+    };
+    with_handle(engine, |e| apply_config(e, loaded)).unwrap_or(-1)
+}
+
+/// Save settings of `engine` to a file, see `ime_save_config`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `path` must be a valid null-terminated UTF-8 path
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_save_config(
+    engine: *mut ImeEngine,
+    path: *const std::os::raw::c_char,
+) -> i32 {
+    let Some(path_str) = c_str(path) else {
+        return -1;
+    };
+    with_handle(engine, |e| save_config(e, path_str)).unwrap_or(-1)
+}
+
+// Custom Input Method FFI
+
+/// Parse a method definition, None for null or invalid
+fn custom_method(definition: Option<&str>) -> Option<engine::CustomMethod> {
+    engine::CustomMethod::parse(definition?).ok()
+}
+
+/// Register a custom input method from its text definition.
+///
+/// See `engine::input::custom` for the definition format. Registering a
+/// definition with an existing name replaces it and keeps its id.
+///
+/// # Arguments
+/// * `definition` - C string with the method definition
+///
+/// # Returns
+/// Method id to pass to `ime_method`, or -1 if the definition is invalid
+/// or engine not initialized.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_register_method(definition: *const std::os::raw::c_char) -> i32 {
+    let Some(method) = custom_method(c_str(definition)) else {
+        return -1;
+    };
+    with_global(|e| e.register_method(method).map_or(-1, i32::from)).unwrap_or(-1)
+}
+
+/// Register a custom input method on `engine`, see `ime_register_method`.
+///
+/// Ids are per handle: pass the returned id to `ime_engine_method`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `definition` must be a valid null-terminated UTF-8 string
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_register_method(
+    engine: *mut ImeEngine,
+    definition: *const std::os::raw::c_char,
+) -> i32 {
+    let Some(method) = custom_method(c_str(definition)) else {
+        return -1;
+    };
+    with_handle(engine, |e| e.register_method(method).map_or(-1, i32::from)).unwrap_or(-1)
+}
+
+// User Lexicon FFI
+
+/// Language of a pinned word: 0 = English, 1 = Vietnamese
+fn lexicon_language(language: u8) -> Option<engine::Language> {
+    match language {
+        0 => Some(engine::Language::English),
+        1 => Some(engine::Language::Vietnamese),
+        _ => None,
+    }
+}
+
+/// Undo exceptions of `e` as "language\tword" lines
+fn lexicon_exceptions(e: &Engine) -> String {
+    let lines: Vec<String> = e
+        .lexicon()
+        .exceptions()
+        .into_iter()
+        .map(|w| format!("{}\t{}", w.language as u8, w.word))
+        .collect();
+    lines.join("\n")
+}
+
+/// Read a lexicon file, None for a null path or an unreadable file
+fn load_lexicon(path: Option<&str>) -> Option<engine::UserLexicon> {
+    engine::UserLexicon::load(path?).ok()
+}
+
+/// Replace the lexicon of `e`, returning the number of words
+fn replace_lexicon(e: &mut Engine, lexicon: engine::UserLexicon) -> i32 {
+    let count = lexicon.len() as i32;
+    *e.lexicon_mut() = lexicon;
+    count
+}
+
+/// Enable or disable learning committed words into the user lexicon.
+///
+/// Default: false. Pinned words and undo exceptions apply either way.
+/// No-op if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_lexicon_learning(enabled: bool) {
+    with_global(|e| e.set_lexicon_learning(enabled));
+}
+
+/// Pin a word in the user lexicon: auto-restore keeps it in that language.
+///
+/// # Arguments
+/// * `word` - C string with one word ("kubectl", "gõ")
+/// * `language` - 0 = always English, 1 = always Vietnamese
+///
+/// # Returns
+/// false for an invalid word or language, a full lexicon, or engine not initialized.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_lexicon_add(word: *const std::os::raw::c_char, language: u8) -> bool {
+    let (Some(word_str), Some(language)) = (c_str(word), lexicon_language(language)) else {
+        return false;
+    };
+    with_global(|e| e.lexicon_mut().pin(word_str, language)).unwrap_or(false)
+}
+
+/// Remove a word (pinned or learned) from the user lexicon.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_lexicon_remove(word: *const std::os::raw::c_char) -> bool {
+    let Some(word_str) = c_str(word) else {
+        return false;
+    };
+    with_global(|e| e.lexicon_mut().remove(word_str)).unwrap_or(false)
+}
+
+/// Remove all words from the user lexicon.
+#[no_mangle]
+pub extern "C" fn ime_lexicon_clear() {
+    with_global(|e| e.lexicon_mut().clear());
+}
+
+/// Exceptions learned when the user undid an auto-restore decision.
+///
+/// One word per line as "language\tword" (0 = English, 1 = Vietnamese), the
+/// language the user went back to. Saved with `ime_lexicon_save`.
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_lexicon_exceptions() -> *mut std::os::raw::c_char {
+    with_global(|e| lexicon_exceptions(e)).map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Forget the exceptions learned from undo; pinned words stay.
+///
+/// # Returns
+/// Number of exceptions removed (0 if engine not initialized).
+#[no_mangle]
+pub extern "C" fn ime_lexicon_clear_exceptions() -> u32 {
+    with_global(|e| e.lexicon_mut().clear_exceptions() as u32).unwrap_or(0)
+}
+
+/// Replace the user lexicon with the contents of a file.
+///
+/// # Returns
+/// Number of words loaded, or -1 if the file can't be read or parsed
+/// (the current lexicon is kept) or engine not initialized.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 path.
+#[no_mangle]
+pub unsafe extern "C" fn ime_lexicon_load(path: *const std::os::raw::c_char) -> i32 {
+    let Some(lexicon) = load_lexicon(c_str(path)) else {
+        return -1;
+    };
+    with_global(|e| replace_lexicon(e, lexicon)).unwrap_or(-1)
+}
+
+/// Save the user lexicon (pinned and learned words) to a file.
+///
+/// # Returns
+/// 0 on success, -1 on write error or engine not initialized.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 path.
+#[no_mangle]
+pub unsafe extern "C" fn ime_lexicon_save(path: *const std::os::raw::c_char) -> i32 {
+    let Some(path_str) = c_str(path) else {
+        return -1;
+    };
+    with_global(|e| e.lexicon().save(path_str).map_or(-1, |_| 0)).unwrap_or(-1)
+}
+
+/// Pin a word in the lexicon of `engine`, see `ime_lexicon_add`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `word` must be a valid null-terminated UTF-8 string
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_lexicon_add(
+    engine: *mut ImeEngine,
+    word: *const std::os::raw::c_char,
+    language: u8,
+) -> bool {
+    let (Some(word_str), Some(language)) = (c_str(word), lexicon_language(language)) else {
+        return false;
+    };
+    with_handle(engine, |e| e.lexicon_mut().pin(word_str, language)).unwrap_or(false)
+}
+
+/// Remove a word from the lexicon of `engine`, see `ime_lexicon_remove`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `word` must be a valid null-terminated UTF-8 string
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_lexicon_remove(
+    engine: *mut ImeEngine,
+    word: *const std::os::raw::c_char,
+) -> bool {
+    let Some(word_str) = c_str(word) else {
+        return false;
+    };
+    with_handle(engine, |e| e.lexicon_mut().remove(word_str)).unwrap_or(false)
+}
+
+/// Remove all words from the lexicon of `engine`, see `ime_lexicon_clear`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_lexicon_clear(engine: *mut ImeEngine) {
+    with_handle(engine, |e| e.lexicon_mut().clear());
+}
+
+/// Undo exceptions of `engine`, see `ime_lexicon_exceptions`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_lexicon_exceptions(
+    engine: *mut ImeEngine,
+) -> *mut std::os::raw::c_char {
+    with_handle(engine, |e| lexicon_exceptions(e)).map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Forget undo exceptions of `engine`, see `ime_lexicon_clear_exceptions`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_lexicon_clear_exceptions(engine: *mut ImeEngine) -> u32 {
+    with_handle(engine, |e| e.lexicon_mut().clear_exceptions() as u32).unwrap_or(0)
+}
+
+/// Replace the lexicon of `engine` from a file, see `ime_lexicon_load`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `path` must be a valid null-terminated UTF-8 path
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_lexicon_load(
+    engine: *mut ImeEngine,
+    path: *const std::os::raw::c_char,
+) -> i32 {
+    let Some(lexicon) = load_lexicon(c_str(path)) else {
+        return -1;
+    };
+    with_handle(engine, |e| replace_lexicon(e, lexicon)).unwrap_or(-1)
+}
+
+/// Save the lexicon of `engine` to a file, see `ime_lexicon_save`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `path` must be a valid null-terminated UTF-8 path
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_lexicon_save(
+    engine: *mut ImeEngine,
+    path: *const std::os::raw::c_char,
+) -> i32 {
+    let Some(path_str) = c_str(path) else {
+        return -1;
+    };
+    with_handle(engine, |e| e.lexicon().save(path_str).map_or(-1, |_| 0)).unwrap_or(-1)
+}
+
+// Word Restore FFI
+
+/// Restore buffer from a Vietnamese word string.
+///
+/// Used when native app detects cursor at word boundary and user
+/// wants to continue editing (e.g., backspace into previous word).
+/// Parses Vietnamese characters back to buffer components.
+/// Accepts precomposed or decomposed (NFD) text.
+///
+/// # Arguments
+/// * `word` - C string containing the Vietnamese word to restore
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_restore_word(word: *const std::os::raw::c_char) {
+    let Some(word_str) = c_str(word) else {
+        return;
+    };
+    with_global(|e| e.restore_word(word_str));
+}
+
+/// Restore the buffer of `engine` from a word, see `ime_restore_word`.
+///
+/// # Safety
+/// * `engine` must be a live handle from `ime_engine_new`, or null
+/// * `word` must be a valid null-terminated UTF-8 string
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_restore_word(
+    engine: *mut ImeEngine,
+    word: *const std::os::raw::c_char,
+) {
+    let Some(word_str) = c_str(word) else {
+        return;
+    };
+    with_handle(engine, |e| e.restore_word(word_str));
+}
+
+/// Enable or disable word completion and next-syllable prediction.
+///
+/// Default: false (the completion index is only loaded when used).
+/// No-op if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_completion(enabled: bool) {
+    with_global(|e| e.set_completion(enabled));
+}
+
+/// Completions for the word being typed, best first, one per line.
+///
+/// A completion may span several words separated by spaces ("Việt Nam").
+/// Empty string when completion is disabled or nothing matches.
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_completions(limit: u32) -> *mut std::os::raw::c_char {
+    with_global(|e| e.completions(limit as usize).join("\n"))
+        .map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Replace the word being typed with completion `index` from `ime_completions`.
+///
+/// # Returns
+/// * Pointer to `Result` struct (caller must free with `ime_free`);
+///   action is 0 for an index out of range
+/// * `null` if engine not initialized
+#[no_mangle]
+pub extern "C" fn ime_accept_completion(index: u32) -> *mut Result {
+    into_result(with_global(|e| e.accept_completion(index as usize)))
+}
+
+/// Completions for the word typed on `engine`, see `ime_completions`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_completions(
+    engine: *mut ImeEngine,
+    limit: u32,
+) -> *mut std::os::raw::c_char {
+    with_handle(engine, |e| e.completions(limit as usize).join("\n"))
+        .map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Accept a completion on `engine`, see `ime_accept_completion`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_accept_completion(
+    engine: *mut ImeEngine,
+    index: u32,
+) -> *mut Result {
+    into_result(with_handle(engine, |e| e.accept_completion(index as usize)))
+}
+
+/// Candidates of `e` as "kind\ttext" lines
+fn candidate_lines(e: &Engine) -> String {
+    let lines: Vec<String> = e
+        .candidates()
+        .into_iter()
+        .map(|c| format!("{}\t{}", c.kind as u8, c.text))
+        .collect();
+    lines.join("\n")
+}
+
+/// Alternatives for the word being typed, best first.
+///
+/// One candidate per line as "kind\ttext"; kind is 0=Vietnamese (on screen),
+/// 1=English, 2=nearest dictionary word, 3=raw keystrokes.
+/// Empty string when no word is being typed.
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null if engine not initialized.
+#[no_mangle]
+pub extern "C" fn ime_candidates() -> *mut std::os::raw::c_char {
+    with_global(|e| candidate_lines(e)).map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Replace the word on screen with candidate `index` from `ime_candidates`.
+///
+/// The word is finished afterwards.
+///
+/// # Returns
+/// * Pointer to `Result` struct (caller must free with `ime_free`);
+///   action is 0 for an index out of range
+/// * `null` if engine not initialized
+#[no_mangle]
+pub extern "C" fn ime_commit_candidate(index: u32) -> *mut Result {
+    into_result(with_global(|e| e.commit_candidate(index as usize)))
+}
+
+/// Alternatives for the word typed on `engine`, see `ime_candidates`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_candidates(
+    engine: *mut ImeEngine,
+) -> *mut std::os::raw::c_char {
+    with_handle(engine, |e| candidate_lines(e)).map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Commit a candidate on `engine`, see `ime_commit_candidate`.
+///
+/// # Safety
+/// `engine` must be a live handle from `ime_engine_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn ime_engine_commit_candidate(
+    engine: *mut ImeEngine,
+    index: u32,
+) -> *mut Result {
+    into_result(with_handle(engine, |e| e.commit_candidate(index as usize)))
+}
+
+// Text FFI
+
+/// Convert a Rust string into a C string owned by the caller
+fn into_c_string(s: String) -> *mut std::os::raw::c_char {
+    // Interior NULs can't come from a C string input
+    std::ffi::CString::new(s).map_or(std::ptr::null_mut(), |c| c.into_raw())
+}
+
+/// Strip Vietnamese diacritics: "Tiếng Việt" → "Tieng Viet", "Đ" → "D".
+///
+/// Works without `ime_init`.
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null on invalid input.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_remove_diacritics(
+    text: *const std::os::raw::c_char,
+) -> *mut std::os::raw::c_char {
+    c_str(text).map_or(std::ptr::null_mut(), |s| {
+        into_c_string(data::remove_diacritics(s))
+    })
+}
+
+/// Restore diacritics on unaccented text: "khong biet" → "không biết".
+///
+/// Works without `ime_init`. Uses the bundled dictionary and syllable
+/// frequencies, fully offline. Each run of words is decoded as a whole;
+/// offer `ime_restore_alternatives` for corrections.
+///
+/// # Arguments
+/// * `text` - C string to restore
+/// * `modern` - Modern tone placement (hoà) instead of traditional (hòa)
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null on invalid input.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_restore_diacritics(
+    text: *const std::os::raw::c_char,
+    modern: bool,
+) -> *mut std::os::raw::c_char {
+    c_str(text).map_or(std::ptr::null_mut(), |s| {
+        into_c_string(data::restore_diacritics(s, modern))
+    })
+}
+
+/// Ranked diacritic alternatives for every ambiguous word of `text`.
+///
+/// One line per word: byte start, byte end, then candidates (best first),
+/// separated by tabs: "7\t10\thóc\thốc\thọc\thộc".
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null on invalid input.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_restore_alternatives(
+    text: *const std::os::raw::c_char,
+    modern: bool,
+) -> *mut std::os::raw::c_char {
+    let Some(text_str) = c_str(text) else {
+        return std::ptr::null_mut();
+    };
+    let lines: Vec<String> = data::restore_alternatives(text_str, modern)
+        .into_iter()
+        .map(|r| {
+            let mut line = format!("{}\t{}", r.range.start, r.range.end);
+            for candidate in &r.candidates {
+                line.push('\t');
+                line.push_str(candidate);
+            }
+            line
+        })
+        .collect();
+    into_c_string(lines.join("\n"))
+}
+
+/// Spelling suggestions for a word, best first: "nghành" → "ngành".
+///
+/// Works without `ime_init`.
+///
+/// # Arguments
+/// * `word` - C string with one word
+/// * `modern` - Suggest modern tone placement (hoà) instead of traditional (hòa)
+/// * `limit` - Maximum number of suggestions
+///
+/// # Returns
+/// New C string with one suggestion per line (empty when nothing is close),
+/// to release with `ime_free_string`, or null on invalid input.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_suggest(
+    word: *const std::os::raw::c_char,
+    modern: bool,
+    limit: u32,
+) -> *mut std::os::raw::c_char {
+    c_str(word).map_or(std::ptr::null_mut(), |s| {
+        into_c_string(data::suggest(s, modern, limit as usize).join("\n"))
+    })
+}
+
+/// Rewrite every syllable of a text to one tone placement style:
+/// "hòa, khỏe" ↔ "hoà, khoẻ". Non-Vietnamese words are left untouched.
+///
+/// Works without `ime_init`.
+///
+/// # Arguments
+/// * `text` - C string to normalize
+/// * `modern` - Modern style (hoà) instead of traditional (hòa)
+/// * `changes` - Receives the number of words changed (may be null)
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null on invalid input.
+///
+/// # Safety
+/// `text` must be a valid null-terminated UTF-8 string; `changes` must be
+/// null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn ime_normalize_tone_style(
+    text: *const std::os::raw::c_char,
+    modern: bool,
+    changes: *mut u32,
+) -> *mut std::os::raw::c_char {
+    let Some(text_str) = c_str(text) else {
+        return std::ptr::null_mut();
+    };
+    let normalized = data::normalize_tone_style(text_str, modern);
+    if !changes.is_null() {
+        *changes = normalized.changes as u32;
+    }
+    into_c_string(normalized.text)
+}
+
+/// Make a URL/filename slug: "Tiếng Việt có dấu" → "tieng-viet-co-dau".
+///
+/// Works without `ime_init`.
+///
+/// # Arguments
+/// * `text` - C string to convert
+/// * `separator` - C string placed between words (null for "-")
+/// * `lowercase` - Lowercase the result
+/// * `ascii_only` - Transliterate Latin letters and drop other scripts
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null on invalid input.
+///
+/// # Safety
+/// Pointers must be valid null-terminated UTF-8 strings (`separator` may be null).
+#[no_mangle]
+pub unsafe extern "C" fn ime_slugify(
+    text: *const std::os::raw::c_char,
+    separator: *const std::os::raw::c_char,
+    lowercase: bool,
+    ascii_only: bool,
+) -> *mut std::os::raw::c_char {
+    let Some(text_str) = c_str(text) else {
+        return std::ptr::null_mut();
+    };
+    let mut options = data::SlugOptions {
+        lowercase,
+        ascii_only,
+        ..Default::default()
+    };
+    if !separator.is_null() {
+        let Some(separator_str) = c_str(separator) else {
+            return std::ptr::null_mut();
+        };
+        options.separator = separator_str.to_string();
+    }
+    into_c_string(data::slugify_with(text_str, &options))
+}
+
+/// Change the case of Vietnamese text.
+///
+/// Works without `ime_init`.
+///
+/// # Arguments
+/// * `text` - C string to convert
+/// * `mode` - 0=UPPER, 1=lower, 2=Sentence case, 3=Title Case,
+///   4=Title Case keeping particles (và, của, ...) lowercase, 5=tOGGLE
+///
+/// # Returns
+/// New C string to release with `ime_free_string`, or null on invalid
+/// input or unknown mode.
+///
+/// # Safety
+/// Pointer must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ime_convert_case(
+    text: *const std::os::raw::c_char,
+    mode: u8,
+) -> *mut std::os::raw::c_char {
+    let (Some(text_str), Some(case)) = (c_str(text), data::Case::from_id(mode)) else {
+        return std::ptr::null_mut();
+    };
+    into_c_string(data::convert_case(text_str, case))
+}
+
+/// Free a string returned by the text functions (`ime_remove_diacritics`, ...).
+///
+/// # Safety
+/// * `s` must be a pointer returned by one of those functions, or null
+/// * Must be called exactly once per non-null return
+#[no_mangle]
+pub unsafe extern "C" fn ime_free_string(s: *mut std::os::raw::c_char) {
+    if !s.is_null() {
+        drop(std::ffi::CString::from_raw(s));
     }
 }
 
@@ -503,6 +2305,221 @@ mod tests {
         ime_clear();
     }
 
+    #[test]
+    #[serial]
+    fn test_candidates_ffi() {
+        ime_init();
+        ime_method(0); // Telex
+
+        for key in [keys::T, keys::E, keys::X, keys::T] {
+            drop(into_box(ime_key(key, false, false)));
+        }
+        let list = ime_candidates();
+        let text = unsafe { std::ffi::CStr::from_ptr(list) }.to_str().unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("0\ttẽt"));
+        assert_eq!(lines.next(), Some("1\ttext"));
+        unsafe { ime_free_string(list) };
+
+        let r = into_box(ime_commit_candidate(1));
+        assert_eq!(r.backspace, 3);
+        assert_eq!(r.count, 4);
+        assert_eq!(r.chars[1], 'e' as u32);
+        drop(r);
+
+        // Word finished: nothing left to choose
+        let list = ime_candidates();
+        assert_eq!(unsafe { std::ffi::CStr::from_ptr(list) }.to_bytes(), b"");
+        unsafe { ime_free_string(list) };
+        ime_clear();
+    }
+
+    #[test]
+    #[serial]
+    fn test_completion_ffi() {
+        ime_init();
+        ime_method(0); // Telex
+
+        for key in [keys::H, keys::O, keys::O, keys::M, keys::SPACE] {
+            drop(into_box(ime_key(key, false, false)));
+        }
+        // Disabled by default
+        let list = ime_completions(3);
+        assert_eq!(unsafe { std::ffi::CStr::from_ptr(list) }.to_bytes(), b"");
+        unsafe { ime_free_string(list) };
+
+        // Learned from a committed phrase
+        ime_completion(true);
+        ime_clear_all();
+        let phrase = [
+            keys::H,
+            keys::O,
+            keys::O,
+            keys::M,
+            keys::SPACE,
+            keys::N,
+            keys::A,
+        ];
+        for key in phrase.into_iter().chain([keys::Y, keys::SPACE]) {
+            drop(into_box(ime_key(key, false, false)));
+        }
+        ime_clear_all();
+        for key in phrase {
+            drop(into_box(ime_key(key, false, false)));
+        }
+        let list = ime_completions(3);
+        let text = unsafe { std::ffi::CStr::from_ptr(list) }.to_str().unwrap();
+        assert_eq!(text.lines().next(), Some("nay"));
+        unsafe { ime_free_string(list) };
+
+        let r = into_box(ime_accept_completion(0));
+        assert_eq!(r.backspace, 2);
+        assert_eq!(r.count, 3);
+        drop(r);
+        ime_completion(false);
+        ime_clear_all();
+    }
+
+    #[test]
+    #[serial]
+    fn test_lexicon_ffi() {
+        ime_init();
+        ime_method(0); // Telex
+        ime_english_auto_restore(true);
+
+        let word = CString::new("dax").unwrap();
+        let path =
+            std::env::temp_dir().join(format!("gonhanh-ffi-lexicon-{}.txt", std::process::id()));
+        let path_c = CString::new(path.to_str().unwrap()).unwrap();
+        unsafe {
+            assert!(ime_lexicon_add(word.as_ptr(), 0));
+            assert!(!ime_lexicon_add(word.as_ptr(), 7));
+            assert_eq!(ime_lexicon_save(path_c.as_ptr()), 0);
+            ime_lexicon_clear();
+            assert_eq!(ime_lexicon_load(path_c.as_ptr()), 1);
+        }
+
+        // "dax" would become "dã"; the pinned word is restored on space
+        for key in [keys::D, keys::A, keys::X] {
+            drop(into_box(ime_key(key, false, false)));
+        }
+        let r = into_box(ime_key(keys::SPACE, false, false));
+        assert_eq!(r.count, 4);
+        assert_eq!(r.chars[2], 'x' as u32);
+        drop(r);
+
+        unsafe {
+            assert!(ime_lexicon_remove(word.as_ptr()));
+            assert!(!ime_lexicon_remove(word.as_ptr()));
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(ime_lexicon_load(path_c.as_ptr()), -1);
+        }
+        ime_english_auto_restore(false);
+        ime_clear_all();
+    }
+
+    #[test]
+    #[serial]
+    fn test_lexicon_exceptions_ffi() {
+        ime_init();
+        ime_method(0); // Telex
+        ime_english_auto_restore(true);
+        ime_lexicon_clear();
+
+        // "dax " stays "dã "; backspace + x reverts to "dax" and commits it
+        for key in [
+            keys::D,
+            keys::A,
+            keys::X,
+            keys::SPACE,
+            keys::DELETE,
+            keys::X,
+        ] {
+            drop(into_box(ime_key(key, false, false)));
+        }
+        drop(into_box(ime_key(keys::SPACE, false, false)));
+
+        let list = ime_lexicon_exceptions();
+        assert_eq!(
+            unsafe { std::ffi::CStr::from_ptr(list) }.to_bytes(),
+            b"0\tdax"
+        );
+        unsafe { ime_free_string(list) };
+
+        assert_eq!(ime_lexicon_clear_exceptions(), 1);
+        assert_eq!(ime_lexicon_clear_exceptions(), 0);
+        ime_english_auto_restore(false);
+        ime_clear_all();
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_ffi() {
+        ime_init();
+        ime_method(1); // VNI
+        ime_modern(false);
+        let trigger = CString::new("vn").unwrap();
+        let replacement = CString::new("Việt Nam").unwrap();
+        unsafe { ime_add_shortcut(trigger.as_ptr(), replacement.as_ptr()) };
+
+        let path =
+            std::env::temp_dir().join(format!("gonhanh-ffi-config-{}.txt", std::process::id()));
+        let path_c = CString::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { ime_save_config(path_c.as_ptr()) }, 0);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains(&format!("version = {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(text.contains("method = vni\n"));
+        assert!(text.contains("modern_tone = false\n"));
+        assert!(text.contains("[shortcuts]\nvn = Việt Nam\n"));
+
+        // Loading restores every setting
+        ime_method(0);
+        ime_modern(true);
+        ime_clear_shortcuts();
+        assert_eq!(unsafe { ime_load_config(path_c.as_ptr()) }, 0);
+        {
+            let guard = lock_engine();
+            let config = guard.as_ref().unwrap().config();
+            assert_eq!(config.method, 1);
+            assert!(!config.modern_tone);
+            assert_eq!(config.shortcuts.len(), 1);
+        }
+
+        // Newer file: unknown settings dropped and counted
+        std::fs::write(
+            &path,
+            "version = 99.0.0\nmodern_tone = false\nnew_toggle = true\n",
+        )
+        .unwrap();
+        assert_eq!(unsafe { ime_load_config(path_c.as_ptr()) }, 1);
+
+        // macOS UserDefaults
+        let defaults = CString::new(
+            "gonhanh.method = 1\ngonhanh.modernTone = 0\ngonhanh.shortcuts = [{\"key\":\"ko\",\"value\":\"không\",\"isEnabled\":true}]\n",
+        )
+        .unwrap();
+        assert_eq!(unsafe { ime_import_user_defaults(defaults.as_ptr()) }, 3);
+        {
+            let guard = lock_engine();
+            let config = guard.as_ref().unwrap().config();
+            assert_eq!(config.method, 1);
+            assert!(!config.modern_tone);
+            assert_eq!(config.shortcuts[0].trigger, "ko");
+        }
+        let bad = CString::new("gonhanh.method = 7\n").unwrap();
+        assert_eq!(unsafe { ime_import_user_defaults(bad.as_ptr()) }, -1);
+
+        std::fs::write(&path, "method = nope\n").unwrap();
+        assert_eq!(unsafe { ime_load_config(path_c.as_ptr()) }, -1);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(unsafe { ime_load_config(path_c.as_ptr()) }, -1);
+
+        ime_method(0);
+        ime_modern(true);
+        ime_clear_shortcuts();
+        ime_clear_all();
+    }
+
     #[test]
     #[serial]
     fn test_shortcut_ffi_add_and_clear() {
@@ -1072,4 +3089,722 @@ mod tests {
             engine::MAX
         );
     }
+
+    #[test]
+    #[serial]
+    fn test_register_method_ffi() {
+        ime_init();
+        ime_clear();
+
+        let definition = CString::new(
+            "name = \"Brackets\"\n[marks]\nsac = \"s\"\n[tones]\n\"[\" = \"horn:o\"\n",
+        )
+        .unwrap();
+        let id = unsafe { ime_register_method(definition.as_ptr()) };
+        assert!(id >= 0, "Valid definition should return an id");
+        ime_method(id as u8);
+
+        // o + [ → ơ, then s → ớ
+        drop(into_box(ime_key(keys::O, false, false)));
+        let r = into_box(ime_key(keys::LBRACKET, false, false));
+        assert_eq!(r.chars[0], 'ơ' as u32);
+        let r = into_box(ime_key(keys::S, false, false));
+        assert_eq!(r.chars[0], 'ớ' as u32);
+
+        // Invalid definition and null pointer are rejected
+        let invalid = CString::new("[tones]\nw = \"horn:b\"").unwrap();
+        assert_eq!(unsafe { ime_register_method(invalid.as_ptr()) }, -1);
+        assert_eq!(unsafe { ime_register_method(std::ptr::null()) }, -1);
+
+        ime_method(0);
+        ime_clear();
+    }
+
+    #[test]
+    #[serial]
+    fn test_charset_ffi() {
+        ime_init();
+        ime_clear();
+        ime_method(0);
+        ime_charset(2); // VNI-Windows
+
+        // a + s → á = "a" + 0xF9, replacing the "a" on screen
+        drop(into_box(ime_key(keys::A, false, false)));
+        let r = into_box(ime_key(keys::S, false, false));
+        assert_eq!(r.backspace, 1);
+        assert_eq!(&r.chars[..r.count as usize], &['a' as u32, 0xF9]);
+
+        // á → à now deletes both bytes
+        let r = into_box(ime_key(keys::F, false, false));
+        assert_eq!(r.backspace, 2);
+        assert_eq!(&r.chars[..r.count as usize], &['a' as u32, 0xF8]);
+
+        ime_charset(0);
+        ime_clear();
+    }
+
+    /// Composed buffer of a handle
+    fn handle_buffer(engine: *mut ImeEngine) -> String {
+        let mut out = [0u32; 64];
+        let len = unsafe { ime_engine_get_buffer(engine, out.as_mut_ptr(), 64) };
+        out[..len as usize]
+            .iter()
+            .filter_map(|&c| char::from_u32(c))
+            .collect()
+    }
+
+    #[test]
+    #[serial]
+    fn test_key_into_ffi() {
+        ime_init();
+        ime_method(0);
+        let mut r = Result::none();
+        unsafe {
+            assert!(ime_key_into(keys::A, false, false, false, 0, &mut r));
+            assert!(ime_key_into(keys::S, false, false, false, 0, &mut r));
+            assert_eq!(r.backspace, 1);
+            assert_eq!(&r.chars[..r.count as usize], &['á' as u32]);
+            // char_code maps to the key it types
+            assert!(ime_key_into(0, false, false, false, 'f' as u32, &mut r));
+            assert_eq!(&r.chars[..r.count as usize], &['à' as u32]);
+            assert!(!ime_key_into(
+                keys::A,
+                false,
+                false,
+                false,
+                0,
+                std::ptr::null_mut()
+            ));
+
+            let e = ime_engine_new();
+            assert!(ime_engine_key_into(
+                e,
+                keys::D,
+                false,
+                false,
+                false,
+                0,
+                &mut r
+            ));
+            assert!(ime_engine_key_into(
+                e,
+                keys::D,
+                false,
+                false,
+                false,
+                0,
+                &mut r
+            ));
+            assert_eq!(&r.chars[..r.count as usize], &['đ' as u32]);
+            ime_engine_free(e);
+            assert!(!ime_engine_key_into(
+                std::ptr::null_mut(),
+                keys::A,
+                false,
+                false,
+                false,
+                0,
+                &mut r
+            ));
+            assert_eq!(r.action, 0);
+        }
+        ime_clear();
+    }
+
+    #[test]
+    #[serial]
+    fn test_key_utf8_ffi() {
+        ime_init();
+        ime_method(0);
+        let mut r = std::mem::MaybeUninit::<Utf8Result>::uninit();
+        let text = |r: &Utf8Result| {
+            std::str::from_utf8(&r.text[..r.len as usize])
+                .unwrap()
+                .to_string()
+        };
+        unsafe {
+            for key in [keys::V, keys::I, keys::E, keys::E] {
+                assert!(ime_key_utf8(key, false, false, false, 0, r.as_mut_ptr()));
+            }
+            let r = r.assume_init_mut();
+            assert_eq!(r.backspace, 1);
+            assert_eq!(text(r), "ê");
+            assert_eq!(r.text[r.len as usize], 0);
+            assert!(ime_key_utf8(keys::J, false, false, false, 0, r));
+            assert_eq!((r.backspace, text(r).as_str()), (1, "ệ"));
+
+            // Legacy charsets keep their bytes
+            ime_clear();
+            ime_charset(2);
+            ime_key_utf8(keys::A, false, false, false, 0, r);
+            ime_key_utf8(keys::S, false, false, false, 0, r);
+            assert_eq!(&r.text[..r.len as usize], b"a\xF9");
+            ime_charset(0);
+
+            let e = ime_engine_new();
+            ime_engine_key_utf8(e, keys::A, false, false, false, 0, r);
+            ime_engine_key_utf8(e, keys::A, false, false, false, 0, r);
+            assert_eq!(text(r), "â");
+            ime_engine_free(e);
+        }
+        ime_clear();
+    }
+
+    /// Verify Utf8Result layout: text[1025] then action/backspace/flags and
+    /// a u16 len at an aligned offset, no padding.
+    #[test]
+    fn test_utf8_result_layout() {
+        assert_eq!(UTF8_MAX, 1024);
+        assert_eq!(std::mem::size_of::<Utf8Result>(), 1030);
+        assert_eq!(std::mem::offset_of!(Utf8Result, action), 1025);
+        assert_eq!(std::mem::offset_of!(Utf8Result, flags), 1027);
+        assert_eq!(std::mem::offset_of!(Utf8Result, len), 1028);
+
+        let h_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../platforms/linux/src/RustBridge.h"
+        );
+        let source = std::fs::read_to_string(h_path)
+            .unwrap_or_else(|e| panic!("Cannot read RustBridge.h: {}", e));
+        assert!(source
+            .lines()
+            .any(|line| line.contains("static_assert")
+                && line.contains("sizeof(ImeUtf8Result) == 1030")));
+    }
+
+    #[test]
+    #[serial]
+    fn test_key_compose_ffi() {
+        ime_init();
+        ime_method(0);
+        let mut r = std::mem::MaybeUninit::<ComposeResult>::uninit();
+        let text =
+            |b: &[u8], len: u16| std::str::from_utf8(&b[..len as usize]).unwrap().to_string();
+        unsafe {
+            for key in [keys::V, keys::I, keys::E, keys::E, keys::J] {
+                assert!(ime_key_compose(key, false, false, false, 0, r.as_mut_ptr()));
+            }
+            let r = r.assume_init_mut();
+            assert_eq!(text(&r.preedit, r.preedit_len), "việ");
+            assert_eq!(r.preedit[r.preedit_len as usize], 0);
+            assert_eq!(r.cursor, r.preedit_len);
+            assert_eq!((r.commit_len, r.delete, r.pass_through), (0, 0, false));
+
+            ime_key_compose(keys::T, false, false, false, 0, r);
+            ime_key_compose(keys::SPACE, false, false, false, 0, r);
+            assert_eq!(text(&r.commit, r.commit_len), "việt ");
+            assert_eq!(r.preedit_len, 0);
+
+            // Focus out commits what is left
+            ime_key_compose(keys::A, false, false, false, 0, r);
+            assert!(ime_finish_composition(r));
+            assert_eq!(text(&r.commit, r.commit_len), "a");
+            assert!(!ime_finish_composition(std::ptr::null_mut()));
+
+            let e = ime_engine_new();
+            ime_engine_key_compose(e, keys::A, false, false, false, 0, r);
+            ime_engine_key_compose(e, keys::A, false, false, false, 0, r);
+            assert_eq!(text(&r.preedit, r.preedit_len), "â");
+            ime_engine_key_compose(e, keys::C, false, true, false, 0, r);
+            assert!(r.pass_through);
+            assert_eq!(text(&r.commit, r.commit_len), "â");
+            ime_engine_free(e);
+        }
+        ime_clear();
+    }
+
+    /// Verify ComposeResult layout: both buffers, then u16 fields at even
+    /// offsets and the u8/bool pair, no padding.
+    #[test]
+    fn test_compose_result_layout() {
+        assert_eq!(std::mem::size_of::<ComposeResult>(), 3084);
+        assert_eq!(std::mem::offset_of!(ComposeResult, preedit), 2049);
+        assert_eq!(std::mem::offset_of!(ComposeResult, commit_len), 3074);
+        assert_eq!(std::mem::offset_of!(ComposeResult, delete), 3080);
+        assert_eq!(std::mem::offset_of!(ComposeResult, pass_through), 3083);
+
+        let h_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../platforms/linux/src/RustBridge.h"
+        );
+        let source = std::fs::read_to_string(h_path)
+            .unwrap_or_else(|e| panic!("Cannot read RustBridge.h: {}", e));
+        assert!(source.lines().any(|line| line.contains("static_assert")
+            && line.contains("sizeof(ImeComposeResult) == 3084")));
+    }
+
+    #[test]
+    #[serial]
+    fn test_key_surrounding_ffi() {
+        ime_init();
+        ime_method(0);
+        let mut r = std::mem::MaybeUninit::<EditResult>::uninit();
+        let text = |r: &EditResult| {
+            std::str::from_utf8(&r.text[..r.len as usize])
+                .unwrap()
+                .to_string()
+        };
+        unsafe {
+            let before = CString::new("chào tiêng").unwrap();
+            ime_set_surrounding_text(before.as_ptr(), std::ptr::null());
+            assert!(ime_key_surrounding(
+                keys::S,
+                false,
+                false,
+                false,
+                0,
+                r.as_mut_ptr()
+            ));
+            let r = r.assume_init_mut();
+            assert_eq!((r.start, r.end, text(r).as_str()), (3, 2, "ế"));
+            assert_eq!(r.text[r.len as usize], 0);
+            assert!(!r.pass_through);
+
+            ime_key_surrounding(keys::N, false, false, false, 0, r);
+            assert!(r.pass_through);
+            assert_eq!((r.start, r.end, r.len), (0, 0, 0));
+
+            // Backspace after space brings the previous word back
+            let e = ime_engine_new();
+            let before = CString::new("chào ban ").unwrap();
+            ime_engine_set_surrounding_text(e, before.as_ptr(), std::ptr::null());
+            ime_engine_key_surrounding(e, keys::DELETE, false, false, false, 0, r);
+            assert_eq!((r.start, r.end, r.pass_through), (1, 0, false));
+            ime_engine_key_surrounding(e, keys::F, false, false, false, 0, r);
+            assert_eq!((r.start, r.end, text(r).as_str()), (2, 1, "à"));
+            ime_engine_free(e);
+
+            assert!(!ime_key_surrounding(
+                keys::A,
+                false,
+                false,
+                false,
+                0,
+                std::ptr::null_mut()
+            ));
+        }
+        ime_clear_all();
+    }
+
+    /// Verify EditResult layout: text[1025], a padding byte, then u16 fields
+    /// and the bool.
+    #[test]
+    fn test_edit_result_layout() {
+        assert_eq!(std::mem::size_of::<EditResult>(), 1034);
+        assert_eq!(std::mem::offset_of!(EditResult, start), 1026);
+        assert_eq!(std::mem::offset_of!(EditResult, len), 1030);
+        assert_eq!(std::mem::offset_of!(EditResult, pass_through), 1032);
+
+        let h_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../platforms/linux/src/RustBridge.h"
+        );
+        let source = std::fs::read_to_string(h_path)
+            .unwrap_or_else(|e| panic!("Cannot read RustBridge.h: {}", e));
+        assert!(source
+            .lines()
+            .any(|line| line.contains("static_assert")
+                && line.contains("sizeof(ImeEditResult) == 1034")));
+    }
+
+    #[test]
+    #[serial]
+    fn test_trace_ffi() {
+        ime_init();
+        ime_method(0);
+        ime_clear_all();
+        let json = |p: *mut std::os::raw::c_char| unsafe {
+            assert!(!p.is_null());
+            let s = std::ffi::CStr::from_ptr(p).to_str().unwrap().to_string();
+            ime_free_string(p);
+            s
+        };
+        assert_eq!(json(ime_trace_json()), "[]");
+
+        ime_trace(true);
+        for key in [keys::A, keys::S] {
+            unsafe { ime_free(ime_key(key, false, false)) };
+        }
+        let dump = json(ime_trace_json());
+        assert_eq!(dump.lines().count(), 4);
+        assert!(dump.contains("\"steps\":[\"mark\"]"));
+        assert!(dump.contains("\"buffer\":\"á\""));
+        ime_clear_trace();
+        assert_eq!(json(ime_trace_json()), "[]");
+        ime_trace(false);
+
+        unsafe {
+            let e = ime_engine_new();
+            ime_engine_trace(e, true);
+            ime_free(ime_engine_key(e, keys::D, false, false));
+            ime_free(ime_engine_key(e, keys::D, false, false));
+            let dump = json(ime_engine_trace_json(e));
+            assert!(dump.contains("\"steps\":[\"stroke\"]"));
+            ime_engine_clear_trace(e);
+            assert_eq!(json(ime_engine_trace_json(e)), "[]");
+            ime_engine_free(e);
+            assert!(ime_engine_trace_json(std::ptr::null_mut()).is_null());
+        }
+        ime_clear_all();
+    }
+
+    #[test]
+    #[serial]
+    fn test_recording_ffi() {
+        ime_init();
+        ime_method(0);
+        ime_clear_all();
+        let text = |p: *mut std::os::raw::c_char| unsafe {
+            assert!(!p.is_null());
+            let s = std::ffi::CStr::from_ptr(p).to_str().unwrap().to_string();
+            ime_free_string(p);
+            s
+        };
+        assert!(ime_recording_text().is_null());
+
+        ime_record(true);
+        unsafe {
+            ime_free(ime_key(keys::A, false, false));
+            ime_free(ime_key_with_char(keys::S, false, false, false, 's' as u32));
+            let mut out: Result = std::mem::zeroed();
+            ime_key_into(keys::SPACE, false, false, false, 0, &mut out);
+        }
+        let recorded = text(ime_recording_text());
+        assert!(recorded.starts_with("# Gõ Nhanh recording\n"));
+        assert!(recorded.ends_with(
+            "[keys]\n0 - - => none 0 0 \"\"\n1 - \"s\" => send 1 1 \"á\"\n49 - - => none 0 0 \"\"\n"
+        ));
+        let rec = Recording::parse(&recorded).unwrap();
+        assert_eq!(rec.keys.len(), 3);
+        assert_eq!(rec.replay().unwrap(), None);
+        ime_record(false);
+        assert!(ime_recording_text().is_null());
+
+        unsafe {
+            let e = ime_engine_new();
+            ime_engine_method(e, 1);
+            ime_engine_record(e, true);
+            let mut out: Utf8Result = std::mem::zeroed();
+            ime_engine_key_utf8(e, keys::A, false, false, false, 0, &mut out);
+            ime_engine_key_utf8(e, keys::N1, false, false, false, 0, &mut out);
+            let rec = Recording::parse(&text(ime_engine_recording_text(e))).unwrap();
+            assert_eq!(rec.config.method, 1);
+            assert_eq!(rec.keys[1].output.text, "á");
+            assert_eq!(rec.replay().unwrap(), None);
+            // The global engine records nothing meanwhile
+            assert!(ime_recording_text().is_null());
+            ime_engine_free(e);
+            assert!(ime_engine_recording_text(std::ptr::null_mut()).is_null());
+        }
+        ime_clear_all();
+    }
+
+    #[test]
+    #[serial]
+    fn test_engine_handles_are_independent() {
+        ime_init();
+        ime_method(0);
+        let telex = ime_engine_new();
+        let vni = ime_engine_new();
+        unsafe {
+            ime_engine_method(vni, 1);
+
+            // Interleaved keystrokes, as from two input contexts
+            drop(into_box(ime_engine_key_ext(
+                telex,
+                keys::A,
+                false,
+                false,
+                false,
+            )));
+            drop(into_box(ime_engine_key_ext(
+                vni,
+                keys::A,
+                false,
+                false,
+                false,
+            )));
+            let r = into_box(ime_engine_key_ext(telex, keys::S, false, false, false));
+            assert_eq!(r.chars[0], 'á' as u32);
+            let r = into_box(ime_engine_key_ext(vni, keys::N1, false, false, false));
+            assert_eq!(r.chars[0], 'á' as u32);
+            drop(into_box(ime_engine_key(telex, keys::V, false, false)));
+
+            assert_eq!(handle_buffer(telex), "áv");
+            assert_eq!(handle_buffer(vni), "á");
+            ime_engine_clear(telex);
+            assert_eq!(handle_buffer(telex), "");
+            assert_eq!(handle_buffer(vni), "á");
+
+            ime_engine_free(telex);
+            ime_engine_free(vni);
+        }
+        // The global engine saw none of it
+        let mut out = [0u32; 8];
+        assert_eq!(unsafe { ime_get_buffer(out.as_mut_ptr(), 8) }, 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_engine_handle_shortcuts() {
+        ime_init();
+        let a = ime_engine_new();
+        let b = ime_engine_new();
+        let trigger = CString::new("vn").unwrap();
+        let replacement = CString::new("Việt Nam").unwrap();
+        let shortcuts = |h| unsafe { with_handle(h, |e| e.shortcuts().len()).unwrap() };
+        unsafe {
+            ime_engine_add_shortcut(a, trigger.as_ptr(), replacement.as_ptr());
+            assert_eq!(shortcuts(a), 1);
+            assert_eq!(shortcuts(b), 0);
+            assert_eq!(with_global(|e| e.shortcuts().len()), Some(0));
+
+            ime_engine_remove_shortcut(a, trigger.as_ptr());
+            assert_eq!(shortcuts(a), 0);
+            ime_engine_add_shortcut(b, trigger.as_ptr(), replacement.as_ptr());
+            ime_engine_clear_shortcuts(b);
+            assert_eq!(shortcuts(b), 0);
+
+            ime_engine_free(a);
+            ime_engine_free(b);
+        }
+    }
+
+    /// Take a C string returned by an FFI function
+    fn take_string(s: *mut std::os::raw::c_char) -> String {
+        assert!(!s.is_null(), "FFI returned null string");
+        let text = unsafe { std::ffi::CStr::from_ptr(s) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { ime_free_string(s) };
+        text
+    }
+
+    #[test]
+    #[serial]
+    fn test_engine_handle_config_and_lexicon() {
+        ime_init();
+        let a = ime_engine_new();
+        let b = ime_engine_new();
+        let dir = std::env::temp_dir();
+        let config_path = dir.join(format!(
+            "gonhanh-ffi-handle-config-{}.txt",
+            std::process::id()
+        ));
+        let lexicon_path = dir.join(format!(
+            "gonhanh-ffi-handle-lexicon-{}.txt",
+            std::process::id()
+        ));
+        let config_c = CString::new(config_path.to_str().unwrap()).unwrap();
+        let lexicon_c = CString::new(lexicon_path.to_str().unwrap()).unwrap();
+        let word = CString::new("dax").unwrap();
+        let method = |h| unsafe { with_handle(h, |e| e.config().method).unwrap() };
+        unsafe {
+            // Settings move between handles through a file
+            ime_engine_method(a, 1);
+            assert_eq!(ime_engine_save_config(a, config_c.as_ptr()), 0);
+            assert_eq!(ime_engine_load_config(b, config_c.as_ptr()), 0);
+            assert_eq!(method(b), 1);
+            assert_eq!(with_global(|e| e.config().method), Some(0));
+            let defaults = CString::new("gonhanh.method = 0\n").unwrap();
+            assert_eq!(ime_engine_import_user_defaults(b, defaults.as_ptr()), 1);
+            assert_eq!(method(b), 0);
+            std::fs::remove_file(&config_path).unwrap();
+            assert_eq!(ime_engine_load_config(b, config_c.as_ptr()), -1);
+
+            // Custom method ids are per handle
+            let definition =
+                CString::new("name = \"Brackets\"\n[tones]\n\"[\" = \"horn:o\"\n").unwrap();
+            let id = ime_engine_register_method(a, definition.as_ptr());
+            assert!(id >= 0);
+            ime_engine_method(a, id as u8);
+            drop(into_box(ime_engine_key(a, keys::O, false, false)));
+            let r = into_box(ime_engine_key(a, keys::LBRACKET, false, false));
+            assert_eq!(r.chars[0], 'ơ' as u32);
+            assert_eq!(ime_engine_register_method(a, std::ptr::null()), -1);
+
+            // Lexicon
+            assert!(ime_engine_lexicon_add(a, word.as_ptr(), 0));
+            assert!(!ime_engine_lexicon_add(a, word.as_ptr(), 7));
+            assert_eq!(ime_engine_lexicon_save(a, lexicon_c.as_ptr()), 0);
+            assert_eq!(ime_engine_lexicon_load(b, lexicon_c.as_ptr()), 1);
+            assert!(ime_engine_lexicon_remove(b, word.as_ptr()));
+            assert!(!ime_engine_lexicon_remove(b, word.as_ptr()));
+            ime_engine_lexicon_clear(a);
+            assert_eq!(ime_engine_lexicon_save(a, lexicon_c.as_ptr()), 0);
+            assert_eq!(ime_engine_lexicon_load(b, lexicon_c.as_ptr()), 0);
+            std::fs::remove_file(&lexicon_path).unwrap();
+            assert_eq!(ime_engine_lexicon_load(b, lexicon_c.as_ptr()), -1);
+            assert_eq!(take_string(ime_engine_lexicon_exceptions(a)), "");
+            assert_eq!(ime_engine_lexicon_clear_exceptions(a), 0);
+
+            ime_engine_free(a);
+            ime_engine_free(b);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_engine_handle_completions_and_candidates() {
+        ime_init();
+        let e = ime_engine_new();
+        let word = CString::new("việt").unwrap();
+        unsafe {
+            ime_engine_restore_word(e, word.as_ptr());
+            assert_eq!(handle_buffer(e), "việt");
+            ime_engine_clear_all(e);
+
+            for key in [keys::T, keys::E, keys::X, keys::T] {
+                drop(into_box(ime_engine_key(e, key, false, false)));
+            }
+            let text = take_string(ime_engine_candidates(e));
+            assert_eq!(
+                text.lines().take(2).collect::<Vec<_>>(),
+                ["0\ttẽt", "1\ttext"]
+            );
+            let r = into_box(ime_engine_commit_candidate(e, 1));
+            assert_eq!(r.backspace, 3);
+            assert_eq!(r.count, 4);
+            assert_eq!(take_string(ime_engine_candidates(e)), "");
+
+            // Next syllable predicted from the bundled frequencies
+            ime_engine_completion(e, true);
+            ime_engine_clear_all(e);
+            for key in [
+                keys::V,
+                keys::I,
+                keys::E,
+                keys::E,
+                keys::J,
+                keys::T,
+                keys::SPACE,
+            ] {
+                drop(into_box(ime_engine_key(e, key, false, false)));
+            }
+            assert_eq!(take_string(ime_engine_completions(e, 1)), "nam");
+            let r = into_box(ime_engine_accept_completion(e, 0));
+            assert_eq!(r.count, 3);
+            assert_eq!(r.chars[0], 'n' as u32);
+
+            // The global engine has completion off
+            assert_eq!(take_string(ime_completions(3)), "");
+            ime_engine_free(e);
+        }
+    }
+
+    #[test]
+    fn test_engine_handle_null_safety() {
+        let null = std::ptr::null_mut();
+        let trigger = CString::new("vn").unwrap();
+        unsafe {
+            assert!(ime_engine_key_ext(null, keys::A, false, false, false).is_null());
+            assert!(ime_engine_key_with_char(null, keys::A, false, false, false, 0).is_null());
+            assert!(!ime_engine_quick_consonant(null, keys::F, true));
+            ime_engine_method(null, 1);
+            ime_engine_add_shortcut(null, trigger.as_ptr(), trigger.as_ptr());
+            assert_eq!(ime_engine_get_buffer(null, std::ptr::null_mut(), 8), 0);
+            assert_eq!(ime_engine_register_method(null, trigger.as_ptr()), -1);
+            assert_eq!(ime_engine_save_config(null, trigger.as_ptr()), -1);
+            assert!(!ime_engine_lexicon_add(null, trigger.as_ptr(), 0));
+            assert!(ime_engine_lexicon_exceptions(null).is_null());
+            assert!(ime_engine_completions(null, 3).is_null());
+            assert!(ime_engine_accept_completion(null, 0).is_null());
+            assert!(ime_engine_candidates(null).is_null());
+            assert!(ime_engine_commit_candidate(null, 0).is_null());
+            ime_engine_restore_word(null, trigger.as_ptr());
+            ime_engine_free(null);
+
+            // Null strings on a live handle
+            let e = ime_engine_new();
+            ime_engine_add_shortcut(e, std::ptr::null(), trigger.as_ptr());
+            assert_eq!(with_handle(e, |e| e.shortcuts().len()), Some(0));
+            ime_engine_free(e);
+        }
+    }
+
+    #[test]
+    fn test_text_ffi() {
+        // Pure text functions need no engine
+        let text = CString::new("Tiếng Việt có dấu, Đà Nẵng").unwrap();
+        unsafe {
+            let out = ime_remove_diacritics(text.as_ptr());
+            assert_eq!(
+                std::ffi::CStr::from_ptr(out).to_str().unwrap(),
+                "Tieng Viet co dau, Da Nang"
+            );
+            ime_free_string(out);
+
+            let out = ime_slugify(text.as_ptr(), std::ptr::null(), true, true);
+            assert_eq!(
+                std::ffi::CStr::from_ptr(out).to_str().unwrap(),
+                "tieng-viet-co-dau-da-nang"
+            );
+            ime_free_string(out);
+
+            let separator = CString::new("_").unwrap();
+            let out = ime_slugify(text.as_ptr(), separator.as_ptr(), false, true);
+            assert_eq!(
+                std::ffi::CStr::from_ptr(out).to_str().unwrap(),
+                "Tieng_Viet_co_dau_Da_Nang"
+            );
+            ime_free_string(out);
+
+            let plain = CString::new("khong biet").unwrap();
+            let out = ime_restore_diacritics(plain.as_ptr(), true);
+            assert_eq!(
+                std::ffi::CStr::from_ptr(out).to_str().unwrap(),
+                "không biết"
+            );
+            ime_free_string(out);
+
+            let plain = CString::new("toi di hoc").unwrap();
+            let out = ime_restore_alternatives(plain.as_ptr(), true);
+            let alternatives = std::ffi::CStr::from_ptr(out).to_str().unwrap();
+            let hoc = alternatives.lines().find(|l| l.starts_with("7\t10\t"));
+            assert!(hoc.is_some_and(|l| l.starts_with("7\t10\thọc\t")));
+            ime_free_string(out);
+
+            let word = CString::new("nghành").unwrap();
+            let out = ime_suggest(word.as_ptr(), true, 3);
+            let suggestions = std::ffi::CStr::from_ptr(out).to_str().unwrap();
+            assert_eq!(suggestions.lines().next(), Some("ngành"));
+            assert!(suggestions.lines().count() <= 3);
+            ime_free_string(out);
+
+            let styled = CString::new("hòa bình, khỏe").unwrap();
+            let mut changes = 0;
+            let out = ime_normalize_tone_style(styled.as_ptr(), true, &mut changes);
+            assert_eq!(
+                std::ffi::CStr::from_ptr(out).to_str().unwrap(),
+                "hoà bình, khoẻ"
+            );
+            assert_eq!(changes, 2);
+            ime_free_string(out);
+            let out = ime_normalize_tone_style(styled.as_ptr(), false, std::ptr::null_mut());
+            ime_free_string(out);
+
+            assert!(ime_remove_diacritics(std::ptr::null()).is_null());
+            ime_free_string(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_convert_case_ffi() {
+        let text = CString::new("tiếng việt của đồng bào").unwrap();
+        let cases = [
+            (0, "TIẾNG VIỆT CỦA ĐỒNG BÀO"),
+            (2, "Tiếng việt của đồng bào"),
+            (3, "Tiếng Việt Của Đồng Bào"),
+            (4, "Tiếng Việt của Đồng Bào"),
+        ];
+        unsafe {
+            for (mode, expected) in cases {
+                let out = ime_convert_case(text.as_ptr(), mode);
+                assert_eq!(std::ffi::CStr::from_ptr(out).to_str().unwrap(), expected);
+                ime_free_string(out);
+            }
+            assert!(ime_convert_case(text.as_ptr(), 42).is_null());
+        }
+    }
 }
@@ -29,6 +29,45 @@ struct ImeResult {
 // Verify struct size matches Rust at compile time
 static_assert(sizeof(ImeResult) == 1028, "ImeResult size mismatch with Rust core");
 
+// Key result with UTF-8 text - must match Utf8Result in crates/ffi/src/lib.rs
+// Filled in place by ime_key_utf8, nothing to free.
+struct ImeUtf8Result {
+    char text[1025];     // NUL-terminated UTF-8, 4 bytes per Rust MAX char + NUL
+    uint8_t action;      // offset 1025
+    uint8_t backspace;   // offset 1026
+    uint8_t flags;       // offset 1027
+    uint16_t len;        // offset 1028, bytes in text before the NUL
+};
+
+static_assert(sizeof(ImeUtf8Result) == 1030, "ImeUtf8Result size mismatch with Rust core");
+
+// Composition mode result - must match ComposeResult in crates/ffi/src/lib.rs
+// Apply in order: delete, commit, show preedit, forward the key if pass_through.
+struct ImeComposeResult {
+    char commit[2049];   // NUL-terminated UTF-8
+    char preedit[1025];  // NUL-terminated UTF-8, offset 2049
+    uint16_t commit_len; // offset 3074
+    uint16_t preedit_len;// offset 3076
+    uint16_t cursor;     // offset 3078, byte offset in preedit
+    uint16_t delete_;    // offset 3080, chars before the cursor to delete
+    uint8_t hint;        // offset 3082, 0 = underline, 1 = would be restored
+    bool pass_through;   // offset 3083
+};
+
+static_assert(sizeof(ImeComposeResult) == 3084, "ImeComposeResult size mismatch with Rust core");
+
+// Replace-range edit - must match EditResult in crates/ffi/src/lib.rs
+// Replace chars [cursor - start, cursor - end) with text, then forward the key if pass_through.
+struct ImeEditResult {
+    char text[1025];     // NUL-terminated UTF-8
+    uint16_t start;      // offset 1026 (after a padding byte)
+    uint16_t end;        // offset 1028
+    uint16_t len;        // offset 1030, bytes in text before the NUL
+    bool pass_through;   // offset 1032
+};
+
+static_assert(sizeof(ImeEditResult) == 1034, "ImeEditResult size mismatch with Rust core");
+
 // Action types
 enum class ImeAction : uint8_t {
     None = 0,    // Pass through
@@ -50,6 +89,38 @@ extern "C" {
     void ime_enabled(bool enabled);
     void ime_clear();
     void ime_free(ImeResult* result);
+    bool ime_key_utf8(uint16_t key, bool caps, bool ctrl, bool shift, uint32_t char_code, ImeUtf8Result* out);
+    bool ime_key_compose(uint16_t key, bool caps, bool ctrl, bool shift, uint32_t char_code, ImeComposeResult* out);
+    bool ime_finish_composition(ImeComposeResult* out);
+    void ime_set_surrounding_text(const char* before, const char* after);
+    bool ime_key_surrounding(uint16_t key, bool caps, bool ctrl, bool shift, uint32_t char_code, ImeEditResult* out);
+    void ime_trace(bool enabled);
+    char* ime_trace_json();
+    void ime_record(bool enabled);
+    char* ime_recording_text();
+    void ime_free_string(char* s);
+
+    // One engine per input context
+    struct ImeEngine;
+    ImeEngine* ime_engine_new();
+    void ime_engine_free(ImeEngine* engine);
+    ImeResult* ime_engine_key_ext(ImeEngine* engine, uint16_t key, bool caps, bool ctrl, bool shift);
+    bool ime_engine_key_utf8(ImeEngine* engine, uint16_t key, bool caps, bool ctrl, bool shift, uint32_t char_code, ImeUtf8Result* out);
+    bool ime_engine_key_compose(ImeEngine* engine, uint16_t key, bool caps, bool ctrl, bool shift, uint32_t char_code, ImeComposeResult* out);
+    bool ime_engine_finish_composition(ImeEngine* engine, ImeComposeResult* out);
+    void ime_engine_set_surrounding_text(ImeEngine* engine, const char* before, const char* after);
+    bool ime_engine_key_surrounding(ImeEngine* engine, uint16_t key, bool caps, bool ctrl, bool shift, uint32_t char_code, ImeEditResult* out);
+    void ime_engine_trace(ImeEngine* engine, bool enabled);
+    char* ime_engine_trace_json(ImeEngine* engine);
+    void ime_engine_record(ImeEngine* engine, bool enabled);
+    char* ime_engine_recording_text(ImeEngine* engine);
+    void ime_engine_method(ImeEngine* engine, uint8_t method);
+    void ime_engine_enabled(ImeEngine* engine, bool enabled);
+    void ime_engine_clear(ImeEngine* engine);
+    void ime_engine_clear_all(ImeEngine* engine);
+    void ime_engine_add_shortcut(ImeEngine* engine, const char* trigger, const char* replacement);
+    void ime_engine_remove_shortcut(ImeEngine* engine, const char* trigger);
+    void ime_engine_clear_shortcuts(ImeEngine* engine);
 }
 
 // C++ wrapper class for Rust bridge
@@ -76,9 +147,6 @@ public:
     // Clear the input buffer (call on word boundaries)
     static void clear();
 
-    // Convert UTF-32 codepoint to UTF-8 string (public for testing)
-    static std::string codePointToUtf8(uint32_t cp);
-
 private:
     static bool initialized_;
 };